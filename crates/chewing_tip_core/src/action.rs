@@ -0,0 +1,454 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2026 Kan-Ru Chen
+
+//! Actions triggered by keybindings.
+
+use std::fmt::Display;
+
+use chewing::editor::zhuyin_layout::KeyboardLayoutCompat;
+use error_plus::expect_error;
+
+use crate::{
+    config::ConfigError,
+    script::ScriptVariant,
+    shell::open_url,
+    template::{LocalTime, Locale, Template, TemplateContext},
+};
+
+/// What a keybinding does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Switches between Chinese and English
+    ToggleLanguage,
+    /// Switches between fullwidth and halfwidth
+    ToggleShape,
+    ToggleSimplifiedChinese,
+    /// Switches to the next Chinese script variant of the output
+    CycleOutputVariant,
+    /// Switches between the Hsu and the standard keyboard layout
+    ToggleHsuKeyboard,
+    /// Switches to the next zhuyin or pinyin keyboard layout
+    CycleKeyboardLayout,
+    /// Turns learning phrases from the committed text on or off
+    ToggleAutoLearn,
+    OpenUserPhraseEditor,
+    OpenPreferences,
+    /// Commits the keys typed since the composition started instead of
+    /// the converted text
+    CommitRawKeys,
+    /// Discards the composition
+    ClearBuffer,
+    /// Commits the composition followed by the expanded template
+    Text(Template),
+    /// Removes the candidate under the cursor from the user dictionary,
+    /// only while selecting candidates
+    UnlearnPhrase,
+    /// Shows the zhuyin readings of the candidate under the cursor, or of
+    /// the phrase committed last
+    ShowReadings,
+}
+
+impl Action {
+    /// Parses the action and parameter of a keybinding, e.g. `text` and `酷`.
+    pub fn parse(name: &str, param: &str) -> Result<Action, ConfigError> {
+        expect_error("Failed to parse keybinding action", || {
+            let action = match name.trim() {
+                "toggle_language" => Action::ToggleLanguage,
+                "toggle_shape" => Action::ToggleShape,
+                "toggle_simplified_chinese" => Action::ToggleSimplifiedChinese,
+                "cycle_output_variant" => Action::CycleOutputVariant,
+                "toggle_hsu_keyboard" => Action::ToggleHsuKeyboard,
+                "cycle_keyboard_layout" => Action::CycleKeyboardLayout,
+                "toggle_auto_learn" => Action::ToggleAutoLearn,
+                "open_user_phrase_editor" => Action::OpenUserPhraseEditor,
+                "open_preferences" => Action::OpenPreferences,
+                "commit_raw_keys" => Action::CommitRawKeys,
+                "clear_buffer" => Action::ClearBuffer,
+                "text" if param.is_empty() => return Err("text action without text".into()),
+                "text" => Action::Text(param.parse()?),
+                // Name before keybindings had scopes
+                "unlearn_phrase" | "selecting_unlearn_phrase" => Action::UnlearnPhrase,
+                "show_readings" => Action::ShowReadings,
+                other => return Err(format!("unknown action \"{other}\"").into()),
+            };
+            if !param.is_empty() && !matches!(action, Action::Text(_)) {
+                return Err(format!("action \"{name}\" does not take a parameter").into());
+            }
+            Ok(action)
+        })
+    }
+    /// Name of the action in the config.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::ToggleLanguage => "toggle_language",
+            Action::ToggleShape => "toggle_shape",
+            Action::ToggleSimplifiedChinese => "toggle_simplified_chinese",
+            Action::CycleOutputVariant => "cycle_output_variant",
+            Action::ToggleHsuKeyboard => "toggle_hsu_keyboard",
+            Action::CycleKeyboardLayout => "cycle_keyboard_layout",
+            Action::ToggleAutoLearn => "toggle_auto_learn",
+            Action::OpenUserPhraseEditor => "open_user_phrase_editor",
+            Action::OpenPreferences => "open_preferences",
+            Action::CommitRawKeys => "commit_raw_keys",
+            Action::ClearBuffer => "clear_buffer",
+            Action::Text(_) => "text",
+            Action::UnlearnPhrase => "unlearn_phrase",
+            Action::ShowReadings => "show_readings",
+        }
+    }
+    /// Parameter of the action in the config, empty if it has none.
+    pub fn param(&self) -> &str {
+        match self {
+            Action::Text(template) => template.source(),
+            _ => "",
+        }
+    }
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())?;
+        if !self.param().is_empty() {
+            write!(f, ":{}", self.param())?;
+        }
+        Ok(())
+    }
+}
+
+/// Carries out actions for a text service.
+///
+/// Implemented by both the TIP and the host so that keybindings behave the
+/// same in either.
+pub trait ActionHandler {
+    type Error;
+
+    fn toggle_language(&mut self) -> Result<(), Self::Error>;
+    fn toggle_shape(&mut self) -> Result<(), Self::Error>;
+    fn toggle_simplified_chinese(&mut self) -> Result<(), Self::Error>;
+    fn set_output_variant(&mut self, variant: ScriptVariant) -> Result<(), Self::Error>;
+    fn output_variant(&self) -> ScriptVariant;
+    fn set_keyboard_layout(&mut self, layout: KeyboardLayoutCompat) -> Result<(), Self::Error>;
+    fn keyboard_layout(&self) -> KeyboardLayoutCompat;
+    fn toggle_auto_learn(&mut self) -> Result<(), Self::Error>;
+    fn commit_raw_keys(&mut self) -> Result<(), Self::Error>;
+    fn clear_buffer(&mut self) -> Result<(), Self::Error>;
+    /// Commits the composition followed by `text`.
+    fn commit_text(&mut self, text: &str) -> Result<(), Self::Error>;
+    /// The phrase committed last, inserted by `{last}` in text templates.
+    fn last_commit(&self) -> &str;
+    /// What text templates are expanded with.
+    fn template_context(&self) -> TemplateContext<'_> {
+        TemplateContext {
+            now: LocalTime::now(),
+            locale: Locale::user_default(),
+            last_commit: self.last_commit(),
+        }
+    }
+    /// Returns `false` if no candidate is being selected, which is always
+    /// the case for handlers that do not track the candidate cursor.
+    fn unlearn_selected_phrase(&mut self) -> Result<bool, Self::Error> {
+        Ok(false)
+    }
+    /// Returns `false` if there is nothing to look up, or the handler has
+    /// nowhere to show the readings.
+    fn show_readings(&mut self) -> Result<bool, Self::Error> {
+        Ok(false)
+    }
+    fn open_user_phrase_editor(&mut self) {
+        open_url("chewing-editor://open");
+    }
+    fn open_preferences(&mut self) {
+        open_url("chewing-preferences://config");
+    }
+}
+
+/// Runs `action` and returns whether the key that triggered it was used.
+pub fn dispatch<H: ActionHandler>(handler: &mut H, action: &Action) -> Result<bool, H::Error> {
+    match action {
+        Action::ToggleLanguage => handler.toggle_language()?,
+        Action::ToggleShape => handler.toggle_shape()?,
+        Action::ToggleSimplifiedChinese => handler.toggle_simplified_chinese()?,
+        Action::CycleOutputVariant => {
+            let variant = handler.output_variant().next();
+            handler.set_output_variant(variant)?;
+        }
+        Action::ToggleHsuKeyboard => {
+            let layout = match handler.keyboard_layout() {
+                KeyboardLayoutCompat::Hsu => KeyboardLayoutCompat::Default,
+                _ => KeyboardLayoutCompat::Hsu,
+            };
+            handler.set_keyboard_layout(layout)?;
+        }
+        Action::CycleKeyboardLayout => {
+            let layout = next_keyboard_layout(handler.keyboard_layout());
+            handler.set_keyboard_layout(layout)?;
+        }
+        Action::ToggleAutoLearn => handler.toggle_auto_learn()?,
+        Action::OpenUserPhraseEditor => handler.open_user_phrase_editor(),
+        Action::OpenPreferences => handler.open_preferences(),
+        Action::CommitRawKeys => handler.commit_raw_keys()?,
+        Action::ClearBuffer => handler.clear_buffer()?,
+        Action::Text(template) => {
+            let text = template.expand(&handler.template_context());
+            handler.commit_text(&text)?;
+        }
+        Action::UnlearnPhrase => return handler.unlearn_selected_phrase(),
+        Action::ShowReadings => return handler.show_readings(),
+    }
+    Ok(true)
+}
+
+/// Keyboard layouts visited by [`Action::CycleKeyboardLayout`], in order.
+///
+/// Layouts that only remap the English keys share the syllable editor of
+/// another layout and are left out.
+const KEYBOARD_LAYOUT_CYCLE: [KeyboardLayoutCompat; 10] = [
+    KeyboardLayoutCompat::Default,
+    KeyboardLayoutCompat::Hsu,
+    KeyboardLayoutCompat::Ibm,
+    KeyboardLayoutCompat::GinYieh,
+    KeyboardLayoutCompat::Et,
+    KeyboardLayoutCompat::Et26,
+    KeyboardLayoutCompat::DachenCp26,
+    KeyboardLayoutCompat::HanyuPinyin,
+    KeyboardLayoutCompat::ThlPinyin,
+    KeyboardLayoutCompat::Mps2Pinyin,
+];
+
+/// The layout after `current`, the first one if `current` is not cycled.
+pub fn next_keyboard_layout(current: KeyboardLayoutCompat) -> KeyboardLayoutCompat {
+    let next = KEYBOARD_LAYOUT_CYCLE
+        .iter()
+        .position(|&layout| layout == current)
+        .map_or(0, |i| (i + 1) % KEYBOARD_LAYOUT_CYCLE.len());
+    KEYBOARD_LAYOUT_CYCLE[next]
+}
+
+/// Name of the layout shown when switching to it.
+pub fn keyboard_layout_name(layout: KeyboardLayoutCompat) -> &'static str {
+    match layout {
+        KeyboardLayoutCompat::Default => "標準鍵盤",
+        KeyboardLayoutCompat::Hsu => "許氏鍵盤",
+        KeyboardLayoutCompat::Ibm => "IBM 鍵盤",
+        KeyboardLayoutCompat::GinYieh => "精業鍵盤",
+        KeyboardLayoutCompat::Et => "倚天鍵盤",
+        KeyboardLayoutCompat::Et26 => "倚天 26 鍵",
+        KeyboardLayoutCompat::Dvorak => "Dvorak 鍵盤",
+        KeyboardLayoutCompat::DvorakHsu => "Dvorak 許氏鍵盤",
+        KeyboardLayoutCompat::DachenCp26 => "大千 26 鍵",
+        KeyboardLayoutCompat::HanyuPinyin => "漢語拼音",
+        KeyboardLayoutCompat::ThlPinyin => "台灣華語羅馬拼音",
+        KeyboardLayoutCompat::Mps2Pinyin => "注音二式",
+        KeyboardLayoutCompat::Carpalx => "Carpalx 鍵盤",
+        KeyboardLayoutCompat::ColemakDhAnsi => "Colemak-DH ANSI 鍵盤",
+        KeyboardLayoutCompat::ColemakDhOrth => "Colemak-DH 直列鍵盤",
+        KeyboardLayoutCompat::Workman => "Workman 鍵盤",
+        KeyboardLayoutCompat::Colemak => "Colemak 鍵盤",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chewing::editor::zhuyin_layout::KeyboardLayoutCompat;
+
+    use super::{Action, ActionHandler, dispatch, next_keyboard_layout};
+    use crate::{
+        script::ScriptVariant,
+        template::{LocalTime, Locale, TemplateContext},
+    };
+
+    fn text(template: &str) -> Action {
+        Action::Text(template.parse().unwrap())
+    }
+
+    #[test]
+    fn parse_round_trip() {
+        let actions = [
+            Action::ToggleLanguage,
+            Action::ToggleShape,
+            Action::ToggleSimplifiedChinese,
+            Action::CycleOutputVariant,
+            Action::ToggleHsuKeyboard,
+            Action::CycleKeyboardLayout,
+            Action::ToggleAutoLearn,
+            Action::OpenUserPhraseEditor,
+            Action::OpenPreferences,
+            Action::CommitRawKeys,
+            Action::ClearBuffer,
+            text("酷"),
+            Action::UnlearnPhrase,
+            Action::ShowReadings,
+        ];
+        for action in actions {
+            assert_eq!(
+                action,
+                Action::parse(action.name(), action.param()).unwrap()
+            );
+        }
+        assert_eq!("text:酷", text("酷").to_string());
+        assert_eq!(
+            Action::UnlearnPhrase,
+            Action::parse("selecting_unlearn_phrase", "").unwrap()
+        );
+        assert_eq!("clear_buffer", Action::ClearBuffer.to_string());
+    }
+
+    #[test]
+    fn invalid_actions() {
+        assert!(Action::parse("toggle_everything", "").is_err());
+        assert!(Action::parse("", "").is_err());
+        assert!(Action::parse("text", "").is_err());
+        assert!(Action::parse("clear_buffer", "all").is_err());
+        assert!(Action::parse("text", "{today}").is_err());
+    }
+
+    #[test]
+    fn cycle_keyboard_layouts() {
+        assert_eq!(
+            KeyboardLayoutCompat::Hsu,
+            next_keyboard_layout(KeyboardLayoutCompat::Default)
+        );
+        assert_eq!(
+            KeyboardLayoutCompat::Default,
+            next_keyboard_layout(KeyboardLayoutCompat::Mps2Pinyin)
+        );
+        assert_eq!(
+            KeyboardLayoutCompat::Default,
+            next_keyboard_layout(KeyboardLayoutCompat::Dvorak)
+        );
+    }
+
+    #[derive(Default)]
+    struct Recorder {
+        layout: Option<KeyboardLayoutCompat>,
+        variant: ScriptVariant,
+        calls: Vec<String>,
+        selecting: bool,
+    }
+
+    impl ActionHandler for Recorder {
+        type Error = ();
+
+        fn toggle_language(&mut self) -> Result<(), ()> {
+            self.calls.push("toggle_language".to_string());
+            Ok(())
+        }
+        fn toggle_shape(&mut self) -> Result<(), ()> {
+            self.calls.push("toggle_shape".to_string());
+            Ok(())
+        }
+        fn toggle_simplified_chinese(&mut self) -> Result<(), ()> {
+            self.calls.push("toggle_simplified_chinese".to_string());
+            Ok(())
+        }
+        fn set_output_variant(&mut self, variant: ScriptVariant) -> Result<(), ()> {
+            self.variant = variant;
+            Ok(())
+        }
+        fn output_variant(&self) -> ScriptVariant {
+            self.variant
+        }
+        fn set_keyboard_layout(&mut self, layout: KeyboardLayoutCompat) -> Result<(), ()> {
+            self.layout = Some(layout);
+            Ok(())
+        }
+        fn keyboard_layout(&self) -> KeyboardLayoutCompat {
+            self.layout.unwrap_or(KeyboardLayoutCompat::Default)
+        }
+        fn toggle_auto_learn(&mut self) -> Result<(), ()> {
+            self.calls.push("toggle_auto_learn".to_string());
+            Ok(())
+        }
+        fn commit_raw_keys(&mut self) -> Result<(), ()> {
+            self.calls.push("commit_raw_keys".to_string());
+            Ok(())
+        }
+        fn clear_buffer(&mut self) -> Result<(), ()> {
+            self.calls.push("clear_buffer".to_string());
+            Ok(())
+        }
+        fn commit_text(&mut self, text: &str) -> Result<(), ()> {
+            self.calls.push(format!("commit_text {text}"));
+            Ok(())
+        }
+        fn last_commit(&self) -> &str {
+            "酷音"
+        }
+        fn template_context(&self) -> TemplateContext<'_> {
+            TemplateContext {
+                now: LocalTime {
+                    year: 2026,
+                    month: 10,
+                    day: 17,
+                    weekday: 6,
+                    hour: 9,
+                    minute: 30,
+                    second: 0,
+                },
+                locale: &Locale::ZH_TW,
+                last_commit: self.last_commit(),
+            }
+        }
+        fn unlearn_selected_phrase(&mut self) -> Result<bool, ()> {
+            Ok(self.selecting)
+        }
+        fn show_readings(&mut self) -> Result<bool, ()> {
+            self.calls.push("show_readings".to_string());
+            Ok(true)
+        }
+        fn open_user_phrase_editor(&mut self) {
+            self.calls.push("open_user_phrase_editor".to_string());
+        }
+        fn open_preferences(&mut self) {
+            self.calls.push("open_preferences".to_string());
+        }
+    }
+
+    #[test]
+    fn dispatch_to_handler() {
+        let mut handler = Recorder::default();
+        assert_eq!(Ok(true), dispatch(&mut handler, &Action::ClearBuffer));
+        assert_eq!(Ok(true), dispatch(&mut handler, &text("酷")));
+        assert_eq!(Ok(true), dispatch(&mut handler, &Action::OpenPreferences));
+        assert_eq!(
+            vec!["clear_buffer", "commit_text 酷", "open_preferences"],
+            handler.calls
+        );
+    }
+
+    #[test]
+    fn dispatch_text_template() {
+        let mut handler = Recorder::default();
+        dispatch(&mut handler, &text("民國{date:%R年%-m月%-d日} {last}")).unwrap();
+        assert_eq!(vec!["commit_text 民國115年10月17日 酷音"], handler.calls);
+    }
+
+    #[test]
+    fn dispatch_keyboard_layouts() {
+        let mut handler = Recorder::default();
+        dispatch(&mut handler, &Action::ToggleHsuKeyboard).unwrap();
+        assert_eq!(Some(KeyboardLayoutCompat::Hsu), handler.layout);
+        dispatch(&mut handler, &Action::ToggleHsuKeyboard).unwrap();
+        assert_eq!(Some(KeyboardLayoutCompat::Default), handler.layout);
+        dispatch(&mut handler, &Action::CycleKeyboardLayout).unwrap();
+        dispatch(&mut handler, &Action::CycleKeyboardLayout).unwrap();
+        assert_eq!(Some(KeyboardLayoutCompat::Ibm), handler.layout);
+    }
+
+    #[test]
+    fn dispatch_output_variants() {
+        let mut handler = Recorder::default();
+        dispatch(&mut handler, &Action::CycleOutputVariant).unwrap();
+        assert_eq!(ScriptVariant::Simplified, handler.variant);
+        dispatch(&mut handler, &Action::CycleOutputVariant).unwrap();
+        assert_eq!(ScriptVariant::HongKong, handler.variant);
+    }
+
+    #[test]
+    fn unlearn_only_while_selecting() {
+        let mut handler = Recorder::default();
+        assert_eq!(Ok(false), dispatch(&mut handler, &Action::UnlearnPhrase));
+        handler.selecting = true;
+        assert_eq!(Ok(true), dispatch(&mut handler, &Action::UnlearnPhrase));
+    }
+}
@@ -42,10 +42,20 @@ pub struct Config {
 pub struct ChewingTsfConfig {
     pub switch_lang_with_shift: bool,
     pub shift_key_sensitivity: i32,
+    /// Key switching the language while `switch_lang_with_shift` is set,
+    /// one of `Shift`, `LShift`, `RShift`, `Ctrl`, `CapsLock` or `Ctrl+Space`
+    pub lang_toggle_key: String,
+    /// Milliseconds after typing another key during which the language
+    /// toggle key is ignored, 0 to always accept it
+    pub lang_toggle_guard: i32,
     pub enable_fullwidth_toggle_key: bool,
     pub enable_caps_lock: bool,
     pub lock_chinese_on_caps_lock: bool,
     pub show_notification: bool,
+    /// Milliseconds mode change and info notifications stay visible
+    pub notification_duration: i32,
+    /// Show the input mode next to the caret on focus and mode changes
+    pub show_mode_indicator: bool,
     pub enable_auto_learn: bool,
     pub esc_clean_all_buf: bool,
     pub full_shape_symbols: bool,
@@ -60,13 +70,34 @@ pub struct ChewingTsfConfig {
     pub advance_after_selection: bool,
     pub default_full_space: bool,
     pub default_english: bool,
+    /// Offer to commit the keys typed in Chinese mode as English when they
+    /// look like an English word
+    pub detect_english: bool,
+    /// Key committing the keys as English while `detect_english` offers it,
+    /// empty to only show the hint
+    pub english_convert_key: String,
     pub output_simp_chinese: bool,
+    /// Chinese script variant of the output, one of `zh-TW`, `zh-CN`,
+    /// `zh-HK` or `zh-Hans-TW`. Empty to follow `output_simp_chinese`
+    pub output_variant: String,
     pub sel_key_type: i32,
     pub conv_engine: i32,
     pub cand_per_row: i32,
     pub cand_per_page: i32,
+    pub cand_list_layout: i32,
+    pub show_page_arrows: bool,
+    pub show_cand_title: bool,
+    pub compact_cand_list: bool,
+    pub show_cand_annotations: bool,
+    pub floating_composition: bool,
+    /// Executable names that always use the floating composition window,
+    /// separated by `;`
+    pub floating_composition_apps: String,
     pub font_size: i32,
     pub font_family: String,
+    /// Font families tried in order when `font_family` lacks a glyph,
+    /// separated by `;`
+    pub font_fallback: String,
     pub font_fg_color: String,
     pub font_bg_color: String,
     pub font_highlight_fg_color: String,
@@ -76,12 +107,22 @@ pub struct ChewingTsfConfig {
     pub notify_fg_color: String,
     pub notify_bg_color: String,
     pub notify_border_color: String,
+    pub theme: String,
     pub keyboard_layout: i32,
     pub simulate_english_layout: i32,
     pub sync_lang_mode_openclose: bool,
     pub keybind: Vec<KeybindValue>,
     pub auto_check_update_channel: String,
     pub update_info_url: String,
+    pub update_feed_url: String,
+    pub update_proxy: String,
+    pub update_timeout: i32,
+    pub update_check_interval: i32,
+    pub update_check_jitter: i32,
+    pub skip_update_on_metered_network: bool,
+    pub update_skip_version: String,
+    pub update_snooze_until: u64,
+    pub update_pin_major: i32,
     pub last_update_check_time: u64,
     pub modified_timestamp: u64,
 }
@@ -91,10 +132,14 @@ impl Default for ChewingTsfConfig {
         Self {
             switch_lang_with_shift: true,
             shift_key_sensitivity: 200,
+            lang_toggle_key: "Shift".to_owned(),
+            lang_toggle_guard: 0,
             enable_fullwidth_toggle_key: false,
             enable_caps_lock: false,
             lock_chinese_on_caps_lock: true,
             show_notification: true,
+            notification_duration: 500,
+            show_mode_indicator: false,
             enable_auto_learn: true,
             esc_clean_all_buf: false,
             full_shape_symbols: true,
@@ -109,13 +154,24 @@ impl Default for ChewingTsfConfig {
             advance_after_selection: true,
             default_full_space: false,
             default_english: false,
+            detect_english: false,
+            english_convert_key: "Tab".to_owned(),
             output_simp_chinese: false,
+            output_variant: "".to_string(),
             sel_key_type: 0,
             conv_engine: 1,
             cand_per_row: 3,
             cand_per_page: 9,
+            cand_list_layout: 0,
+            show_page_arrows: false,
+            show_cand_title: false,
+            compact_cand_list: false,
+            show_cand_annotations: false,
+            floating_composition: false,
+            floating_composition_apps: "".to_string(),
             font_size: 16,
             font_family: "Segoe UI".to_owned(),
+            font_fallback: "".to_string(),
             font_fg_color: "000000FF".to_owned(),
             font_bg_color: "FAFAFAFF".to_owned(),
             font_highlight_fg_color: "FFFFFFFF".to_owned(),
@@ -125,29 +181,65 @@ impl Default for ChewingTsfConfig {
             notify_fg_color: "000000FF".to_owned(),
             notify_bg_color: "FCFBDAFF".to_owned(),
             notify_border_color: "D6D9DBFF".to_owned(),
+            theme: "custom".to_owned(),
             keyboard_layout: 0,
             simulate_english_layout: 0,
             sync_lang_mode_openclose: false,
             keybind: vec![
                 KeybindValue {
                     key: "Ctrl+F12".to_string(),
+                    when: "".to_string(),
                     action: "toggle_simplified_chinese".to_string(),
                     param: "".to_string(),
                 },
                 KeybindValue {
                     key: "Ctrl+Delete".to_string(),
-                    action: "selecting_unlearn_phrase".to_string(),
+                    when: "selecting".to_string(),
+                    action: "unlearn_phrase".to_string(),
                     param: "".to_string(),
                 },
             ],
             auto_check_update_channel: "stable".to_string(),
             update_info_url: "".to_string(),
+            update_feed_url: "".to_string(),
+            update_proxy: "".to_string(),
+            update_timeout: 30,
+            update_check_interval: 24,
+            update_check_jitter: 60,
+            skip_update_on_metered_network: true,
+            update_skip_version: "".to_string(),
+            update_snooze_until: 0,
+            update_pin_major: 0,
             last_update_check_time: 0,
             modified_timestamp: 0,
         }
     }
 }
 
+impl ChewingTsfConfig {
+    /// Whether the preedit of `exe_name` is shown in a floating window
+    /// instead of inline in the document.
+    pub fn use_floating_composition(&self, exe_name: &str) -> bool {
+        self.floating_composition
+            || self
+                .floating_composition_apps
+                .split(';')
+                .map(str::trim)
+                .any(|app| !app.is_empty() && app.eq_ignore_ascii_case(exe_name))
+    }
+    /// Fallback font families in order, without duplicates.
+    pub fn font_fallback_families(&self) -> Vec<String> {
+        let mut families: Vec<String> = vec![];
+        for family in self.font_fallback.split(';').map(str::trim) {
+            let duplicate = families.iter().any(|f| f.eq_ignore_ascii_case(family));
+            if !family.is_empty() && !duplicate && !family.eq_ignore_ascii_case(&self.font_family) {
+                families.push(family.to_string());
+            }
+        }
+        families
+    }
+}
+
 impl Config {
     pub fn reload_if_needed(&mut self) -> Result<bool, ConfigError> {
         let cfg = Config::from_reg()?;
@@ -191,6 +283,12 @@ impl Config {
             if let Ok(value) = reg_get_bool(&key, "DefaultEnglish") {
                 cfg.default_english = value;
             }
+            if let Ok(value) = reg_get_bool(&key, "DetectEnglish") {
+                cfg.detect_english = value;
+            }
+            if let Ok(value) = key.get_string("EnglishConvertKey") {
+                cfg.english_convert_key = value;
+            }
             if let Ok(value) = reg_get_bool(&key, "DefaultFullSpace") {
                 cfg.default_full_space = value;
             }
@@ -203,12 +301,27 @@ impl Config {
             if let Ok(value) = reg_get_i32(&key, "ShiftKeySensitivity") {
                 cfg.shift_key_sensitivity = value;
             }
+            if let Ok(value) = key.get_string("LangToggleKey") {
+                cfg.lang_toggle_key = value;
+            }
+            if let Ok(value) = reg_get_i32(&key, "LangToggleGuard") {
+                cfg.lang_toggle_guard = value;
+            }
             if let Ok(value) = reg_get_bool(&key, "ShowNotification") {
                 cfg.show_notification = value;
             }
+            if let Ok(value) = reg_get_i32(&key, "NotificationDuration") {
+                cfg.notification_duration = value;
+            }
+            if let Ok(value) = reg_get_bool(&key, "ShowModeIndicator") {
+                cfg.show_mode_indicator = value;
+            }
             if let Ok(value) = reg_get_bool(&key, "OutputSimpChinese") {
                 cfg.output_simp_chinese = value;
             }
+            if let Ok(value) = key.get_string("OutputVariant") {
+                cfg.output_variant = value;
+            }
             if let Ok(value) = reg_get_bool(&key, "AddPhraseForward") {
                 cfg.add_phrase_forward = value;
             }
@@ -224,6 +337,9 @@ impl Config {
             if let Ok(value) = key.get_string("DefFontFamily") {
                 cfg.font_family = value;
             }
+            if let Ok(value) = key.get_string("FontFallback") {
+                cfg.font_fallback = value;
+            }
             if let Ok(value) = key.get_string("DefFontFgColor") {
                 cfg.font_fg_color = value;
             }
@@ -251,6 +367,9 @@ impl Config {
             if let Ok(value) = key.get_string("DefNotifyBorderColor") {
                 cfg.notify_border_color = value;
             }
+            if let Ok(value) = key.get_string("Theme") {
+                cfg.theme = value;
+            }
             if let Ok(value) = reg_get_i32(&key, "SelKeyType") {
                 cfg.sel_key_type = value;
             }
@@ -263,6 +382,27 @@ impl Config {
             if let Ok(value) = reg_get_bool(&key, "CursorCandList") {
                 cfg.cursor_cand_list = value;
             }
+            if let Ok(value) = reg_get_i32(&key, "CandListLayout") {
+                cfg.cand_list_layout = value;
+            }
+            if let Ok(value) = reg_get_bool(&key, "ShowPageArrows") {
+                cfg.show_page_arrows = value;
+            }
+            if let Ok(value) = reg_get_bool(&key, "ShowCandTitle") {
+                cfg.show_cand_title = value;
+            }
+            if let Ok(value) = reg_get_bool(&key, "CompactCandList") {
+                cfg.compact_cand_list = value;
+            }
+            if let Ok(value) = reg_get_bool(&key, "ShowCandAnnotations") {
+                cfg.show_cand_annotations = value;
+            }
+            if let Ok(value) = reg_get_bool(&key, "FloatingComposition") {
+                cfg.floating_composition = value;
+            }
+            if let Ok(value) = key.get_string("FloatingCompositionApps") {
+                cfg.floating_composition_apps = value;
+            }
             if let Ok(value) = reg_get_bool(&key, "SortCandidatesByFrequency") {
                 cfg.sort_candidates_by_frequency = value;
             }
@@ -299,6 +439,33 @@ impl Config {
             if let Ok(value) = key.get_string("UpdateInfoUrl") {
                 cfg.update_info_url = value;
             }
+            if let Ok(value) = key.get_string("UpdateFeedUrl") {
+                cfg.update_feed_url = value;
+            }
+            if let Ok(value) = key.get_string("UpdateProxy") {
+                cfg.update_proxy = value;
+            }
+            if let Ok(value) = reg_get_i32(&key, "UpdateTimeout") {
+                cfg.update_timeout = value;
+            }
+            if let Ok(value) = reg_get_i32(&key, "UpdateCheckInterval") {
+                cfg.update_check_interval = value;
+            }
+            if let Ok(value) = reg_get_i32(&key, "UpdateCheckJitter") {
+                cfg.update_check_jitter = value;
+            }
+            if let Ok(value) = reg_get_bool(&key, "SkipUpdateOnMeteredNetwork") {
+                cfg.skip_update_on_metered_network = value;
+            }
+            if let Ok(value) = key.get_string("UpdateSkipVersion") {
+                cfg.update_skip_version = value;
+            }
+            if let Ok(value) = key.get_u64("UpdateSnoozeUntil") {
+                cfg.update_snooze_until = value;
+            }
+            if let Ok(value) = reg_get_i32(&key, "UpdatePinMajor") {
+                cfg.update_pin_major = value;
+            }
             if let Ok(value) = key.get_u64("LastUpdateCheckTime") {
                 cfg.last_update_check_time = value;
             }
@@ -346,6 +513,8 @@ impl Config {
         );
         let _ = reg_set_i32(&key, "CandPerRow", chewing_tsf.cand_per_row);
         let _ = reg_set_bool(&key, "DefaultEnglish", chewing_tsf.default_english);
+        let _ = reg_set_bool(&key, "DetectEnglish", chewing_tsf.detect_english);
+        let _ = key.set_string("EnglishConvertKey", &chewing_tsf.english_convert_key);
         let _ = reg_set_bool(&key, "DefaultFullSpace", chewing_tsf.default_full_space);
         let _ = reg_set_bool(
             &key,
@@ -362,13 +531,22 @@ impl Config {
             "ShiftKeySensitivity",
             chewing_tsf.shift_key_sensitivity,
         );
+        let _ = key.set_string("LangToggleKey", &chewing_tsf.lang_toggle_key);
+        let _ = reg_set_i32(&key, "LangToggleGuard", chewing_tsf.lang_toggle_guard);
         let _ = reg_set_bool(
             &key,
             "EnableFullwidthToggleKey",
             chewing_tsf.enable_fullwidth_toggle_key,
         );
         let _ = reg_set_bool(&key, "ShowNotification", chewing_tsf.show_notification);
+        let _ = reg_set_i32(
+            &key,
+            "NotificationDuration",
+            chewing_tsf.notification_duration,
+        );
+        let _ = reg_set_bool(&key, "ShowModeIndicator", chewing_tsf.show_mode_indicator);
         let _ = reg_set_bool(&key, "OutputSimpChinese", chewing_tsf.output_simp_chinese);
+        let _ = key.set_string("OutputVariant", &chewing_tsf.output_variant);
         let _ = reg_set_bool(&key, "AddPhraseForward", chewing_tsf.add_phrase_forward);
         let _ = reg_set_bool(
             &key,
@@ -382,6 +560,7 @@ impl Config {
         );
         let _ = reg_set_i32(&key, "DefFontSize", chewing_tsf.font_size);
         let _ = key.set_string("DefFontFamily", &chewing_tsf.font_family);
+        let _ = key.set_string("FontFallback", &chewing_tsf.font_fallback);
         let _ = key.set_string("DefFontFgColor", &chewing_tsf.font_fg_color);
         let _ = key.set_string("DefFontBgColor", &chewing_tsf.font_bg_color);
         let _ = key.set_string(
@@ -400,10 +579,29 @@ impl Config {
         let _ = key.set_string("DefNotifyFgColor", &chewing_tsf.notify_fg_color);
         let _ = key.set_string("DefNotifyBgColor", &chewing_tsf.notify_bg_color);
         let _ = key.set_string("DefNotifyBorderColor", &chewing_tsf.notify_border_color);
+        let _ = key.set_string("Theme", &chewing_tsf.theme);
         let _ = reg_set_i32(&key, "SelKeyType", chewing_tsf.sel_key_type);
         let _ = reg_set_i32(&key, "ConvEngine", chewing_tsf.conv_engine);
         let _ = reg_set_i32(&key, "SelAreaLen", chewing_tsf.cand_per_page);
         let _ = reg_set_bool(&key, "CursorCandList", chewing_tsf.cursor_cand_list);
+        let _ = reg_set_i32(&key, "CandListLayout", chewing_tsf.cand_list_layout);
+        let _ = reg_set_bool(&key, "ShowPageArrows", chewing_tsf.show_page_arrows);
+        let _ = reg_set_bool(&key, "ShowCandTitle", chewing_tsf.show_cand_title);
+        let _ = reg_set_bool(&key, "CompactCandList", chewing_tsf.compact_cand_list);
+        let _ = reg_set_bool(
+            &key,
+            "ShowCandAnnotations",
+            chewing_tsf.show_cand_annotations,
+        );
+        let _ = reg_set_bool(
+            &key,
+            "FloatingComposition",
+            chewing_tsf.floating_composition,
+        );
+        let _ = key.set_string(
+            "FloatingCompositionApps",
+            &chewing_tsf.floating_composition_apps,
+        );
         let _ = reg_set_bool(
             &key,
             "SortCandidatesByFrequency",
@@ -437,6 +635,21 @@ impl Config {
             "AutoCheckUpdateChannel",
             &chewing_tsf.auto_check_update_channel,
         );
+        let _ = key.set_string("UpdateFeedUrl", &chewing_tsf.update_feed_url);
+        let _ = key.set_string("UpdateProxy", &chewing_tsf.update_proxy);
+        let _ = reg_set_i32(&key, "UpdateTimeout", chewing_tsf.update_timeout);
+        let _ = reg_set_i32(
+            &key,
+            "UpdateCheckInterval",
+            chewing_tsf.update_check_interval,
+        );
+        let _ = reg_set_i32(&key, "UpdateCheckJitter", chewing_tsf.update_check_jitter);
+        let _ = reg_set_bool(
+            &key,
+            "SkipUpdateOnMeteredNetwork",
+            chewing_tsf.skip_update_on_metered_network,
+        );
+        let _ = reg_set_i32(&key, "UpdatePinMajor", chewing_tsf.update_pin_major);
         let _ = key.set_multi_string(
             "Keybind".to_string(),
             chewing_tsf
@@ -469,6 +682,10 @@ impl Config {
 #[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct KeybindValue {
     pub key: String,
+    /// Comma separated input states where the keybinding applies, empty
+    /// for all of them
+    #[serde(default)]
+    pub when: String,
     pub action: String,
     pub param: String,
 }
@@ -478,14 +695,32 @@ impl FromStr for KeybindValue {
 
     fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
         expect_error("Failed to parse keybinding", || {
-            let (key, action) = s.rsplit_once('=').ok_or("missing seperator =")?;
-            let (action, param) = if action.contains(':') {
-                action.rsplit_once(':').ok_or("missing seperator :")?
-            } else {
-                (action, "")
+            // Keys and text parameters may have `=` too, the action name
+            // after the seperator has only lowercase letters and `_`
+            let (key, action) = s
+                .match_indices('=')
+                .map(|(i, _)| (&s[..i], &s[i + 1..]))
+                .find(|(key, action)| {
+                    let name = action.split(':').next().unwrap_or_default();
+                    !key.is_empty()
+                        && !name.is_empty()
+                        && name.chars().all(|c| c.is_ascii_lowercase() || c == '_')
+                })
+                .ok_or("missing seperator =")?;
+            // `@` is also a key, scopes are only lowercase names
+            let (key, when) = match key.rsplit_once('@') {
+                Some((key, when))
+                    if !when.is_empty()
+                        && when.chars().all(|c| c.is_ascii_lowercase() || c == ',') =>
+                {
+                    (key, when)
+                }
+                _ => (key, ""),
             };
+            let (action, param) = action.split_once(':').unwrap_or((action, ""));
             Ok(KeybindValue {
                 key: key.to_string(),
+                when: when.to_string(),
                 action: action.to_string(),
                 param: param.to_string(),
             })
@@ -495,7 +730,11 @@ impl FromStr for KeybindValue {
 
 impl Display for KeybindValue {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}={}", self.key.trim(), self.action.trim())?;
+        write!(f, "{}", self.key.trim())?;
+        if !self.when.is_empty() {
+            write!(f, "@{}", self.when.trim())?;
+        }
+        write!(f, "={}", self.action.trim())?;
         if !self.param.is_empty() {
             write!(f, ":{}", self.param)?;
         }
@@ -634,7 +873,7 @@ impl_context_error!(pub ConfigError);
 
 #[cfg(test)]
 mod test {
-    use crate::config::KeybindValue;
+    use crate::config::{ChewingTsfConfig, KeybindValue};
 
     #[test]
     fn parse_keybind_action() {
@@ -658,4 +897,55 @@ mod test {
         let value: KeybindValue = keybind.parse().unwrap();
         assert_eq!(keybind, value.to_string());
     }
+    #[test]
+    fn parse_keybind_template() {
+        let keybind = "Ctrl+Alt+D=text:民國{date:%R年%-m月%-d日}";
+        let value: KeybindValue = keybind.parse().unwrap();
+        assert_eq!(value.key, "Ctrl+Alt+D");
+        assert_eq!(value.action, "text");
+        assert_eq!(value.param, "民國{date:%R年%-m月%-d日}");
+        assert_eq!(keybind, value.to_string());
+
+        let value: KeybindValue = "Ctrl+==text:a=b".parse().unwrap();
+        assert_eq!(value.key, "Ctrl+=");
+        assert_eq!(value.action, "text");
+        assert_eq!(value.param, "a=b");
+    }
+    #[test]
+    fn parse_keybind_scopes() {
+        let keybind = "Ctrl+Delete@selecting=unlearn_phrase";
+        let value: KeybindValue = keybind.parse().unwrap();
+        assert_eq!(value.key, "Ctrl+Delete");
+        assert_eq!(value.when, "selecting");
+        assert_eq!(value.action, "unlearn_phrase");
+        assert_eq!(keybind, value.to_string());
+
+        let value: KeybindValue = "Ctrl+Shift+@=text:@".parse().unwrap();
+        assert_eq!(value.key, "Ctrl+Shift+@");
+        assert_eq!(value.when, "");
+    }
+    #[test]
+    fn floating_composition_apps() {
+        let mut cfg = ChewingTsfConfig {
+            floating_composition_apps: "mintty.exe; Game.EXE;".to_string(),
+            ..Default::default()
+        };
+        assert!(cfg.use_floating_composition("game.exe"));
+        assert!(cfg.use_floating_composition("mintty.exe"));
+        assert!(!cfg.use_floating_composition("notepad.exe"));
+        assert!(!cfg.use_floating_composition(""));
+        cfg.floating_composition = true;
+        assert!(cfg.use_floating_composition("notepad.exe"));
+    }
+    #[test]
+    fn font_fallback_families() {
+        let mut cfg = ChewingTsfConfig::default();
+        assert!(cfg.font_fallback_families().is_empty());
+        cfg.font_fallback = " Segoe UI Emoji;MingLiU-ExtB;;segoe ui emoji; Segoe UI ".to_string();
+        // The primary font is not tried twice
+        assert_eq!(
+            vec!["Segoe UI Emoji", "MingLiU-ExtB"],
+            cfg.font_fallback_families()
+        );
+    }
 }
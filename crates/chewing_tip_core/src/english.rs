@@ -0,0 +1,196 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2026 Kan-Ru Chen
+
+//! Spotting English typed by mistake in Chinese mode.
+//!
+//! The keys typed since the composition started are checked after each key.
+//! They look like English when they spell a word in the word list, or when
+//! the keyboard layout rejected some of them as zhuyin. The keys are then
+//! committed as English with the `EnglishConvertKey` setting, Tab by default.
+//! The bundled word list can be tuned with `english_words.txt` under the user
+//! dir:
+//!
+//! ```text
+//! # Words to add
+//! kubectl
+//! # Bundled words to remove
+//! !for
+//! ```
+
+use std::{collections::HashSet, io::ErrorKind};
+
+use error_plus::{expect_error, impl_context_error};
+
+use crate::{
+    keybind::{KeyCombo, Trigger},
+    shell::user_dir,
+};
+
+const BUNDLED_WORDS: &str = include_str!("english_words.txt");
+
+/// Shorter key sequences are too likely to be zhuyin.
+pub const MIN_WORD_LEN: usize = 3;
+
+/// Keys rejected by the keyboard layout before the keys count as English
+/// without being in the word list.
+pub const MIN_REJECTED_KEYS: usize = 2;
+
+/// Why the keys look like English.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnglishMatch {
+    /// The keys spell a word in the word list
+    Word,
+    /// The keys do not form valid zhuyin syllables
+    InvalidSyllables,
+}
+
+/// Words recognized as English, in lower case.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EnglishWords {
+    words: HashSet<String>,
+}
+
+impl EnglishWords {
+    /// The word list shipped with the input method.
+    pub fn bundled() -> EnglishWords {
+        let mut words = EnglishWords::default();
+        words.apply(BUNDLED_WORDS);
+        words
+    }
+    /// The bundled word list tuned by the user word list, if any.
+    pub fn load() -> Result<EnglishWords, EnglishError> {
+        expect_error("Unable to load English word list", || {
+            let mut words = EnglishWords::bundled();
+            match std::fs::read_to_string(user_dir()?.join("english_words.txt")) {
+                Ok(list) => words.apply(&list),
+                Err(error) if error.kind() == ErrorKind::NotFound => {}
+                Err(error) => return Err(error.into()),
+            }
+            Ok(words)
+        })
+    }
+    /// Applies a word list with one word per line. Words starting with `!`
+    /// are removed and lines starting with `#` are comments.
+    pub fn apply(&mut self, list: &str) {
+        for line in list.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.strip_prefix('!') {
+                Some(word) => self.words.remove(&word.trim().to_lowercase()),
+                None => self.words.insert(line.to_lowercase()),
+            };
+        }
+    }
+    pub fn contains(&self, word: &str) -> bool {
+        self.words.contains(&word.to_lowercase())
+    }
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+    /// Checks the keys typed since the composition started, `rejected_keys`
+    /// of which the keyboard layout could not use as zhuyin.
+    pub fn detect(&self, raw_keys: &str, rejected_keys: usize) -> Option<EnglishMatch> {
+        let mut chars = raw_keys.chars();
+        let starts_with_letter = chars.next().is_some_and(|ch| ch.is_ascii_alphabetic());
+        if raw_keys.len() < MIN_WORD_LEN
+            || !starts_with_letter
+            || !chars.all(|ch| ch.is_ascii_alphanumeric())
+        {
+            return None;
+        }
+        if self.contains(raw_keys) {
+            Some(EnglishMatch::Word)
+        } else if rejected_keys >= MIN_REJECTED_KEYS {
+            Some(EnglishMatch::InvalidSyllables)
+        } else {
+            None
+        }
+    }
+}
+
+/// Parses the `EnglishConvertKey` setting, a single key combination. Empty
+/// to never take over a key.
+pub fn parse_convert_key(s: &str) -> Result<Option<KeyCombo>, EnglishError> {
+    expect_error("Failed to parse English convert key", || {
+        if s.trim().is_empty() {
+            return Ok(None);
+        }
+        match s.parse::<Trigger>()? {
+            Trigger::Sequence(combos) if combos.len() == 1 => Ok(Some(combos[0])),
+            _ => Err(format!("\"{s}\" is not a single key combination").into()),
+        }
+    })
+}
+
+impl_context_error!(pub EnglishError);
+
+#[cfg(test)]
+mod tests {
+    use chewing::input::keysym::{SYM_F12, SYM_TAB};
+
+    use super::{EnglishMatch, EnglishWords, parse_convert_key};
+    use crate::keybind::{KeyCombo, Modifiers};
+
+    #[test]
+    fn bundled_words() {
+        let words = EnglishWords::bundled();
+        assert!(words.len() > 100);
+        assert!(words.contains("commit"));
+        assert!(words.contains("GitHub"));
+        assert!(!words.contains("# English words offered for conversion"));
+    }
+
+    #[test]
+    fn tune_word_list() {
+        let mut words = EnglishWords::bundled();
+        words.apply("# comment\n\nKubectl\n  !for \n!notaword\n");
+        assert!(words.contains("kubectl"));
+        assert!(!words.contains("for"));
+        assert!(words.contains("commit"));
+    }
+
+    #[test]
+    fn detect_english() {
+        let mut words = EnglishWords::default();
+        words.apply("hello\nutf8");
+        assert_eq!(Some(EnglishMatch::Word), words.detect("Hello", 0));
+        assert_eq!(Some(EnglishMatch::Word), words.detect("utf8", 0));
+        assert_eq!(
+            Some(EnglishMatch::InvalidSyllables),
+            words.detect("kubectl", 2)
+        );
+        // Zhuyin keys
+        assert_eq!(None, words.detect("ji3cl3", 0));
+        assert_eq!(None, words.detect("ji3cl3", 1));
+        // Too short, or not like a word
+        assert_eq!(None, words.detect("hi", 2));
+        assert_eq!(None, words.detect("3su", 2));
+        assert_eq!(None, words.detect("su;3", 2));
+    }
+
+    #[test]
+    fn convert_key() {
+        assert_eq!(
+            Some(KeyCombo {
+                ksym: SYM_TAB,
+                modifiers: Modifiers::NONE,
+            }),
+            parse_convert_key("Tab").unwrap()
+        );
+        assert_eq!(
+            Some(KeyCombo {
+                ksym: SYM_F12,
+                modifiers: Modifiers::CTRL,
+            }),
+            parse_convert_key("Ctrl+F12").unwrap()
+        );
+        assert_eq!(None, parse_convert_key("").unwrap());
+        assert!(parse_convert_key("Ctrl+K Ctrl+E").is_err());
+        assert!(parse_convert_key("Shift").is_err());
+        assert!(parse_convert_key("NotAKey").is_err());
+    }
+}
@@ -11,15 +11,23 @@ use interprocess::{
     os::windows::named_pipe::{DuplexPipeStream, pipe_mode::Bytes},
 };
 
-use crate::ipc::{
-    messages::{Ping, PingReply},
-    named_pipe::{connect_and_attest, named_pipe_path},
-    varlink::{MethodCall, MethodReply},
+use crate::{
+    ipc::{
+        messages::{
+            ListCoveringFonts, ListCoveringFontsReply, LookupReadings, LookupReadingsReply, Ping,
+            PingReply, SkipUpdate, SnoozeUpdate,
+        },
+        named_pipe::{connect_and_attest, named_pipe_path},
+        varlink::{MethodCall, MethodReply},
+    },
+    reading::Readings,
 };
 
 #[derive(Clone, Default)]
 pub struct ChewingIpcClient {
     pipe: Rc<RefCell<Option<DuplexPipeStream<Bytes>>>>,
+    // Kept across calls so streamed replies buffered together are not lost
+    reader: Rc<RefCell<Option<BufReader<DuplexPipeStream<Bytes>>>>>,
 }
 
 impl ChewingIpcClient {
@@ -30,6 +38,7 @@ impl ChewingIpcClient {
         expect_error("Unable to connect to chewing_tip_host", || {
             let pipe_path = named_pipe_path()?;
             let pipe = connect_and_attest(&pipe_path, Duration::from_millis(100))?;
+            self.reader.replace(Some(BufReader::new(pipe.try_clone()?)));
             self.pipe.replace(Some(pipe));
             Ok(())
         })
@@ -50,15 +59,18 @@ impl ChewingIpcClient {
                     error: None,
                 });
             }
+            self.receive()
+        })
+    }
+    /// Reads the next reply, used for calls with `more` set.
+    pub fn receive(&self) -> Result<MethodReply, IpcOpError> {
+        expect_error("Failed to receive IPC reply", || {
             let mut buffer = vec![];
-            let mut reader = BufReader::new(
-                self.pipe
-                    .try_borrow()?
-                    .as_ref()
-                    .ok_or("Broken Pipe")?
-                    .try_clone()?,
-            );
-            reader.read_until(0, &mut buffer)?;
+            self.reader
+                .try_borrow_mut()?
+                .as_mut()
+                .ok_or("Broken Pipe")?
+                .read_until(0, &mut buffer)?;
             if buffer.last().is_none_or(|b| *b != 0) {
                 log::debug!("EOF - server exited");
                 return Err("EOF - server exited".into());
@@ -80,6 +92,64 @@ impl ChewingIpcClient {
             Ok(params.uuid)
         })
     }
+    /// Installed font families that can display all of `text`.
+    pub fn covering_fonts(&self, text: &str) -> Result<Vec<String>, IpcOpError> {
+        expect_error("Cannot list covering fonts", || {
+            let reply = self.send(MethodCall {
+                method: ListCoveringFonts::METHOD.to_string(),
+                parameters: serde_json::to_value(ListCoveringFonts {
+                    text: text.to_string(),
+                })?,
+                oneway: Some(false),
+                more: Some(false),
+                upgrade: Some(false),
+            })?;
+            let params: ListCoveringFontsReply = serde_json::from_value(reply.parameters)?;
+            Ok(params.families)
+        })
+    }
+    /// Zhuyin readings of `text` in the dictionaries loaded by the server.
+    pub fn lookup_readings(&self, text: &str) -> Result<Vec<Readings>, IpcOpError> {
+        expect_error("Cannot look up readings", || {
+            let reply = self.send(MethodCall {
+                method: LookupReadings::METHOD.to_string(),
+                parameters: serde_json::to_value(LookupReadings {
+                    text: text.to_string(),
+                })?,
+                oneway: Some(false),
+                more: Some(false),
+                upgrade: Some(false),
+            })?;
+            let params: LookupReadingsReply = serde_json::from_value(reply.parameters)?;
+            Ok(params.readings)
+        })
+    }
+    /// Stops notifying about the release currently offered.
+    pub fn skip_update(&self) -> Result<(), IpcOpError> {
+        expect_error("Cannot skip update", || {
+            self.send(MethodCall {
+                method: SkipUpdate::METHOD.to_string(),
+                parameters: serde_json::to_value(SkipUpdate)?,
+                oneway: Some(true),
+                more: None,
+                upgrade: None,
+            })?;
+            Ok(())
+        })
+    }
+    /// Suppresses update notifications for the next `days` days.
+    pub fn snooze_update(&self, days: u32) -> Result<(), IpcOpError> {
+        expect_error("Cannot snooze update", || {
+            self.send(MethodCall {
+                method: SnoozeUpdate::METHOD.to_string(),
+                parameters: serde_json::to_value(SnoozeUpdate { days })?,
+                oneway: Some(true),
+                more: None,
+                upgrade: None,
+            })?;
+            Ok(())
+        })
+    }
 }
 
 impl Drop for ChewingIpcClient {
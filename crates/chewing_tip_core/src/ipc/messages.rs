@@ -1,8 +1,14 @@
+use std::time::Duration;
+
 use serde::{Deserialize, Serialize};
 
-use crate::ipc::values::{CandidateList, Composition, IpcKeyEvent, IpcShiftKeyState};
+use crate::ipc::values::{
+    CandidateAnnotation, CandidateEvent, CandidateLayoutMode, CandidateList, Composition,
+    IpcKeyEvent, NotificationKind,
+};
 
-use super::values::Position;
+use super::values::{Position, ScreenRect};
+use crate::reading::Readings;
 
 #[derive(Debug, Default, Deserialize, Serialize)]
 pub struct Ping {
@@ -37,16 +43,47 @@ pub struct ShowNotification {
     pub fg_color: String,
     pub bg_color: String,
     pub border_color: String,
+    #[serde(default)]
+    pub kind: NotificationKind,
+    /// Milliseconds to show the notification, `Some(0)` to keep it until
+    /// dismissed and `None` to use the default of `kind`
+    #[serde(default)]
+    pub duration_ms: Option<u32>,
+    /// Shown before the text, falls back to the icon of `kind` when empty
+    #[serde(default)]
+    pub icon: String,
 }
 pub type ShowNotificationReply = ();
 impl ShowNotification {
     pub const METHOD: &str = "im.chewing.ui.ShowNotification";
+
+    /// Returns `None` if the notification stays until dismissed.
+    pub fn duration(&self) -> Option<Duration> {
+        match self.duration_ms {
+            Some(0) => None,
+            Some(ms) => Some(Duration::from_millis(ms.into())),
+            None => self.kind.default_duration(),
+        }
+    }
+    pub fn icon(&self) -> &str {
+        if self.icon.is_empty() {
+            self.kind.default_icon()
+        } else {
+            &self.icon
+        }
+    }
 }
 
 #[derive(Debug, Default, Deserialize, Serialize)]
 pub struct ShowCandidateList {
     pub position: Position,
     pub items: Vec<String>,
+    /// Annotations of `items` by index
+    ///
+    /// Kept apart from `items` so hosts that predate annotations can still
+    /// read the message. May be shorter than `items` or empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<CandidateAnnotation>,
     pub selkeys: Vec<u16>,
     pub total_page: u32,
     pub current_page: u32,
@@ -61,10 +98,46 @@ pub struct ShowCandidateList {
     pub highlight_fg_color: String,
     pub highlight_bg_color: String,
     pub border_color: String,
+    /// Falls back to `fg_color` when empty
+    #[serde(default)]
+    pub page_indicator_color: String,
+    #[serde(default)]
+    pub layout: CandidateLayoutMode,
+    /// Show clickable previous and next page arrows
+    #[serde(default)]
+    pub show_page_arrows: bool,
+    /// Header above the candidates, hidden when empty
+    #[serde(default)]
+    pub title: String,
+    /// Use less spacing around the candidates
+    #[serde(default)]
+    pub compact: bool,
+    /// Session that receives mouse events from the candidate window
+    #[serde(default)]
+    pub session: u64,
+    /// Caret to place the window around, `position` is used when absent
+    #[serde(default)]
+    pub caret: Option<ScreenRect>,
+    /// Font families tried before the system fallback
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub font_fallback: Vec<String>,
+    /// Locale of the text, such as `zh-TW` or `zh-CN`, `zh-TW` when empty
+    #[serde(default)]
+    pub locale: String,
 }
 pub type ShowCandidateListReply = ();
 impl ShowCandidateList {
     pub const METHOD: &str = "im.chewing.ui.ShowCandidateList";
+
+    /// Number of candidates in each row after applying `layout`.
+    pub fn columns(&self) -> usize {
+        match self.layout {
+            CandidateLayoutMode::Grid => self.cand_per_row as usize,
+            CandidateLayoutMode::Vertical => 1,
+            CandidateLayoutMode::Horizontal => self.items.len(),
+        }
+        .max(1)
+    }
 }
 
 #[derive(Debug, Default, Deserialize, Serialize)]
@@ -74,6 +147,81 @@ impl HideCandidateList {
     pub const METHOD: &str = "im.chewing.ui.HideCandidateList";
 }
 
+/// Shows the preedit in a floating window near the caret.
+///
+/// Used instead of inline composition for applications with poor TSF
+/// support. Offsets are counted in chars of `preedit`.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct ShowComposition {
+    pub position: Position,
+    pub preedit: String,
+    pub segments: Vec<(usize, usize)>,
+    /// Range of the unfinished zhuyin syllable
+    pub bopomofo: Option<(usize, usize)>,
+    pub cursor: usize,
+    pub font_family: String,
+    pub font_size: f32,
+    pub fg_color: String,
+    pub bg_color: String,
+    pub highlight_bg_color: String,
+    pub border_color: String,
+    /// Caret to place the window around, `position` is used when absent
+    #[serde(default)]
+    pub caret: Option<ScreenRect>,
+    /// Font families tried before the system fallback
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub font_fallback: Vec<String>,
+    /// Locale of the text, such as `zh-TW` or `zh-CN`, `zh-TW` when empty
+    #[serde(default)]
+    pub locale: String,
+}
+pub type ShowCompositionReply = ();
+impl ShowComposition {
+    pub const METHOD: &str = "im.chewing.ui.ShowComposition";
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct HideComposition;
+pub type HideCompositionReply = ();
+impl HideComposition {
+    pub const METHOD: &str = "im.chewing.ui.HideComposition";
+}
+
+/// Streams [`CandidateEvent`]s of the candidate window shown by `session`.
+///
+/// Must be called with `more` set. The connection is used exclusively for the
+/// event stream until the session is unwatched.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct WatchCandidateEvents {
+    pub session: u64,
+}
+pub type WatchCandidateEventsReply = CandidateEvent;
+impl WatchCandidateEvents {
+    pub const METHOD: &str = "im.chewing.ui.WatchCandidateEvents";
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct UnwatchCandidateEvents {
+    pub session: u64,
+}
+pub type UnwatchCandidateEventsReply = ();
+impl UnwatchCandidateEvents {
+    pub const METHOD: &str = "im.chewing.ui.UnwatchCandidateEvents";
+}
+
+/// Lists the installed font families with glyphs for all of `text`.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct ListCoveringFonts {
+    pub text: String,
+}
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct ListCoveringFontsReply {
+    pub families: Vec<String>,
+}
+impl ListCoveringFonts {
+    pub const METHOD: &str = "im.chewing.ui.ListCoveringFonts";
+}
+
 #[derive(Debug, Default, Deserialize, Serialize)]
 pub struct Stop;
 pub type StopReply = ();
@@ -88,11 +236,26 @@ impl CheckUpdate {
     pub const METHOD: &str = "im.chewing.ui.CheckUpdate";
 }
 
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct SkipUpdate;
+pub type SkipUpdateReply = ();
+impl SkipUpdate {
+    pub const METHOD: &str = "im.chewing.ui.SkipUpdate";
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct SnoozeUpdate {
+    pub days: u32,
+}
+pub type SnoozeUpdateReply = ();
+impl SnoozeUpdate {
+    pub const METHOD: &str = "im.chewing.ui.SnoozeUpdate";
+}
+
 #[derive(Debug, Default, Deserialize, Serialize)]
 pub struct OnTestKeyDown {
     pub is_context_mutable: bool,
     pub is_composing: bool,
-    pub shift_key_state: IpcShiftKeyState,
     pub event: IpcKeyEvent,
 }
 #[derive(Debug, Default, Deserialize, Serialize)]
@@ -144,3 +307,132 @@ pub struct OnKeyUpReply {
 impl OnKeyUp {
     pub const METHOD: &str = "im.chewing.tip.OnKeyUp";
 }
+
+/// Looks up the zhuyin readings of `text` in the loaded dictionaries.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct LookupReadings {
+    pub text: String,
+}
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct LookupReadingsReply {
+    pub readings: Vec<Readings>,
+}
+impl LookupReadings {
+    pub const METHOD: &str = "im.chewing.tip.LookupReadings";
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use super::{ShowCandidateList, ShowNotification};
+    use crate::ipc::values::{CandidateAnnotation, NotificationKind};
+
+    #[test]
+    fn show_candidate_list_without_annotations() {
+        let params: ShowCandidateList = serde_json::from_value(json!({
+            "position": { "x": 0, "y": 0 },
+            "items": ["測", "策"],
+            "selkeys": [49, 50],
+            "total_page": 1,
+            "current_page": 1,
+            "font_family": "Segoe UI",
+            "font_size": 16.0,
+            "cand_per_row": 3,
+            "use_cursor": true,
+            "current_sel": 0,
+            "selkey_color": "0000FFFF",
+            "fg_color": "000000FF",
+            "bg_color": "FAFAFAFF",
+            "highlight_fg_color": "FFFFFFFF",
+            "highlight_bg_color": "000000FF",
+            "border_color": "D6D9DBFF"
+        }))
+        .unwrap();
+        assert_eq!(vec!["測", "策"], params.items);
+        assert!(params.annotations.is_empty());
+        assert_eq!(None, params.caret);
+        assert!(params.font_fallback.is_empty());
+        assert_eq!("", params.locale);
+    }
+
+    #[test]
+    fn empty_annotation_fields_are_omitted() {
+        let params = ShowCandidateList {
+            items: vec!["測".to_string(), "★".to_string()],
+            annotations: vec![
+                CandidateAnnotation {
+                    reading: "ㄘㄜˋ".to_string(),
+                    user_phrase: true,
+                    frequency: Some(3),
+                    ..Default::default()
+                },
+                CandidateAnnotation {
+                    gloss: "黑星".to_string(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let value = serde_json::to_value(&params).unwrap();
+        assert_eq!(
+            json!([
+                { "reading": "ㄘㄜˋ", "user_phrase": true, "frequency": 3 },
+                { "gloss": "黑星" }
+            ]),
+            value["annotations"]
+        );
+        let round_trip: ShowCandidateList = serde_json::from_value(value).unwrap();
+        assert_eq!(params.annotations, round_trip.annotations);
+    }
+
+    #[test]
+    fn no_annotations_are_not_serialized() {
+        let value = serde_json::to_value(ShowCandidateList::default()).unwrap();
+        assert!(value.get("annotations").is_none());
+    }
+
+    #[test]
+    fn show_notification_from_old_client() {
+        let params: ShowNotification = serde_json::from_value(json!({
+            "position": { "x": 0, "y": 0 },
+            "text": "中文模式",
+            "font_family": "Segoe UI",
+            "font_size": 16.0,
+            "fg_color": "000000FF",
+            "bg_color": "FCFBDAFF",
+            "border_color": "D6D9DBFF"
+        }))
+        .unwrap();
+        assert_eq!(NotificationKind::Info, params.kind);
+        assert_eq!(Some(Duration::from_millis(500)), params.duration());
+        assert_eq!("", params.icon());
+    }
+
+    #[test]
+    fn notification_duration() {
+        let mut params = ShowNotification {
+            kind: NotificationKind::Error,
+            ..Default::default()
+        };
+        assert_eq!(None, params.duration());
+        params.duration_ms = Some(2000);
+        assert_eq!(Some(Duration::from_secs(2)), params.duration());
+        params.kind = NotificationKind::ModeChange;
+        params.duration_ms = Some(0);
+        assert_eq!(None, params.duration());
+    }
+
+    #[test]
+    fn notification_icon() {
+        let mut params = ShowNotification {
+            kind: NotificationKind::Warning,
+            ..Default::default()
+        };
+        assert_eq!("⚠", params.icon());
+        params.icon = "🔔".to_string();
+        assert_eq!("🔔", params.icon());
+    }
+}
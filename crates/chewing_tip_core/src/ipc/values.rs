@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use base64_serde::base64_serde_type;
 use serde::{Deserialize, Serialize};
 
@@ -9,12 +11,13 @@ pub struct Position {
     pub y: i32,
 }
 
-#[derive(Debug, Default, Deserialize, Serialize)]
-pub enum IpcShiftKeyState {
-    Down,
-    Consumed,
-    #[default]
-    Up,
+/// A rectangle in screen coordinates, such as the caret.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ScreenRect {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
 }
 
 #[derive(Debug, Default, Deserialize, Serialize)]
@@ -41,3 +44,94 @@ pub struct CandidateList {
     pub total_page: u32,
     pub current_page: u32,
 }
+
+/// Optional information shown next to a candidate.
+///
+/// Empty fields are omitted from the serialized form.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CandidateAnnotation {
+    /// Bopomofo reading, e.g. "ㄘㄜˋ ㄕˋ"
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub reading: String,
+    /// Learned by the user and stored in chewing.dat
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub user_phrase: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frequency: Option<u32>,
+    /// Short description of a symbol
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub gloss: String,
+}
+
+impl CandidateAnnotation {
+    pub fn is_empty(&self) -> bool {
+        self == &CandidateAnnotation::default()
+    }
+}
+
+/// Arrangement of the candidates in the candidate window.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum CandidateLayoutMode {
+    /// Rows of `cand_per_row` candidates
+    #[default]
+    Grid,
+    /// One candidate per row
+    Vertical,
+    /// All candidates in a single row
+    Horizontal,
+}
+
+impl From<i32> for CandidateLayoutMode {
+    fn from(value: i32) -> Self {
+        match value {
+            1 => CandidateLayoutMode::Vertical,
+            2 => CandidateLayoutMode::Horizontal,
+            _ => CandidateLayoutMode::Grid,
+        }
+    }
+}
+
+/// Severity and purpose of a notification.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum NotificationKind {
+    ModeChange,
+    /// Compact input mode shown next to the caret
+    ModeIndicator,
+    #[default]
+    Info,
+    Warning,
+    Error,
+    UpdateAvailable,
+}
+
+impl NotificationKind {
+    /// How long a notification of this kind is shown if not specified.
+    ///
+    /// Returns `None` for notifications that stay until dismissed.
+    pub fn default_duration(self) -> Option<Duration> {
+        match self {
+            NotificationKind::ModeChange
+            | NotificationKind::ModeIndicator
+            | NotificationKind::Info => Some(Duration::from_millis(500)),
+            NotificationKind::Warning => Some(Duration::from_secs(3)),
+            NotificationKind::Error | NotificationKind::UpdateAvailable => None,
+        }
+    }
+    pub fn default_icon(self) -> &'static str {
+        match self {
+            NotificationKind::ModeChange
+            | NotificationKind::ModeIndicator
+            | NotificationKind::Info => "",
+            NotificationKind::Warning => "⚠",
+            NotificationKind::Error => "✖",
+            NotificationKind::UpdateAvailable => "⬆",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "event")]
+pub enum CandidateEvent {
+    CandidateClicked { index: u32 },
+    CandidatePageRequested { delta: i32 },
+}
@@ -0,0 +1,1442 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2026 Kan-Ru Chen
+
+//! Keybindings and matching key presses against them.
+//!
+//! A keybinding is triggered either by a sequence of key combinations, like
+//! `Ctrl+K` or `Ctrl+; D`, or by tapping a lone modifier key, like `RCtrl`.
+//! It can be limited to some input states with scopes, like
+//! `Ctrl+Delete@selecting`.
+
+use std::{
+    fmt::{self, Display},
+    ops::BitOr,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use chewing::input::keysym::*;
+use error_plus::expect_error;
+
+use crate::{
+    action::Action,
+    config::{ChewingTsfConfig, ConfigError, KeybindValue},
+    lang_toggle::{LangToggle, ToggleKey},
+};
+
+/// How long to wait for the next key of a sequence
+pub const SEQUENCE_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// Modifier keys held down, telling left and right apart.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Modifiers(u8);
+
+impl Modifiers {
+    pub const NONE: Modifiers = Modifiers(0);
+    pub const LEFT_CTRL: Modifiers = Modifiers(1 << 0);
+    pub const RIGHT_CTRL: Modifiers = Modifiers(1 << 1);
+    pub const LEFT_SHIFT: Modifiers = Modifiers(1 << 2);
+    pub const RIGHT_SHIFT: Modifiers = Modifiers(1 << 3);
+    pub const LEFT_ALT: Modifiers = Modifiers(1 << 4);
+    pub const RIGHT_ALT: Modifiers = Modifiers(1 << 5);
+    pub const LEFT_SUPER: Modifiers = Modifiers(1 << 6);
+    pub const RIGHT_SUPER: Modifiers = Modifiers(1 << 7);
+    /// Either Ctrl key
+    pub const CTRL: Modifiers = Modifiers(0b11);
+    /// Either Shift key
+    pub const SHIFT: Modifiers = Modifiers(0b11 << 2);
+    /// Either Alt key
+    pub const ALT: Modifiers = Modifiers(0b11 << 4);
+    /// Either Super key
+    pub const SUPER: Modifiers = Modifiers(0b11 << 6);
+
+    const KINDS: [Modifiers; 4] = [
+        Modifiers::CTRL,
+        Modifiers::SHIFT,
+        Modifiers::ALT,
+        Modifiers::SUPER,
+    ];
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+    pub fn contains(self, other: Modifiers) -> bool {
+        self.0 & other.0 == other.0
+    }
+    fn has_any_of(self, other: Modifiers) -> bool {
+        self.0 & other.0 != 0
+    }
+    fn intersection(self, other: Modifiers) -> Modifiers {
+        Modifiers(self.0 & other.0)
+    }
+    fn difference(self, other: Modifiers) -> Modifiers {
+        Modifiers(self.0 & !other.0)
+    }
+    /// The modifier set by pressing `ksym`, empty if it is not a modifier key.
+    pub fn of_key(ksym: Keysym) -> Modifiers {
+        match ksym {
+            SYM_LEFTCTRL => Modifiers::LEFT_CTRL,
+            SYM_RIGHTCTRL => Modifiers::RIGHT_CTRL,
+            SYM_LEFTSHIFT => Modifiers::LEFT_SHIFT,
+            SYM_RIGHTSHIFT => Modifiers::RIGHT_SHIFT,
+            SYM_LEFTALT => Modifiers::LEFT_ALT,
+            SYM_RIGHTALT => Modifiers::RIGHT_ALT,
+            SYM_LEFTMETA => Modifiers::LEFT_SUPER,
+            SYM_RIGHTMETA => Modifiers::RIGHT_SUPER,
+            _ => Modifiers::NONE,
+        }
+    }
+    /// Whether `held` satisfies these modifiers of a keybinding.
+    ///
+    /// A modifier without a side, like `Ctrl`, accepts either key. A modifier
+    /// with a side, like `RCtrl`, accepts only that key. Modifiers not in the
+    /// keybinding must not be held.
+    fn accepts(self, held: Modifiers) -> bool {
+        Modifiers::KINDS.iter().all(|&kind| {
+            let want = self.intersection(kind);
+            let held = held.intersection(kind);
+            if want == kind {
+                !held.is_empty()
+            } else {
+                want == held
+            }
+        })
+    }
+}
+
+impl Display for Modifiers {
+    /// Writes the modifiers joined with `+`, e.g. `Ctrl+RShift`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kinds = [
+            (
+                Modifiers::LEFT_CTRL,
+                Modifiers::RIGHT_CTRL,
+                "Ctrl",
+                "LCtrl",
+                "RCtrl",
+            ),
+            (
+                Modifiers::LEFT_SHIFT,
+                Modifiers::RIGHT_SHIFT,
+                "Shift",
+                "LShift",
+                "RShift",
+            ),
+            (
+                Modifiers::LEFT_ALT,
+                Modifiers::RIGHT_ALT,
+                "Alt",
+                "LAlt",
+                "RAlt",
+            ),
+            (
+                Modifiers::LEFT_SUPER,
+                Modifiers::RIGHT_SUPER,
+                "Super",
+                "LSuper",
+                "RSuper",
+            ),
+        ];
+        let mut sep = "";
+        for (left, right, either, left_name, right_name) in kinds {
+            let name = match (self.contains(left), self.contains(right)) {
+                (true, true) => either,
+                (true, false) => left_name,
+                (false, true) => right_name,
+                (false, false) => continue,
+            };
+            write!(f, "{sep}{name}")?;
+            sep = "+";
+        }
+        Ok(())
+    }
+}
+
+impl BitOr for Modifiers {
+    type Output = Modifiers;
+
+    fn bitor(self, rhs: Modifiers) -> Modifiers {
+        Modifiers(self.0 | rhs.0)
+    }
+}
+
+/// A key press as seen by the matcher.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyPress {
+    pub ksym: Keysym,
+    /// Modifiers held down, including the key itself if it is a modifier
+    pub modifiers: Modifiers,
+}
+
+/// What the editor is doing when a key is pressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditState {
+    Idle,
+    /// Has a preedit but no candidate list
+    Composing,
+    /// Has a candidate list
+    Selecting,
+}
+
+/// The state of the text service when a key is pressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputState {
+    pub edit: EditState,
+    pub english: bool,
+}
+
+/// Input states where a keybinding applies.
+///
+/// Edit states and languages are independent, `composing,english` applies
+/// only while composing in English mode. No scope of a kind means any.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Scopes(u8);
+
+impl Scopes {
+    pub const ANY: Scopes = Scopes(0);
+    pub const IDLE: Scopes = Scopes(1 << 0);
+    pub const COMPOSING: Scopes = Scopes(1 << 1);
+    pub const SELECTING: Scopes = Scopes(1 << 2);
+    pub const ENGLISH: Scopes = Scopes(1 << 3);
+    pub const CHINESE: Scopes = Scopes(1 << 4);
+
+    const EDIT: Scopes = Scopes(0b111);
+    const LANGUAGE: Scopes = Scopes(0b11 << 3);
+
+    pub fn is_any(self) -> bool {
+        self.0 == 0
+    }
+    pub fn contains(self, other: Scopes) -> bool {
+        self.0 & other.0 == other.0
+    }
+    fn has_any_of(self, kind: Scopes) -> bool {
+        self.0 & kind.0 != 0
+    }
+    /// Whether some input state is allowed by both.
+    fn overlaps(self, other: Scopes) -> bool {
+        let axis = |scopes: Scopes, kind: Scopes| {
+            if scopes.has_any_of(kind) {
+                scopes.0 & kind.0
+            } else {
+                kind.0
+            }
+        };
+        [Scopes::EDIT, Scopes::LANGUAGE]
+            .into_iter()
+            .all(|kind| axis(self, kind) & axis(other, kind) != 0)
+    }
+    /// Whether a keybinding with these scopes applies in `state`.
+    pub fn allows(self, state: InputState) -> bool {
+        let edit = match state.edit {
+            EditState::Idle => Scopes::IDLE,
+            EditState::Composing => Scopes::COMPOSING,
+            EditState::Selecting => Scopes::SELECTING,
+        };
+        let language = if state.english {
+            Scopes::ENGLISH
+        } else {
+            Scopes::CHINESE
+        };
+        (!self.has_any_of(Scopes::EDIT) || self.contains(edit))
+            && (!self.has_any_of(Scopes::LANGUAGE) || self.contains(language))
+    }
+    /// Whether the keybinding was meant for the editor states, so it takes
+    /// priority over the keys the editor uses there.
+    fn overrides_editor(self) -> bool {
+        self.has_any_of(Scopes::COMPOSING | Scopes::SELECTING)
+    }
+}
+
+impl BitOr for Scopes {
+    type Output = Scopes;
+
+    fn bitor(self, rhs: Scopes) -> Scopes {
+        Scopes(self.0 | rhs.0)
+    }
+}
+
+const SCOPE_NAMES: [(Scopes, &str); 5] = [
+    (Scopes::IDLE, "idle"),
+    (Scopes::COMPOSING, "composing"),
+    (Scopes::SELECTING, "selecting"),
+    (Scopes::ENGLISH, "english"),
+    (Scopes::CHINESE, "chinese"),
+];
+
+impl FromStr for Scopes {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        expect_error("Failed to parse keybinding scopes", || {
+            let mut scopes = Scopes::ANY;
+            for name in s.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+                let (scope, _) = SCOPE_NAMES
+                    .iter()
+                    .find(|(_, scope_name)| *scope_name == name)
+                    .ok_or_else(|| format!("unknown scope \"{name}\""))?;
+                scopes = scopes | *scope;
+            }
+            Ok(scopes)
+        })
+    }
+}
+
+impl Display for Scopes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut sep = "";
+        for (scope, name) in SCOPE_NAMES {
+            if self.contains(scope) {
+                write!(f, "{sep}{name}")?;
+                sep = ",";
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether the editor uses the key while composing or selecting, e.g.
+/// Ctrl+digit adds a user phrase and arrows move the cursor.
+fn is_editor_key(press: &KeyPress) -> bool {
+    let modifiers = press.modifiers;
+    if modifiers.has_any_of(Modifiers::ALT | Modifiers::SUPER) {
+        return false;
+    }
+    if modifiers.has_any_of(Modifiers::CTRL) {
+        return press.ksym.is_digit();
+    }
+    press.ksym.is_unicode()
+        || matches!(
+            press.ksym,
+            SYM_RETURN
+                | SYM_ESC
+                | SYM_BACKSPACE
+                | SYM_DELETE
+                | SYM_TAB
+                | SYM_LEFT
+                | SYM_RIGHT
+                | SYM_UP
+                | SYM_DOWN
+                | SYM_HOME
+                | SYM_END
+                | SYM_PAGEUP
+                | SYM_PAGEDOWN
+        )
+}
+
+/// A key and the modifiers held with it, one step of a sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyCombo {
+    /// `SYM_NONE` matches any key
+    pub ksym: Keysym,
+    pub modifiers: Modifiers,
+}
+
+impl KeyCombo {
+    pub fn matches(&self, press: &KeyPress) -> bool {
+        (self.ksym == press.ksym || self.ksym == SYM_NONE)
+            && self.modifiers.accepts(press.modifiers)
+    }
+}
+
+impl Display for KeyCombo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.modifiers)?;
+        if self.ksym != SYM_NONE {
+            if !self.modifiers.is_empty() {
+                f.write_str("+")?;
+            }
+            fmt_keysym(self.ksym, f)?;
+        }
+        Ok(())
+    }
+}
+
+/// What triggers a keybinding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Trigger {
+    /// Key combinations pressed one after another
+    Sequence(Vec<KeyCombo>),
+    /// A modifier key pressed and released without any other key
+    Tap(Modifiers),
+}
+
+impl FromStr for Trigger {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        expect_error("Failed to parse key combination", || {
+            let steps = split_steps(s);
+            if let [step] = steps.as_slice()
+                && let Some(modifiers) = modifier_from_str(step)
+            {
+                return Ok(Trigger::Tap(modifiers));
+            }
+            let combos = steps
+                .iter()
+                .map(|step| combo_from_str(step))
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| format!("unable to parse key combination \"{s}\""))?;
+            if combos.is_empty() {
+                return Err("empty key combination".into());
+            }
+            Ok(Trigger::Sequence(combos))
+        })
+    }
+}
+
+impl Display for Trigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Trigger::Sequence(combos) => {
+                for (i, combo) in combos.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(" ")?;
+                    }
+                    write!(f, "{combo}")?;
+                }
+                Ok(())
+            }
+            Trigger::Tap(modifiers) => write!(f, "{modifiers}"),
+        }
+    }
+}
+
+/// Splits a sequence at whitespace, keeping `Ctrl + K` in one step.
+fn split_steps(s: &str) -> Vec<String> {
+    let mut steps: Vec<String> = vec![];
+    for token in s.split_whitespace() {
+        match steps.last_mut() {
+            Some(last) if last.ends_with('+') || token.starts_with('+') => last.push_str(token),
+            _ => steps.push(token.to_string()),
+        }
+    }
+    steps
+}
+
+fn modifier_from_str(s: &str) -> Option<Modifiers> {
+    let modifiers = match s.trim().to_lowercase().as_str() {
+        "ctrl" | "control" => Modifiers::CTRL,
+        "lctrl" | "leftctrl" => Modifiers::LEFT_CTRL,
+        "rctrl" | "rightctrl" => Modifiers::RIGHT_CTRL,
+        "shift" => Modifiers::SHIFT,
+        "lshift" | "leftshift" => Modifiers::LEFT_SHIFT,
+        "rshift" | "rightshift" => Modifiers::RIGHT_SHIFT,
+        "alt" | "opt" | "option" => Modifiers::ALT,
+        "lalt" | "leftalt" => Modifiers::LEFT_ALT,
+        "ralt" | "rightalt" => Modifiers::RIGHT_ALT,
+        "super" | "cmd" | "command" | "win" => Modifiers::SUPER,
+        "lsuper" | "leftsuper" | "lwin" => Modifiers::LEFT_SUPER,
+        "rsuper" | "rightsuper" | "rwin" => Modifiers::RIGHT_SUPER,
+        _ => return None,
+    };
+    Some(modifiers)
+}
+
+fn combo_from_str(s: &str) -> Option<KeyCombo> {
+    let mut combo = KeyCombo {
+        ksym: SYM_NONE,
+        modifiers: Modifiers::NONE,
+    };
+    for part in s.split('+') {
+        if let Some(modifiers) = modifier_from_str(part) {
+            combo.modifiers = combo.modifiers | modifiers;
+        } else if combo.ksym == SYM_NONE {
+            combo.ksym = keysym_from_str(part)?;
+        } else {
+            return None;
+        }
+    }
+    Some(combo)
+}
+
+/// Names of the keys, the first name of a key is the one written back to
+/// the config. Other printable characters are named by themselves.
+const KEY_NAMES: &[(&str, Keysym)] = &[
+    ("Esc", SYM_ESC),
+    ("Escape", SYM_ESC),
+    ("F1", SYM_F1),
+    ("F2", SYM_F2),
+    ("F3", SYM_F3),
+    ("F4", SYM_F4),
+    ("F5", SYM_F5),
+    ("F6", SYM_F6),
+    ("F7", SYM_F7),
+    ("F8", SYM_F8),
+    ("F9", SYM_F9),
+    ("F10", SYM_F10),
+    ("F11", SYM_F11),
+    ("F12", SYM_F12),
+    ("Enter", SYM_RETURN),
+    ("Return", SYM_RETURN),
+    ("Tab", SYM_TAB),
+    ("Backspace", SYM_BACKSPACE),
+    ("Space", SYM_SPACE),
+    ("CapsLock", SYM_CAPSLOCK),
+    ("Insert", SYM_INSERT),
+    ("Ins", SYM_INSERT),
+    ("Delete", SYM_DELETE),
+    ("Del", SYM_DELETE),
+    ("Home", SYM_HOME),
+    ("End", SYM_END),
+    ("PageUp", SYM_PAGEUP),
+    ("PgUp", SYM_PAGEUP),
+    ("PageDown", SYM_PAGEDOWN),
+    ("PgDn", SYM_PAGEDOWN),
+    ("Up", SYM_UP),
+    ("Down", SYM_DOWN),
+    ("Left", SYM_LEFT),
+    ("Right", SYM_RIGHT),
+    ("Num0", SYM_KP0),
+    ("Num1", SYM_KP1),
+    ("Num2", SYM_KP2),
+    ("Num3", SYM_KP3),
+    ("Num4", SYM_KP4),
+    ("Num5", SYM_KP5),
+    ("Num6", SYM_KP6),
+    ("Num7", SYM_KP7),
+    ("Num8", SYM_KP8),
+    ("Num9", SYM_KP9),
+    ("NumMultiply", SYM_KPMULTIPLY),
+    ("NumAdd", SYM_KPADD),
+    ("NumSubtract", SYM_KPSUBTRACT),
+    ("NumDecimal", SYM_KPDECIMAL),
+    ("NumDivide", SYM_KPDIVIDE),
+    // `+` separates the keys of a combination
+    ("Plus", Keysym('+' as u32)),
+    // Punctuation keys, by their names on the US layout
+    (";", Keysym(';' as u32)),
+    ("Semicolon", Keysym(';' as u32)),
+    ("=", Keysym('=' as u32)),
+    ("Equal", Keysym('=' as u32)),
+    (",", Keysym(',' as u32)),
+    ("Comma", Keysym(',' as u32)),
+    ("-", Keysym('-' as u32)),
+    ("Minus", Keysym('-' as u32)),
+    (".", Keysym('.' as u32)),
+    ("Period", Keysym('.' as u32)),
+    ("/", Keysym('/' as u32)),
+    ("Slash", Keysym('/' as u32)),
+    ("`", Keysym('`' as u32)),
+    ("Backquote", Keysym('`' as u32)),
+    ("[", Keysym('[' as u32)),
+    ("BracketLeft", Keysym('[' as u32)),
+    ("\\", Keysym('\\' as u32)),
+    ("Backslash", Keysym('\\' as u32)),
+    ("]", Keysym(']' as u32)),
+    ("BracketRight", Keysym(']' as u32)),
+    ("'", Keysym('\'' as u32)),
+    ("Quote", Keysym('\'' as u32)),
+];
+
+fn keysym_from_str(s: &str) -> Option<Keysym> {
+    let s = s.trim();
+    let mut chars = s.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(Keysym::from_char(c));
+    }
+    KEY_NAMES
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(s))
+        .map(|&(_, ksym)| ksym)
+}
+
+fn fmt_keysym(ksym: Keysym, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match KEY_NAMES.iter().find(|&&(_, named)| named == ksym) {
+        Some((name, _)) => f.write_str(name),
+        None => write!(f, "{}", ksym.to_unicode()),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keybinding {
+    pub trigger: Trigger,
+    pub when: Scopes,
+    pub action: Action,
+}
+
+impl TryFrom<&KeybindValue> for Keybinding {
+    type Error = ConfigError;
+
+    fn try_from(value: &KeybindValue) -> Result<Self, Self::Error> {
+        let mut when: Scopes = value.when.parse()?;
+        // Scope of the action before keybindings had scopes
+        if value.action.trim() == "selecting_unlearn_phrase" && when.is_any() {
+            when = Scopes::SELECTING;
+        }
+        Ok(Keybinding {
+            trigger: value.key.parse()?,
+            when,
+            action: Action::parse(&value.action, &value.param)?,
+        })
+    }
+}
+
+impl From<&Keybinding> for KeybindValue {
+    fn from(binding: &Keybinding) -> Self {
+        KeybindValue {
+            key: binding.trigger.to_string(),
+            when: binding.when.to_string(),
+            action: binding.action.name().to_string(),
+            param: binding.action.param().to_string(),
+        }
+    }
+}
+
+impl Keybinding {
+    /// Whether the keybinding may take `press` in `state`.
+    ///
+    /// Keybindings without an edit state scope leave the keys used by the
+    /// editor alone while composing or selecting.
+    fn applies(&self, press: &KeyPress, state: InputState) -> bool {
+        self.when.allows(state)
+            && (state.edit == EditState::Idle
+                || self.when.overrides_editor()
+                || !is_editor_key(press))
+    }
+}
+
+/// A problem found in the keybindings of the config.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeybindIssue {
+    /// The key can not be parsed, the entry is ignored
+    InvalidKey { entry: String },
+    /// The scopes can not be parsed, the entry is ignored
+    InvalidScope { entry: String },
+    /// The action is unknown or has a wrong parameter, the entry is ignored
+    InvalidAction { entry: String },
+    /// Same trigger as an earlier entry in the same input states, the
+    /// earlier one wins
+    Duplicate { entry: String, previous: String },
+    /// Never completes because a shorter sequence starting the same way
+    /// completes first
+    Shadowed { entry: String, by: String },
+    /// Takes a key the input method itself uses
+    Reserved { entry: String, reason: &'static str },
+}
+
+impl Display for KeybindIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeybindIssue::InvalidKey { entry } => {
+                write!(f, "Ignored keybinding {entry}: unable to parse the key")
+            }
+            KeybindIssue::InvalidScope { entry } => {
+                write!(f, "Ignored keybinding {entry}: unknown scope")
+            }
+            KeybindIssue::InvalidAction { entry } => {
+                write!(f, "Ignored keybinding {entry}: invalid action")
+            }
+            KeybindIssue::Duplicate { entry, previous } => {
+                write!(f, "Keybinding {entry} is hidden by {previous}")
+            }
+            KeybindIssue::Shadowed { entry, by } => {
+                write!(f, "Keybinding {entry} never completes because of {by}")
+            }
+            KeybindIssue::Reserved { entry, reason } => {
+                write!(f, "Keybinding {entry} overrides the input method: {reason}")
+            }
+        }
+    }
+}
+
+/// Parses the keybindings of the config and checks them for conflicts.
+///
+/// Entries that can not be parsed are left out, conflicting ones are kept
+/// and reported.
+pub fn parse_keybindings(cfg: &ChewingTsfConfig) -> (Vec<Keybinding>, Vec<KeybindIssue>) {
+    let mut bindings: Vec<Keybinding> = vec![];
+    let mut entries: Vec<String> = vec![];
+    let mut issues = vec![];
+    for value in &cfg.keybind {
+        let entry = value.to_string();
+        if value.key.parse::<Trigger>().is_err() {
+            issues.push(KeybindIssue::InvalidKey { entry });
+            continue;
+        }
+        if value.when.parse::<Scopes>().is_err() {
+            issues.push(KeybindIssue::InvalidScope { entry });
+            continue;
+        }
+        let Ok(binding) = Keybinding::try_from(value) else {
+            issues.push(KeybindIssue::InvalidAction { entry });
+            continue;
+        };
+        for (other, other_entry) in bindings.iter().zip(&entries) {
+            if !binding.when.overlaps(other.when) {
+                continue;
+            }
+            if binding.trigger == other.trigger {
+                issues.push(KeybindIssue::Duplicate {
+                    entry: entry.clone(),
+                    previous: other_entry.clone(),
+                });
+            } else if let (Trigger::Sequence(combos), Trigger::Sequence(other_combos)) =
+                (&binding.trigger, &other.trigger)
+            {
+                if combos.starts_with(other_combos) {
+                    issues.push(KeybindIssue::Shadowed {
+                        entry: entry.clone(),
+                        by: other_entry.clone(),
+                    });
+                } else if other_combos.starts_with(combos) {
+                    issues.push(KeybindIssue::Shadowed {
+                        entry: other_entry.clone(),
+                        by: entry.clone(),
+                    });
+                }
+            }
+        }
+        if let Some(reason) = reserved_by_ime(&binding, cfg) {
+            issues.push(KeybindIssue::Reserved {
+                entry: entry.clone(),
+                reason,
+            });
+        }
+        bindings.push(binding);
+        entries.push(entry);
+    }
+    (bindings, issues)
+}
+
+/// Why the input method needs the key that starts the keybinding.
+fn reserved_by_ime(binding: &Keybinding, cfg: &ChewingTsfConfig) -> Option<&'static str> {
+    let toggle_key = LangToggle::from_config(cfg)
+        .ok()
+        .and_then(|toggle| toggle.key());
+    let toggle_reason = match toggle_key {
+        Some(ToggleKey::Shift | ToggleKey::LeftShift | ToggleKey::RightShift) => {
+            "Shift switches the language"
+        }
+        Some(ToggleKey::Ctrl) => "Ctrl switches the language",
+        Some(ToggleKey::CapsLock) => "CapsLock switches the language",
+        Some(ToggleKey::CtrlSpace) => "Ctrl+Space switches the language",
+        None => "",
+    };
+    let first = match &binding.trigger {
+        Trigger::Tap(modifiers) => {
+            return toggle_key
+                .and_then(ToggleKey::tap_modifiers)
+                .is_some_and(|toggle| modifiers.has_any_of(toggle))
+                .then_some(toggle_reason);
+        }
+        Trigger::Sequence(combos) => combos[0],
+    };
+    let first_press = KeyPress {
+        ksym: first.ksym,
+        modifiers: first.modifiers,
+    };
+    if toggle_key
+        .and_then(ToggleKey::combo)
+        .is_some_and(|combo| combo.matches(&first_press))
+    {
+        return Some(toggle_reason);
+    }
+    let modifiers = first.modifiers;
+    let shift_only = modifiers.has_any_of(Modifiers::SHIFT)
+        && !modifiers.has_any_of(Modifiers::CTRL | Modifiers::ALT | Modifiers::SUPER);
+    let ctrl_shift = modifiers.has_any_of(Modifiers::CTRL)
+        && modifiers.has_any_of(Modifiers::SHIFT)
+        && !modifiers.has_any_of(Modifiers::ALT | Modifiers::SUPER);
+    if first.ksym == SYM_CAPSLOCK && cfg.enable_caps_lock {
+        Some("CapsLock switches the language")
+    } else if first.ksym == SYM_SPACE && shift_only && cfg.enable_fullwidth_toggle_key {
+        Some("Shift+Space switches full and half shape")
+    } else if first.ksym.is_unicode() && ctrl_shift && cfg.easy_symbols_with_shift_ctrl {
+        Some("Ctrl+Shift types easy symbols")
+    } else if first.ksym.is_unicode()
+        && !modifiers.has_any_of(Modifiers::CTRL | Modifiers::ALT | Modifiers::SUPER)
+    {
+        Some("the key is used for typing")
+    } else if binding.when.overrides_editor() && is_editor_key(&first_press) {
+        Some("the key is used for editing the composition")
+    } else {
+        None
+    }
+}
+
+/// Result of matching a key press.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyMatch {
+    /// The key is not part of any keybinding
+    None,
+    /// The key continues a sequence, more keys are needed
+    Pending,
+    /// The key completes a keybinding
+    Complete(Action),
+}
+
+/// Matches key presses against keybindings, remembering sequences in
+/// progress and modifier keys that might be tapped.
+#[derive(Debug, Default)]
+pub struct KeybindingMatcher {
+    bindings: Vec<Keybinding>,
+    /// Keys of the sequence typed so far
+    typed: Vec<KeyPress>,
+    /// When the last key of the sequence was typed
+    typed_at: Option<Instant>,
+    /// Modifier key pressed alone and when
+    tap: Option<(Keysym, Instant)>,
+    /// Longest press still counted as a tap
+    tap_timeout: Duration,
+}
+
+impl KeybindingMatcher {
+    pub fn new(bindings: Vec<Keybinding>, tap_timeout: Duration) -> KeybindingMatcher {
+        KeybindingMatcher {
+            bindings,
+            tap_timeout,
+            ..Default::default()
+        }
+    }
+    pub fn bindings(&self) -> &[Keybinding] {
+        &self.bindings
+    }
+    /// Whether pressing the key would trigger or continue a keybinding.
+    ///
+    /// Does not advance sequences, so it can be called any number of times
+    /// before [`key_down`](Self::key_down) for the same key.
+    pub fn test_key_down(&mut self, state: InputState, press: KeyPress, now: Instant) -> KeyMatch {
+        self.track_tap(&press, now);
+        self.expire(now);
+        let result = self.lookup(state, &press);
+        if result == KeyMatch::None && !self.typed.is_empty() {
+            if !Modifiers::of_key(press.ksym).is_empty() {
+                // Modifiers are pressed again between the keys of a sequence
+                return KeyMatch::None;
+            }
+            // A key not in the sequence breaks it
+            self.typed.clear();
+            return self.lookup(state, &press);
+        }
+        result
+    }
+    /// Feeds a key press, advancing the sequence in progress.
+    pub fn key_down(&mut self, state: InputState, press: KeyPress, now: Instant) -> KeyMatch {
+        let result = self.test_key_down(state, press, now);
+        match &result {
+            KeyMatch::None => {}
+            KeyMatch::Pending => {
+                self.typed.push(press);
+                self.typed_at = Some(now);
+            }
+            KeyMatch::Complete(_) => {
+                self.typed.clear();
+                self.typed_at = None;
+            }
+        }
+        result
+    }
+    /// Feeds a key release, returning the action of a tapped modifier.
+    ///
+    /// Calling it again for the same release returns `None`.
+    pub fn key_up(&mut self, state: InputState, ksym: Keysym, now: Instant) -> Option<Action> {
+        let (tapped, pressed_at) = self.tap.take_if(|(tapped, _)| *tapped == ksym)?;
+        if now.duration_since(pressed_at) >= self.tap_timeout {
+            return None;
+        }
+        let modifier = Modifiers::of_key(tapped);
+        self.bindings
+            .iter()
+            .find(|binding| match binding.trigger {
+                Trigger::Tap(modifiers) => {
+                    modifiers.accepts(modifier) && binding.when.allows(state)
+                }
+                Trigger::Sequence(_) => false,
+            })
+            .map(|binding| binding.action.clone())
+    }
+    fn track_tap(&mut self, press: &KeyPress, now: Instant) {
+        let modifier = Modifiers::of_key(press.ksym);
+        if modifier.is_empty() || !press.modifiers.difference(modifier).is_empty() {
+            self.tap = None;
+        } else if self.tap.is_none_or(|(tapped, _)| tapped != press.ksym) {
+            // Key repeat sends more key downs while the key is held
+            self.tap = Some((press.ksym, now));
+        }
+    }
+    fn expire(&mut self, now: Instant) {
+        if self
+            .typed_at
+            .is_some_and(|typed_at| now.duration_since(typed_at) > SEQUENCE_TIMEOUT)
+        {
+            self.typed.clear();
+            self.typed_at = None;
+        }
+    }
+    fn lookup(&self, state: InputState, press: &KeyPress) -> KeyMatch {
+        let step = self.typed.len();
+        let mut pending = false;
+        for binding in &self.bindings {
+            let Trigger::Sequence(combos) = &binding.trigger else {
+                continue;
+            };
+            if !binding.applies(press, state) {
+                continue;
+            }
+            let matched = combos.len() > step
+                && self
+                    .typed
+                    .iter()
+                    .chain([press])
+                    .zip(combos)
+                    .all(|(press, combo)| combo.matches(press));
+            if !matched {
+                continue;
+            }
+            if combos.len() == step + 1 {
+                return KeyMatch::Complete(binding.action.clone());
+            }
+            pending = true;
+        }
+        if pending {
+            KeyMatch::Pending
+        } else {
+            KeyMatch::None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use chewing::input::keysym::{
+        Keysym, SYM_F12, SYM_LEFTALT, SYM_LEFTCTRL, SYM_LEFTSHIFT, SYM_NONE, SYM_RIGHTCTRL,
+    };
+
+    use super::{
+        EditState, InputState, KeyCombo, KeyMatch, KeyPress, KeybindIssue, Keybinding,
+        KeybindingMatcher, Modifiers, SEQUENCE_TIMEOUT, Scopes, Trigger, parse_keybindings,
+    };
+    use crate::{
+        action::Action,
+        config::{ChewingTsfConfig, KeybindValue},
+    };
+
+    const TAP_TIMEOUT: Duration = Duration::from_millis(200);
+    const IDLE: InputState = InputState {
+        edit: EditState::Idle,
+        english: false,
+    };
+
+    fn press(ksym: Keysym, modifiers: Modifiers) -> KeyPress {
+        KeyPress { ksym, modifiers }
+    }
+
+    fn key(c: char) -> Keysym {
+        Keysym::from_char(c)
+    }
+
+    fn binding(key: &str, action: &str) -> Keybinding {
+        let (key, when) = key.split_once('@').unwrap_or((key, ""));
+        Keybinding::try_from(&KeybindValue {
+            key: key.to_string(),
+            when: when.to_string(),
+            action: action.to_string(),
+            param: "".to_string(),
+        })
+        .unwrap()
+    }
+
+    fn matcher(bindings: &[(&str, &str)]) -> KeybindingMatcher {
+        let bindings = bindings
+            .iter()
+            .map(|(key, action)| binding(key, action))
+            .collect();
+        KeybindingMatcher::new(bindings, TAP_TIMEOUT)
+    }
+
+    #[test]
+    fn match_ctrl_f12() {
+        let Trigger::Sequence(combos) = "Ctrl+F12".parse().unwrap() else {
+            panic!("not a sequence");
+        };
+        let target = KeyCombo {
+            ksym: SYM_F12,
+            modifiers: Modifiers::CTRL,
+        };
+        assert_eq!(vec![target], combos);
+        assert_eq!(Trigger::Sequence(vec![target]), "ctrl+F12".parse().unwrap());
+        assert_eq!(
+            Trigger::Sequence(vec![target]),
+            "control+F12".parse().unwrap()
+        );
+        assert!(target.matches(&press(SYM_F12, Modifiers::LEFT_CTRL)));
+        assert!(target.matches(&press(SYM_F12, Modifiers::RIGHT_CTRL)));
+        assert!(!target.matches(&press(SYM_LEFTCTRL, Modifiers::LEFT_CTRL)));
+        assert!(!target.matches(&press(SYM_F12, Modifiers::NONE)));
+    }
+
+    #[test]
+    fn match_ctrl_shift_a() {
+        let target = Trigger::Sequence(vec![KeyCombo {
+            ksym: key('A'),
+            modifiers: Modifiers::CTRL | Modifiers::SHIFT,
+        }]);
+        assert_eq!(target, "Ctrl+Shift+A".parse().unwrap());
+        assert_eq!(target, "ctrl+shift+A".parse().unwrap());
+        assert_eq!(target, "control+shift+A".parse().unwrap());
+        assert_eq!(target, "Ctrl + Shift + A".parse().unwrap());
+    }
+
+    #[test]
+    fn match_shift_alt() {
+        let target = Trigger::Sequence(vec![KeyCombo {
+            ksym: SYM_NONE,
+            modifiers: Modifiers::SHIFT | Modifiers::ALT,
+        }]);
+        assert_eq!(target, "shift+alt".parse().unwrap());
+        assert_eq!(target, "alt+shift".parse().unwrap());
+        assert_eq!(target, "Shift+Alt".parse().unwrap());
+        let mut matcher = matcher(&[("Shift+Alt", "toggle_language")]);
+        let now = Instant::now();
+        assert_eq!(
+            KeyMatch::Complete(Action::ToggleLanguage),
+            matcher.key_down(
+                IDLE,
+                press(SYM_LEFTALT, Modifiers::LEFT_SHIFT | Modifiers::LEFT_ALT),
+                now
+            )
+        );
+        assert_eq!(
+            KeyMatch::None,
+            matcher.key_down(IDLE, press(SYM_LEFTALT, Modifiers::LEFT_ALT), now)
+        );
+    }
+
+    #[test]
+    fn left_and_right_modifiers() {
+        assert_eq!(
+            Trigger::Sequence(vec![KeyCombo {
+                ksym: key('a'),
+                modifiers: Modifiers::RIGHT_CTRL,
+            }]),
+            "RCtrl+a".parse().unwrap()
+        );
+        let combo = KeyCombo {
+            ksym: key('a'),
+            modifiers: Modifiers::RIGHT_CTRL,
+        };
+        assert!(combo.matches(&press(key('a'), Modifiers::RIGHT_CTRL)));
+        assert!(!combo.matches(&press(key('a'), Modifiers::LEFT_CTRL)));
+        assert!(!combo.matches(&press(key('a'), Modifiers::CTRL)));
+    }
+
+    #[test]
+    fn invalid_keys() {
+        assert!("".parse::<Trigger>().is_err());
+        assert!("Ctrl+Foo".parse::<Trigger>().is_err());
+        assert!("Ctrl+a+b".parse::<Trigger>().is_err());
+        assert!("Ctrl+; Foo".parse::<Trigger>().is_err());
+    }
+
+    #[test]
+    fn sequence() {
+        let mut matcher = matcher(&[("Ctrl+; d", "clear_buffer")]);
+        let now = Instant::now();
+        assert_eq!(
+            Trigger::Sequence(vec![
+                KeyCombo {
+                    ksym: key(';'),
+                    modifiers: Modifiers::CTRL,
+                },
+                KeyCombo {
+                    ksym: key('d'),
+                    modifiers: Modifiers::NONE,
+                },
+            ]),
+            matcher.bindings()[0].trigger
+        );
+        // Testing does not advance the sequence
+        let first = press(key(';'), Modifiers::LEFT_CTRL);
+        assert_eq!(KeyMatch::Pending, matcher.test_key_down(IDLE, first, now));
+        assert_eq!(KeyMatch::Pending, matcher.test_key_down(IDLE, first, now));
+        assert_eq!(
+            KeyMatch::None,
+            matcher.test_key_down(IDLE, press(key('d'), Modifiers::NONE), now)
+        );
+        assert_eq!(KeyMatch::Pending, matcher.key_down(IDLE, first, now));
+        // Releasing and pressing Ctrl again does not break the sequence
+        assert_eq!(
+            KeyMatch::None,
+            matcher.key_down(IDLE, press(SYM_LEFTCTRL, Modifiers::LEFT_CTRL), now)
+        );
+        assert_eq!(
+            KeyMatch::Complete(Action::ClearBuffer),
+            matcher.key_down(IDLE, press(key('d'), Modifiers::NONE), now)
+        );
+        // Starts over
+        assert_eq!(
+            KeyMatch::None,
+            matcher.key_down(IDLE, press(key('d'), Modifiers::NONE), now)
+        );
+    }
+
+    #[test]
+    fn sequence_broken_by_other_key() {
+        let mut matcher = matcher(&[("Ctrl+; d", "clear_buffer")]);
+        let now = Instant::now();
+        let first = press(key(';'), Modifiers::LEFT_CTRL);
+        assert_eq!(KeyMatch::Pending, matcher.key_down(IDLE, first, now));
+        // Not claimed, so only tested
+        assert_eq!(
+            KeyMatch::None,
+            matcher.test_key_down(IDLE, press(key('x'), Modifiers::NONE), now)
+        );
+        assert_eq!(
+            KeyMatch::None,
+            matcher.key_down(IDLE, press(key('d'), Modifiers::NONE), now)
+        );
+        // The breaking key can start the sequence again
+        assert_eq!(KeyMatch::Pending, matcher.key_down(IDLE, first, now));
+        assert_eq!(KeyMatch::Pending, matcher.key_down(IDLE, first, now));
+    }
+
+    #[test]
+    fn sequence_timeout() {
+        let mut matcher = matcher(&[("Ctrl+; d", "clear_buffer")]);
+        let now = Instant::now();
+        assert_eq!(
+            KeyMatch::Pending,
+            matcher.key_down(IDLE, press(key(';'), Modifiers::LEFT_CTRL), now)
+        );
+        let later = now + SEQUENCE_TIMEOUT + Duration::from_millis(1);
+        assert_eq!(
+            KeyMatch::None,
+            matcher.key_down(IDLE, press(key('d'), Modifiers::NONE), later)
+        );
+    }
+
+    #[test]
+    fn shorter_binding_wins() {
+        let mut matcher = matcher(&[
+            ("Ctrl+K Ctrl+D", "clear_buffer"),
+            ("Ctrl+K", "toggle_shape"),
+        ]);
+        let now = Instant::now();
+        assert_eq!(
+            KeyMatch::Complete(Action::ToggleShape),
+            matcher.key_down(IDLE, press(key('K'), Modifiers::LEFT_CTRL), now)
+        );
+    }
+
+    #[test]
+    fn tap_right_ctrl() {
+        let mut matcher = matcher(&[("RCtrl", "toggle_language")]);
+        assert_eq!(
+            Trigger::Tap(Modifiers::RIGHT_CTRL),
+            matcher.bindings()[0].trigger
+        );
+        let now = Instant::now();
+        let down = press(SYM_RIGHTCTRL, Modifiers::RIGHT_CTRL);
+        assert_eq!(KeyMatch::None, matcher.test_key_down(IDLE, down, now));
+        // Key repeat
+        matcher.test_key_down(IDLE, down, now + Duration::from_millis(100));
+        let up = now + Duration::from_millis(150);
+        assert_eq!(
+            Some(Action::ToggleLanguage),
+            matcher.key_up(IDLE, SYM_RIGHTCTRL, up)
+        );
+        assert_eq!(None, matcher.key_up(IDLE, SYM_RIGHTCTRL, up));
+        // Left Ctrl
+        matcher.test_key_down(IDLE, press(SYM_LEFTCTRL, Modifiers::LEFT_CTRL), now);
+        assert_eq!(None, matcher.key_up(IDLE, SYM_LEFTCTRL, up));
+    }
+
+    #[test]
+    fn tap_either_shift() {
+        let mut matcher = matcher(&[("Shift", "toggle_shape")]);
+        let now = Instant::now();
+        matcher.test_key_down(IDLE, press(SYM_LEFTSHIFT, Modifiers::LEFT_SHIFT), now);
+        assert_eq!(
+            Some(Action::ToggleShape),
+            matcher.key_up(IDLE, SYM_LEFTSHIFT, now + Duration::from_millis(10))
+        );
+    }
+
+    #[test]
+    fn not_a_tap() {
+        let mut matcher = matcher(&[("RCtrl", "toggle_language")]);
+        let now = Instant::now();
+        let down = press(SYM_RIGHTCTRL, Modifiers::RIGHT_CTRL);
+        // Held too long
+        matcher.test_key_down(IDLE, down, now);
+        assert_eq!(None, matcher.key_up(IDLE, SYM_RIGHTCTRL, now + TAP_TIMEOUT));
+        // Used with another key
+        matcher.test_key_down(IDLE, down, now);
+        matcher.test_key_down(IDLE, press(key('c'), Modifiers::RIGHT_CTRL), now);
+        assert_eq!(None, matcher.key_up(IDLE, SYM_RIGHTCTRL, now));
+        // Pressed with another modifier
+        matcher.test_key_down(
+            IDLE,
+            press(SYM_RIGHTCTRL, Modifiers::RIGHT_CTRL | Modifiers::LEFT_SHIFT),
+            now,
+        );
+        assert_eq!(None, matcher.key_up(IDLE, SYM_RIGHTCTRL, now));
+    }
+
+    fn state(edit: EditState, english: bool) -> InputState {
+        InputState { edit, english }
+    }
+
+    #[test]
+    fn parse_scopes() {
+        assert_eq!(Scopes::ANY, "".parse().unwrap());
+        assert_eq!(
+            Scopes::COMPOSING | Scopes::SELECTING,
+            "composing, selecting".parse().unwrap()
+        );
+        assert!("typing".parse::<Scopes>().is_err());
+    }
+
+    #[test]
+    fn scopes_allow_states() {
+        let scopes = Scopes::COMPOSING | Scopes::ENGLISH;
+        assert!(scopes.allows(state(EditState::Composing, true)));
+        assert!(!scopes.allows(state(EditState::Composing, false)));
+        assert!(!scopes.allows(state(EditState::Idle, true)));
+        assert!(Scopes::CHINESE.allows(state(EditState::Selecting, false)));
+        assert!(Scopes::ANY.allows(state(EditState::Idle, true)));
+    }
+
+    #[test]
+    fn scoped_binding() {
+        let mut matcher = matcher(&[("Ctrl+Delete@selecting", "unlearn_phrase")]);
+        let key = press(super::SYM_DELETE, Modifiers::LEFT_CTRL);
+        let now = Instant::now();
+        assert_eq!(KeyMatch::None, matcher.test_key_down(IDLE, key, now));
+        assert_eq!(
+            KeyMatch::None,
+            matcher.test_key_down(state(EditState::Composing, false), key, now)
+        );
+        assert_eq!(
+            KeyMatch::Complete(Action::UnlearnPhrase),
+            matcher.key_down(state(EditState::Selecting, false), key, now)
+        );
+    }
+
+    #[test]
+    fn legacy_selecting_action() {
+        let binding = binding("Ctrl+Delete", "selecting_unlearn_phrase");
+        assert_eq!(Scopes::SELECTING, binding.when);
+        assert_eq!(Action::UnlearnPhrase, binding.action);
+    }
+
+    #[test]
+    fn editor_keys_win_over_global_bindings() {
+        let mut matcher = matcher(&[("Ctrl+2", "toggle_shape")]);
+        let key = press(key('2'), Modifiers::LEFT_CTRL);
+        let now = Instant::now();
+        // Adds a user phrase while composing
+        assert_eq!(
+            KeyMatch::None,
+            matcher.key_down(state(EditState::Composing, false), key, now)
+        );
+        assert_eq!(
+            KeyMatch::Complete(Action::ToggleShape),
+            matcher.key_down(IDLE, key, now)
+        );
+    }
+
+    #[test]
+    fn scoped_bindings_win_over_editor_keys() {
+        let mut matcher = matcher(&[("Ctrl+2@composing", "toggle_shape")]);
+        let key = press(key('2'), Modifiers::LEFT_CTRL);
+        let now = Instant::now();
+        assert_eq!(
+            KeyMatch::Complete(Action::ToggleShape),
+            matcher.key_down(state(EditState::Composing, false), key, now)
+        );
+    }
+
+    #[test]
+    fn tap_in_english_mode_only() {
+        let mut matcher = matcher(&[("RCtrl@english", "toggle_shape")]);
+        let now = Instant::now();
+        let down = press(SYM_RIGHTCTRL, Modifiers::RIGHT_CTRL);
+        matcher.test_key_down(IDLE, down, now);
+        assert_eq!(None, matcher.key_up(IDLE, SYM_RIGHTCTRL, now));
+        let english = state(EditState::Idle, true);
+        matcher.test_key_down(english, down, now);
+        assert_eq!(
+            Some(Action::ToggleShape),
+            matcher.key_up(english, SYM_RIGHTCTRL, now)
+        );
+    }
+
+    #[test]
+    fn key_names() {
+        let combo = |s: &str| match s.parse::<Trigger>().unwrap() {
+            Trigger::Sequence(combos) => combos[0].ksym,
+            Trigger::Tap(_) => panic!("not a sequence"),
+        };
+        assert_eq!(super::SYM_PAGEDOWN, combo("Ctrl+PgDn"));
+        assert_eq!(super::SYM_PAGEDOWN, combo("ctrl+pagedown"));
+        assert_eq!(super::SYM_INSERT, combo("Shift+Ins"));
+        assert_eq!(super::SYM_KP5, combo("Alt+Num5"));
+        assert_eq!(super::SYM_LEFT, combo("Ctrl+Left"));
+        assert_eq!(key('+'), combo("Ctrl+Plus"));
+        assert_eq!(key(';'), combo("Ctrl+Semicolon"));
+        assert_eq!(key('\\'), combo("Ctrl+Backslash"));
+        assert_eq!(key('Q'), combo("Ctrl+Q"));
+    }
+
+    #[test]
+    fn display_round_trip() {
+        for key in [
+            "Ctrl+F12",
+            "Ctrl+Shift+A",
+            "LCtrl+RAlt+Delete",
+            "Shift+Alt",
+            "RCtrl",
+            "Shift",
+            "Ctrl+; d",
+            "Ctrl+Plus",
+            "Ctrl+=",
+            "Alt+PageUp",
+            "Super+Num5",
+            "Ctrl+Insert",
+            "Ctrl+Alt+Left Right",
+            "Ctrl+Space",
+        ] {
+            let trigger: Trigger = key.parse().unwrap();
+            assert_eq!(key, trigger.to_string());
+            assert_eq!(trigger, trigger.to_string().parse().unwrap());
+        }
+        assert_eq!(
+            "Ctrl+PageDown",
+            "ctrl + pgdn".parse::<Trigger>().unwrap().to_string()
+        );
+        assert_eq!(
+            "Ctrl+;",
+            "Ctrl+Semicolon".parse::<Trigger>().unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn keybind_value_round_trip() {
+        for entry in [
+            "Ctrl+Delete@selecting=unlearn_phrase",
+            "Ctrl+Alt+T@composing,english=text:酷",
+            "RCtrl=toggle_language",
+        ] {
+            let value: KeybindValue = entry.parse().unwrap();
+            let binding = Keybinding::try_from(&value).unwrap();
+            assert_eq!(entry, KeybindValue::from(&binding).to_string());
+        }
+        let legacy = binding("Ctrl+Delete", "selecting_unlearn_phrase");
+        assert_eq!(
+            "Ctrl+Delete@selecting=unlearn_phrase",
+            KeybindValue::from(&legacy).to_string()
+        );
+    }
+
+    #[test]
+    fn scopes_overlap() {
+        assert!(Scopes::ANY.overlaps(Scopes::SELECTING));
+        assert!(Scopes::SELECTING.overlaps(Scopes::ENGLISH));
+        assert!(!Scopes::SELECTING.overlaps(Scopes::COMPOSING | Scopes::IDLE));
+        assert!(!(Scopes::IDLE | Scopes::ENGLISH).overlaps(Scopes::CHINESE));
+    }
+
+    #[test]
+    fn validate_keybindings() {
+        let entries = [
+            "Ctrl+Foo=toggle_shape",
+            "Ctrl+K@typing=toggle_shape",
+            "Ctrl+K=toggle_everything",
+            "Ctrl+K=toggle_shape",
+            "Ctrl+K@english=clear_buffer",
+            "Ctrl+K@selecting,chinese=clear_buffer",
+            "Ctrl+K d=open_preferences",
+            "LShift=toggle_language",
+            "a=text:x",
+            "Ctrl+1@composing=toggle_shape",
+            "Ctrl+Delete@selecting=unlearn_phrase",
+        ];
+        let cfg = ChewingTsfConfig {
+            keybind: entries.iter().map(|entry| entry.parse().unwrap()).collect(),
+            ..Default::default()
+        };
+        let (bindings, issues) = parse_keybindings(&cfg);
+        assert_eq!(8, bindings.len());
+        let entry = |i: usize| entries[i].to_string();
+        assert_eq!(
+            vec![
+                KeybindIssue::InvalidKey { entry: entry(0) },
+                KeybindIssue::InvalidScope { entry: entry(1) },
+                KeybindIssue::InvalidAction { entry: entry(2) },
+                KeybindIssue::Duplicate {
+                    entry: entry(4),
+                    previous: entry(3),
+                },
+                KeybindIssue::Duplicate {
+                    entry: entry(5),
+                    previous: entry(3),
+                },
+                KeybindIssue::Shadowed {
+                    entry: entry(6),
+                    by: entry(3),
+                },
+                KeybindIssue::Shadowed {
+                    entry: entry(6),
+                    by: entry(4),
+                },
+                KeybindIssue::Shadowed {
+                    entry: entry(6),
+                    by: entry(5),
+                },
+                KeybindIssue::Reserved {
+                    entry: entry(7),
+                    reason: "Shift switches the language",
+                },
+                KeybindIssue::Reserved {
+                    entry: entry(8),
+                    reason: "the key is used for typing",
+                },
+                KeybindIssue::Reserved {
+                    entry: entry(9),
+                    reason: "the key is used for editing the composition",
+                },
+            ],
+            issues
+        );
+    }
+
+    #[test]
+    fn validate_lang_toggle_key() {
+        let entries = [
+            "LShift=toggle_shape",
+            "RCtrl=toggle_shape",
+            "Ctrl+Space=toggle_shape",
+        ];
+        let reserved = |toggle_key: &str| {
+            let cfg = ChewingTsfConfig {
+                keybind: entries.iter().map(|entry| entry.parse().unwrap()).collect(),
+                lang_toggle_key: toggle_key.to_string(),
+                ..Default::default()
+            };
+            parse_keybindings(&cfg)
+                .1
+                .into_iter()
+                .map(|issue| match issue {
+                    KeybindIssue::Reserved { entry, reason } => (entry, reason),
+                    issue => panic!("unexpected {issue:?}"),
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            vec![(entries[0].to_string(), "Shift switches the language")],
+            reserved("LShift")
+        );
+        assert_eq!(Vec::<(String, &str)>::new(), reserved("RShift"));
+        assert_eq!(
+            vec![(entries[1].to_string(), "Ctrl switches the language")],
+            reserved("Ctrl")
+        );
+        assert_eq!(
+            vec![(entries[2].to_string(), "Ctrl+Space switches the language")],
+            reserved("Ctrl+Space")
+        );
+    }
+}
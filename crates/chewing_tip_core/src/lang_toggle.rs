@@ -0,0 +1,381 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2026 Kan-Ru Chen
+
+//! Detecting the key that switches between Chinese and English.
+//!
+//! Modifier keys switch the language when tapped, that is pressed and
+//! released alone within `shift_key_sensitivity`. CapsLock and Ctrl+Space
+//! switch it when pressed. With `lang_toggle_guard` set, the key is ignored
+//! if another key was pressed shortly before it, so typing a capital letter
+//! quickly does not switch the language by accident.
+
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use chewing::input::keysym::*;
+use error_plus::expect_error;
+
+use crate::{
+    config::{ChewingTsfConfig, ConfigError},
+    keybind::{KeyCombo, KeyPress, Modifiers},
+};
+
+/// The `LangToggleKey` setting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToggleKey {
+    /// Tap either Shift key
+    Shift,
+    LeftShift,
+    RightShift,
+    /// Tap either Ctrl key
+    Ctrl,
+    /// Press CapsLock, regardless of the CapsLock state
+    CapsLock,
+    CtrlSpace,
+}
+
+const TOGGLE_KEY_NAMES: [(ToggleKey, &str); 6] = [
+    (ToggleKey::Shift, "Shift"),
+    (ToggleKey::LeftShift, "LShift"),
+    (ToggleKey::RightShift, "RShift"),
+    (ToggleKey::Ctrl, "Ctrl"),
+    (ToggleKey::CapsLock, "CapsLock"),
+    (ToggleKey::CtrlSpace, "Ctrl+Space"),
+];
+
+impl ToggleKey {
+    /// The modifier keys switching the language when tapped, `None` if the
+    /// key switches it when pressed.
+    pub fn tap_modifiers(self) -> Option<Modifiers> {
+        match self {
+            ToggleKey::Shift => Some(Modifiers::SHIFT),
+            ToggleKey::LeftShift => Some(Modifiers::LEFT_SHIFT),
+            ToggleKey::RightShift => Some(Modifiers::RIGHT_SHIFT),
+            ToggleKey::Ctrl => Some(Modifiers::CTRL),
+            ToggleKey::CapsLock | ToggleKey::CtrlSpace => None,
+        }
+    }
+    /// The key combination switching the language when pressed, `None` if
+    /// the key is tapped.
+    pub fn combo(self) -> Option<KeyCombo> {
+        match self {
+            ToggleKey::CapsLock => Some(KeyCombo {
+                ksym: SYM_CAPSLOCK,
+                modifiers: Modifiers::NONE,
+            }),
+            ToggleKey::CtrlSpace => Some(KeyCombo {
+                ksym: SYM_SPACE,
+                modifiers: Modifiers::CTRL,
+            }),
+            _ => None,
+        }
+    }
+    fn is_pressed_by(self, press: &KeyPress) -> bool {
+        match self.tap_modifiers() {
+            Some(modifiers) => {
+                let modifier = Modifiers::of_key(press.ksym);
+                !modifier.is_empty() && modifiers.contains(modifier) && press.modifiers == modifier
+            }
+            None => self.combo().is_some_and(|combo| combo.matches(press)),
+        }
+    }
+}
+
+impl FromStr for ToggleKey {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        expect_error("Failed to parse language toggle key", || {
+            let (key, _) = TOGGLE_KEY_NAMES
+                .iter()
+                .find(|(_, name)| name.eq_ignore_ascii_case(s.trim()))
+                .ok_or_else(|| format!("unknown language toggle key \"{s}\""))?;
+            Ok(*key)
+        })
+    }
+}
+
+impl Display for ToggleKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (_, name) = TOGGLE_KEY_NAMES
+            .iter()
+            .find(|(key, _)| key == self)
+            .expect("every toggle key has a name");
+        f.write_str(name)
+    }
+}
+
+/// Result of testing a key press against the toggle key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToggleMatch {
+    /// The key is not the toggle key
+    None,
+    /// The key might be tapped, it should reach the application
+    Tap,
+    /// The key switches the language when pressed
+    Press,
+}
+
+/// The toggle key held down.
+#[derive(Debug, Clone, Copy)]
+struct Pressed {
+    ksym: Keysym,
+    at: Instant,
+    /// Cleared by other keys, the guard, or once the language is switched
+    armed: bool,
+}
+
+/// Tells when the toggle key switches the language.
+///
+/// The text service sees each key down more than once, so testing a key
+/// can be repeated before [`key_down`](Self::key_down) and releasing it
+/// only counts once.
+#[derive(Debug, Default)]
+pub struct LangToggle {
+    /// `None` if no key switches the language
+    key: Option<ToggleKey>,
+    /// Longest press still counted as a tap
+    tap_timeout: Duration,
+    /// The toggle key is ignored this long after another key
+    guard: Duration,
+    pressed: Option<Pressed>,
+    /// When a key other than a modifier was pressed last
+    last_other: Option<Instant>,
+}
+
+impl LangToggle {
+    pub fn new(key: Option<ToggleKey>, tap_timeout: Duration, guard: Duration) -> LangToggle {
+        LangToggle {
+            key,
+            tap_timeout,
+            guard,
+            ..Default::default()
+        }
+    }
+    /// The toggle configured by `cfg`, `switch_lang_with_shift` turns it
+    /// on or off for any key.
+    pub fn from_config(cfg: &ChewingTsfConfig) -> Result<LangToggle, ConfigError> {
+        let key = if cfg.switch_lang_with_shift {
+            Some(cfg.lang_toggle_key.parse()?)
+        } else {
+            None
+        };
+        Ok(LangToggle::new(
+            key,
+            Duration::from_millis(cfg.shift_key_sensitivity as u64),
+            Duration::from_millis(cfg.lang_toggle_guard as u64),
+        ))
+    }
+    pub fn key(&self) -> Option<ToggleKey> {
+        self.key
+    }
+    /// Whether CapsLock switches the language instead of locking it.
+    pub fn uses_caps_lock(&self) -> bool {
+        self.key == Some(ToggleKey::CapsLock)
+    }
+    /// Tests a key press without switching the language.
+    pub fn test_key_down(&mut self, press: KeyPress, now: Instant) -> ToggleMatch {
+        let Some(key) = self.key else {
+            return ToggleMatch::None;
+        };
+        if !key.is_pressed_by(&press) {
+            if let Some(pressed) = &mut self.pressed {
+                // Shift+A is typing, not a tap
+                pressed.armed = false;
+            }
+            if Modifiers::of_key(press.ksym).is_empty() {
+                self.last_other = Some(now);
+            }
+            return ToggleMatch::None;
+        }
+        // Key repeat sends more key downs while the key is held
+        let pressed = match self.pressed {
+            Some(pressed) if pressed.ksym == press.ksym => pressed,
+            _ => {
+                let guarded = self
+                    .last_other
+                    .is_some_and(|last| now.duration_since(last) < self.guard);
+                let pressed = Pressed {
+                    ksym: press.ksym,
+                    at: now,
+                    armed: !guarded,
+                };
+                self.pressed = Some(pressed);
+                pressed
+            }
+        };
+        if key.tap_modifiers().is_some() {
+            ToggleMatch::Tap
+        } else if pressed.armed {
+            ToggleMatch::Press
+        } else {
+            ToggleMatch::None
+        }
+    }
+    /// Feeds a key press, returning whether it switches the language.
+    pub fn key_down(&mut self, press: KeyPress, now: Instant) -> bool {
+        if self.test_key_down(press, now) != ToggleMatch::Press {
+            return false;
+        }
+        if let Some(pressed) = &mut self.pressed {
+            pressed.armed = false;
+        }
+        true
+    }
+    /// Feeds a key release, returning whether it ends a tap that switches
+    /// the language.
+    ///
+    /// Calling it again for the same release returns `false`.
+    pub fn key_up(&mut self, ksym: Keysym, now: Instant) -> bool {
+        let Some(pressed) = self.pressed.take_if(|pressed| pressed.ksym == ksym) else {
+            return false;
+        };
+        self.key.is_some_and(|key| key.tap_modifiers().is_some())
+            && pressed.armed
+            && now.duration_since(pressed.at) < self.tap_timeout
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use chewing::input::keysym::{
+        Keysym, SYM_CAPSLOCK, SYM_LEFTCTRL, SYM_LEFTSHIFT, SYM_RIGHTSHIFT, SYM_SPACE,
+    };
+
+    use super::{LangToggle, ToggleKey, ToggleMatch};
+    use crate::keybind::{KeyPress, Modifiers};
+
+    const TAP_TIMEOUT: Duration = Duration::from_millis(200);
+    const GUARD: Duration = Duration::from_millis(300);
+
+    fn press(ksym: Keysym, modifiers: Modifiers) -> KeyPress {
+        KeyPress { ksym, modifiers }
+    }
+
+    fn modifier(ksym: Keysym) -> KeyPress {
+        press(ksym, Modifiers::of_key(ksym))
+    }
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn parse_toggle_key() {
+        assert_eq!(Some(ToggleKey::Shift), "shift".parse().ok());
+        assert_eq!(Some(ToggleKey::RightShift), "RShift".parse().ok());
+        assert_eq!(Some(ToggleKey::CtrlSpace), "ctrl+space".parse().ok());
+        assert!("Alt".parse::<ToggleKey>().is_err());
+        assert_eq!("Ctrl+Space", ToggleKey::CtrlSpace.to_string());
+        assert_eq!("CapsLock", ToggleKey::CapsLock.to_string());
+    }
+
+    #[test]
+    fn tap_shift() {
+        let t0 = Instant::now();
+        let mut toggle = LangToggle::new(Some(ToggleKey::Shift), TAP_TIMEOUT, Duration::ZERO);
+        let shift = modifier(SYM_RIGHTSHIFT);
+        // Tested twice, then pressed and released twice
+        assert_eq!(ToggleMatch::Tap, toggle.test_key_down(shift, t0));
+        assert_eq!(ToggleMatch::Tap, toggle.test_key_down(shift, t0));
+        assert!(!toggle.key_down(shift, t0));
+        assert!(toggle.key_up(SYM_RIGHTSHIFT, t0 + ms(50)));
+        assert!(!toggle.key_up(SYM_RIGHTSHIFT, t0 + ms(50)));
+
+        // Held too long, even with key repeat
+        toggle.test_key_down(shift, t0 + ms(300));
+        toggle.test_key_down(shift, t0 + ms(500));
+        assert!(!toggle.key_up(SYM_RIGHTSHIFT, t0 + ms(550)));
+
+        // Used to type a capital letter
+        toggle.test_key_down(shift, t0 + ms(600));
+        toggle.test_key_down(
+            press(Keysym::from_char('A'), Modifiers::RIGHT_SHIFT),
+            t0 + ms(620),
+        );
+        assert!(!toggle.key_up(SYM_RIGHTSHIFT, t0 + ms(650)));
+    }
+
+    #[test]
+    fn tap_one_side() {
+        let t0 = Instant::now();
+        let mut toggle = LangToggle::new(Some(ToggleKey::LeftShift), TAP_TIMEOUT, Duration::ZERO);
+        assert_eq!(
+            ToggleMatch::None,
+            toggle.test_key_down(modifier(SYM_RIGHTSHIFT), t0)
+        );
+        assert!(!toggle.key_up(SYM_RIGHTSHIFT, t0 + ms(50)));
+        assert_eq!(
+            ToggleMatch::Tap,
+            toggle.test_key_down(modifier(SYM_LEFTSHIFT), t0 + ms(100))
+        );
+        assert!(toggle.key_up(SYM_LEFTSHIFT, t0 + ms(150)));
+
+        let mut toggle = LangToggle::new(Some(ToggleKey::Ctrl), TAP_TIMEOUT, Duration::ZERO);
+        assert_eq!(
+            ToggleMatch::None,
+            toggle.test_key_down(modifier(SYM_LEFTSHIFT), t0)
+        );
+        toggle.test_key_down(modifier(SYM_LEFTCTRL), t0);
+        assert!(toggle.key_up(SYM_LEFTCTRL, t0 + ms(50)));
+    }
+
+    #[test]
+    fn press_to_toggle() {
+        let t0 = Instant::now();
+        let mut toggle = LangToggle::new(Some(ToggleKey::CtrlSpace), TAP_TIMEOUT, Duration::ZERO);
+        let space = press(SYM_SPACE, Modifiers::NONE);
+        let ctrl_space = press(SYM_SPACE, Modifiers::LEFT_CTRL);
+        assert_eq!(ToggleMatch::None, toggle.test_key_down(space, t0));
+        toggle.test_key_down(modifier(SYM_LEFTCTRL), t0);
+        assert_eq!(ToggleMatch::Press, toggle.test_key_down(ctrl_space, t0));
+        assert!(toggle.key_down(ctrl_space, t0));
+        // Key repeat does not switch it back
+        assert_eq!(
+            ToggleMatch::None,
+            toggle.test_key_down(ctrl_space, t0 + ms(500))
+        );
+        assert!(!toggle.key_down(ctrl_space, t0 + ms(500)));
+        assert!(!toggle.key_up(SYM_SPACE, t0 + ms(600)));
+        assert!(toggle.key_down(ctrl_space, t0 + ms(700)));
+
+        let mut toggle = LangToggle::new(Some(ToggleKey::CapsLock), TAP_TIMEOUT, Duration::ZERO);
+        assert!(toggle.uses_caps_lock());
+        let caps_lock = press(SYM_CAPSLOCK, Modifiers::NONE);
+        assert!(toggle.key_down(caps_lock, t0));
+        assert!(!toggle.key_up(SYM_CAPSLOCK, t0 + ms(50)));
+        // Held longer than a tap still counts
+        assert!(toggle.key_down(caps_lock, t0 + ms(100)));
+    }
+
+    #[test]
+    fn guard_after_typing() {
+        let t0 = Instant::now();
+        let mut toggle = LangToggle::new(Some(ToggleKey::Shift), TAP_TIMEOUT, GUARD);
+        let shift = modifier(SYM_LEFTSHIFT);
+        toggle.test_key_down(press(Keysym::from_char('a'), Modifiers::NONE), t0);
+        toggle.test_key_down(shift, t0 + ms(100));
+        assert!(!toggle.key_up(SYM_LEFTSHIFT, t0 + ms(150)));
+        toggle.test_key_down(shift, t0 + ms(400));
+        assert!(toggle.key_up(SYM_LEFTSHIFT, t0 + ms(450)));
+
+        // Modifiers do not count as typing
+        let mut toggle = LangToggle::new(Some(ToggleKey::CtrlSpace), TAP_TIMEOUT, GUARD);
+        toggle.test_key_down(modifier(SYM_LEFTCTRL), t0);
+        assert!(toggle.key_down(press(SYM_SPACE, Modifiers::LEFT_CTRL), t0 + ms(50)));
+    }
+
+    #[test]
+    fn disabled() {
+        let t0 = Instant::now();
+        let mut toggle = LangToggle::default();
+        let shift = modifier(SYM_LEFTSHIFT);
+        assert_eq!(ToggleMatch::None, toggle.test_key_down(shift, t0));
+        assert!(!toggle.key_up(SYM_LEFTSHIFT, t0 + ms(50)));
+    }
+}
@@ -1,4 +1,12 @@
+pub mod action;
 pub mod config;
+pub mod english;
 pub mod ipc;
+pub mod keybind;
+pub mod lang_toggle;
+pub mod reading;
 pub mod sandbox;
+pub mod script;
 pub mod shell;
+pub mod template;
+pub mod theme;
@@ -0,0 +1,26 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2026 Kan-Ru Chen
+
+//! Reverse lookup of the zhuyin readings of a text (查注音).
+//!
+//! The lookup lives in `chewing_tip_reading` so it can be tested against
+//! the bundled dictionaries on any platform.
+
+use error_plus::expect_error;
+
+pub use chewing_tip_reading::{ReadingDictionaries, ReadingError, Readings, format_readings};
+
+use crate::shell::{program_dir, user_dir};
+
+/// Loads the system dictionaries with readings from the same places as the
+/// editor.
+pub fn load_dictionaries() -> Result<ReadingDictionaries, ReadingError> {
+    expect_error("Unable to load dictionaries for reading lookup", || {
+        let search_path = format!(
+            "{};{}",
+            user_dir()?.display(),
+            program_dir()?.join("Dictionary").display()
+        );
+        Ok(ReadingDictionaries::load(&search_path)?)
+    })
+}
@@ -0,0 +1,408 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2026 Kan-Ru Chen
+
+//! Chinese script variants of the output.
+//!
+//! The editor produces Taiwan Traditional Chinese. Other variants convert the
+//! text before it is shown or committed. Conversions can be overridden with
+//! `conversion_overrides.txt` under the user dir:
+//!
+//! ```text
+//! # Overrides for every variant
+//! 乾隆 乾隆
+//! [zh-CN]
+//! 軟體 软件
+//! ```
+
+use std::{collections::HashMap, fmt::Display, io::ErrorKind, str::FromStr};
+
+use error_plus::{expect_error, impl_context_error};
+
+use crate::{
+    config::{ChewingTsfConfig, ConfigError},
+    shell::user_dir,
+};
+
+/// The `OutputVariant` setting.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ScriptVariant {
+    /// Taiwan Traditional Chinese, not converted
+    #[default]
+    Traditional,
+    /// Simplified Chinese with mainland phrasing
+    Simplified,
+    /// Hong Kong Traditional Chinese
+    HongKong,
+    /// Simplified Chinese keeping the Taiwan phrasing
+    SimplifiedTaiwan,
+}
+
+/// Variants in the order they are cycled, with their names in the config.
+const SCRIPT_VARIANT_NAMES: [(ScriptVariant, &str); 4] = [
+    (ScriptVariant::Traditional, "zh-TW"),
+    (ScriptVariant::Simplified, "zh-CN"),
+    (ScriptVariant::HongKong, "zh-HK"),
+    (ScriptVariant::SimplifiedTaiwan, "zh-Hans-TW"),
+];
+
+impl ScriptVariant {
+    /// The configured variant. Without one the legacy `OutputSimpChinese`
+    /// setting picks Simplified Chinese with Taiwan phrasing.
+    pub fn from_config(cfg: &ChewingTsfConfig) -> Result<ScriptVariant, ConfigError> {
+        if !cfg.output_variant.trim().is_empty() {
+            cfg.output_variant.parse()
+        } else if cfg.output_simp_chinese {
+            Ok(ScriptVariant::SimplifiedTaiwan)
+        } else {
+            Ok(ScriptVariant::Traditional)
+        }
+    }
+    /// The variant after `self` when cycling.
+    pub fn next(self) -> ScriptVariant {
+        let i = SCRIPT_VARIANT_NAMES
+            .iter()
+            .position(|(variant, _)| *variant == self)
+            .unwrap_or_default();
+        SCRIPT_VARIANT_NAMES[(i + 1) % SCRIPT_VARIANT_NAMES.len()].0
+    }
+    /// Switches between Traditional and Simplified Chinese, going back to
+    /// `simplified` when it is a simplified variant.
+    pub fn toggle_simplified(self, simplified: ScriptVariant) -> ScriptVariant {
+        if self.is_simplified() {
+            ScriptVariant::Traditional
+        } else if simplified.is_simplified() {
+            simplified
+        } else {
+            ScriptVariant::SimplifiedTaiwan
+        }
+    }
+    pub fn is_simplified(self) -> bool {
+        matches!(
+            self,
+            ScriptVariant::Simplified | ScriptVariant::SimplifiedTaiwan
+        )
+    }
+    /// Locale whose glyphs Han characters are drawn with.
+    pub fn locale(self) -> &'static str {
+        match self {
+            ScriptVariant::Traditional => "zh-TW",
+            ScriptVariant::Simplified | ScriptVariant::SimplifiedTaiwan => "zh-CN",
+            ScriptVariant::HongKong => "zh-HK",
+        }
+    }
+    /// Name shown when switching to the variant.
+    pub fn label(self) -> &'static str {
+        match self {
+            ScriptVariant::Traditional => "繁體中文",
+            ScriptVariant::Simplified => "简体中文",
+            ScriptVariant::HongKong => "香港繁體",
+            ScriptVariant::SimplifiedTaiwan => "简体中文（台湾用语）",
+        }
+    }
+    /// Mark in the mode indicator, empty for Taiwan Traditional Chinese.
+    pub fn indicator(self) -> &'static str {
+        match self {
+            ScriptVariant::Traditional => "",
+            ScriptVariant::Simplified => "簡",
+            ScriptVariant::HongKong => "港",
+            ScriptVariant::SimplifiedTaiwan => "簡台",
+        }
+    }
+}
+
+impl FromStr for ScriptVariant {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        expect_error("Failed to parse output variant", || {
+            let s = s.trim();
+            SCRIPT_VARIANT_NAMES
+                .iter()
+                .find(|(_, name)| name.eq_ignore_ascii_case(s))
+                .map(|(variant, _)| *variant)
+                .ok_or_else(|| format!("unknown output variant \"{s}\"").into())
+        })
+    }
+}
+
+impl Display for ScriptVariant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (_, name) = SCRIPT_VARIANT_NAMES
+            .iter()
+            .find(|(variant, _)| variant == self)
+            .expect("all variants are named");
+        f.write_str(name)
+    }
+}
+
+/// Conversions the user prefers over the converter's, per variant.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScriptOverrides {
+    /// Source and replacement, longest source first
+    tables: HashMap<ScriptVariant, Vec<(String, String)>>,
+}
+
+impl ScriptOverrides {
+    /// The overrides in the user dir, if any.
+    pub fn load() -> Result<ScriptOverrides, ScriptError> {
+        expect_error("Unable to load conversion overrides", || {
+            let mut overrides = ScriptOverrides::default();
+            match std::fs::read_to_string(user_dir()?.join("conversion_overrides.txt")) {
+                Ok(list) => overrides.apply(&list)?,
+                Err(error) if error.kind() == ErrorKind::NotFound => {}
+                Err(error) => return Err(error.into()),
+            }
+            Ok(overrides)
+        })
+    }
+    /// Applies a table with a source and a replacement per line. Lines
+    /// before any `[variant]` header apply to all converted variants and
+    /// lines starting with `#` are comments.
+    pub fn apply(&mut self, list: &str) -> Result<(), ScriptError> {
+        expect_error("Failed to parse conversion overrides", || {
+            let mut section: Option<ScriptVariant> = None;
+            for (n, line) in list.lines().map(str::trim).enumerate() {
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                    section = Some(name.parse()?);
+                    continue;
+                }
+                let mut fields = line.split_whitespace();
+                let (Some(source), Some(target), None) =
+                    (fields.next(), fields.next(), fields.next())
+                else {
+                    return Err(
+                        format!("line {}: expected a source and a replacement", n + 1).into(),
+                    );
+                };
+                let variants = match section {
+                    Some(variant) => vec![variant],
+                    None => SCRIPT_VARIANT_NAMES
+                        .iter()
+                        .map(|(variant, _)| *variant)
+                        .filter(|variant| *variant != ScriptVariant::Traditional)
+                        .collect(),
+                };
+                for variant in variants {
+                    let table = self.tables.entry(variant).or_default();
+                    table.retain(|(s, _)| s != source);
+                    table.push((source.to_string(), target.to_string()));
+                }
+            }
+            for table in self.tables.values_mut() {
+                table.sort_by_key(|(source, _)| std::cmp::Reverse(source.chars().count()));
+            }
+            Ok(())
+        })
+    }
+    /// Converts `text` to `variant` with `convert`, except for the
+    /// overridden phrases. Taiwan Traditional Chinese is not converted.
+    pub fn convert(
+        &self,
+        variant: ScriptVariant,
+        text: &str,
+        mut convert: impl FnMut(&str) -> String,
+    ) -> String {
+        if variant == ScriptVariant::Traditional {
+            return text.to_string();
+        }
+        let Some(table) = self.tables.get(&variant) else {
+            return convert(text);
+        };
+        let mut converted = String::new();
+        let mut pending = 0;
+        let mut i = 0;
+        while i < text.len() {
+            let rest = &text[i..];
+            if let Some((source, target)) = table.iter().find(|(s, _)| rest.starts_with(s.as_str()))
+            {
+                if pending < i {
+                    converted.push_str(&convert(&text[pending..i]));
+                }
+                converted.push_str(target);
+                i += source.len();
+                pending = i;
+            } else {
+                i += rest.chars().next().map_or(1, char::len_utf8);
+            }
+        }
+        if pending < text.len() {
+            converted.push_str(&convert(&text[pending..]));
+        }
+        converted
+    }
+}
+
+/// A page of candidates converted to the output variant.
+///
+/// Candidates that convert to the same text are shown once, so the shown
+/// index of a candidate may differ from its index in the editor.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConvertedCandidates {
+    /// Candidates as shown
+    pub items: Vec<String>,
+    /// Index in the editor page of each shown candidate
+    sources: Vec<usize>,
+    /// Candidates in the editor page
+    len: usize,
+}
+
+impl ConvertedCandidates {
+    /// Converts `candidates` with `convert`, keeping the first of the
+    /// candidates that convert to the same text.
+    pub fn new(candidates: &[String], mut convert: impl FnMut(&str) -> String) -> Self {
+        let mut converted = ConvertedCandidates {
+            len: candidates.len(),
+            ..Default::default()
+        };
+        for (i, candidate) in candidates.iter().enumerate() {
+            let item = convert(candidate);
+            if !converted.items.contains(&item) {
+                converted.items.push(item);
+                converted.sources.push(i);
+            }
+        }
+        converted
+    }
+    /// Index in the editor page of the candidate shown at `shown`.
+    pub fn source_index(&self, shown: usize) -> Option<usize> {
+        self.sources.get(shown).copied()
+    }
+    /// Index in the editor page of each shown candidate.
+    pub fn sources(&self) -> &[usize] {
+        &self.sources
+    }
+    /// Number of candidates not shown because of duplicates.
+    pub fn hidden(&self) -> usize {
+        self.len - self.items.len()
+    }
+}
+
+impl_context_error!(pub ScriptError);
+
+#[cfg(test)]
+mod tests {
+    use super::{ConvertedCandidates, ScriptOverrides, ScriptVariant};
+    use crate::config::ChewingTsfConfig;
+
+    /// Stands in for the real converter.
+    fn simplify(text: &str) -> String {
+        text.replace('軟', "软").replace('體', "体")
+    }
+
+    #[test]
+    fn parse_variants() {
+        assert_eq!(Some(ScriptVariant::HongKong), "zh-hk".parse().ok());
+        assert_eq!(
+            Some(ScriptVariant::SimplifiedTaiwan),
+            " zh-Hans-TW ".parse().ok()
+        );
+        assert!("zh-Hans".parse::<ScriptVariant>().is_err());
+        assert!("zh-SG".parse::<ScriptVariant>().is_err());
+        for variant in [
+            ScriptVariant::Traditional,
+            ScriptVariant::Simplified,
+            ScriptVariant::HongKong,
+            ScriptVariant::SimplifiedTaiwan,
+        ] {
+            assert_eq!(Some(variant), variant.to_string().parse().ok());
+        }
+    }
+
+    #[test]
+    fn variant_from_config() {
+        let mut cfg = ChewingTsfConfig::default();
+        assert_eq!(
+            ScriptVariant::Traditional,
+            ScriptVariant::from_config(&cfg).unwrap()
+        );
+        cfg.output_simp_chinese = true;
+        assert_eq!(
+            ScriptVariant::SimplifiedTaiwan,
+            ScriptVariant::from_config(&cfg).unwrap()
+        );
+        cfg.output_variant = "zh-HK".to_string();
+        assert_eq!(
+            ScriptVariant::HongKong,
+            ScriptVariant::from_config(&cfg).unwrap()
+        );
+        cfg.output_variant = "zh-XX".to_string();
+        assert!(ScriptVariant::from_config(&cfg).is_err());
+    }
+
+    #[test]
+    fn cycle_and_toggle() {
+        let mut variant = ScriptVariant::Traditional;
+        let mut seen = vec![];
+        for _ in 0..4 {
+            variant = variant.next();
+            seen.push(variant.to_string());
+        }
+        assert_eq!(vec!["zh-CN", "zh-HK", "zh-Hans-TW", "zh-TW"], seen);
+        assert_eq!(
+            ScriptVariant::Simplified,
+            ScriptVariant::Traditional.toggle_simplified(ScriptVariant::Simplified)
+        );
+        assert_eq!(
+            ScriptVariant::SimplifiedTaiwan,
+            ScriptVariant::HongKong.toggle_simplified(ScriptVariant::HongKong)
+        );
+        assert_eq!(
+            ScriptVariant::Traditional,
+            ScriptVariant::Simplified.toggle_simplified(ScriptVariant::Simplified)
+        );
+    }
+
+    #[test]
+    fn convert_with_overrides() {
+        let mut overrides = ScriptOverrides::default();
+        assert_eq!(
+            "软体",
+            overrides.convert(ScriptVariant::Simplified, "軟體", simplify)
+        );
+        overrides
+            .apply("# comment\n軟 軟\n[zh-CN]\n軟體 软件\n")
+            .unwrap();
+        assert_eq!(
+            "用软件寫軟碟",
+            overrides.convert(ScriptVariant::Simplified, "用軟體寫軟碟", simplify)
+        );
+        assert_eq!(
+            "軟体",
+            overrides.convert(ScriptVariant::HongKong, "軟體", simplify)
+        );
+        assert_eq!(
+            "軟體",
+            overrides.convert(ScriptVariant::Traditional, "軟體", simplify)
+        );
+    }
+
+    #[test]
+    fn dedup_converted_candidates() {
+        let candidates: Vec<String> = ["軟體", "软體", "軟件", "軟", "軟體"]
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        let converted = ConvertedCandidates::new(&candidates, simplify);
+        assert_eq!(vec!["软体", "软件", "软"], converted.items);
+        assert_eq!(&[0, 2, 3], converted.sources());
+        assert_eq!(Some(2), converted.source_index(1));
+        assert_eq!(None, converted.source_index(3));
+        assert_eq!(2, converted.hidden());
+
+        let unchanged = ConvertedCandidates::new(&candidates[..4], str::to_string);
+        assert_eq!(candidates[..4], unchanged.items);
+        assert_eq!(Some(3), unchanged.source_index(3));
+        assert_eq!(0, unchanged.hidden());
+    }
+
+    #[test]
+    fn invalid_overrides() {
+        let mut overrides = ScriptOverrides::default();
+        assert!(overrides.apply("[zh-XX]\n").is_err());
+        assert!(overrides.apply("軟體\n").is_err());
+        assert!(overrides.apply("軟體 软件 軟件\n").is_err());
+    }
+}
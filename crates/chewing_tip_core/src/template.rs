@@ -0,0 +1,471 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2026 Kan-Ru Chen
+
+//! Templates of the text inserted by the `text` keybinding action.
+//!
+//! Text is inserted as is except for placeholders in braces and escapes
+//! starting with a backslash:
+//!
+//! - `{date}` and `{time}` insert the current date and time, formatted by
+//!   an optional format like `{date:%Y/%m/%d}`
+//! - `{last}` inserts the phrase committed last
+//! - `{date:%R|fullwidth}` converts the inserted text to full width
+//! - `\{`, `\}`, `\\`, `\n`, `\t` and `\u{3000}` insert the character
+//!
+//! The formats understand `%Y` (year), `%R` (ROC year), `%m` (month), `%d`
+//! (day), `%H` (hour), `%I` (12-hour clock hour), `%M` (minute), `%S`
+//! (second), `%p` (AM or PM), `%a` and `%A` (short and full weekday) and
+//! `%%`. `%-m`, `%-d`, `%-H` and `%-I` leave out the leading zero. For
+//! example `民國{date:%R年%-m月%-d日}` inserts `民國115年10月17日`.
+
+use std::{fmt::Display, str::FromStr};
+
+use error_plus::expect_error;
+use windows::Win32::{
+    Globalization::GetUserDefaultLocaleName, System::SystemInformation::GetLocalTime,
+    System::SystemServices::LOCALE_NAME_MAX_LENGTH,
+};
+
+use crate::config::ConfigError;
+
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+const DEFAULT_TIME_FORMAT: &str = "%H:%M:%S";
+
+/// The year 1 of the Republic of China calendar
+const ROC_EPOCH: i32 = 1911;
+
+/// Local date and time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    /// Days since Sunday
+    pub weekday: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl LocalTime {
+    pub fn now() -> LocalTime {
+        let time = unsafe { GetLocalTime() };
+        LocalTime {
+            year: time.wYear,
+            month: time.wMonth as u8,
+            day: time.wDay as u8,
+            weekday: time.wDayOfWeek as u8,
+            hour: time.wHour as u8,
+            minute: time.wMinute as u8,
+            second: time.wSecond as u8,
+        }
+    }
+}
+
+/// Names used when formatting dates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Locale {
+    /// Full weekday names, from Sunday
+    pub weekdays: [&'static str; 7],
+    pub short_weekdays: [&'static str; 7],
+    /// Names of the morning and the afternoon
+    pub am_pm: [&'static str; 2],
+}
+
+impl Locale {
+    pub const ZH_TW: Locale = Locale {
+        weekdays: [
+            "星期日",
+            "星期一",
+            "星期二",
+            "星期三",
+            "星期四",
+            "星期五",
+            "星期六",
+        ],
+        short_weekdays: ["週日", "週一", "週二", "週三", "週四", "週五", "週六"],
+        am_pm: ["上午", "下午"],
+    };
+    pub const EN_US: Locale = Locale {
+        weekdays: [
+            "Sunday",
+            "Monday",
+            "Tuesday",
+            "Wednesday",
+            "Thursday",
+            "Friday",
+            "Saturday",
+        ],
+        short_weekdays: ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"],
+        am_pm: ["AM", "PM"],
+    };
+
+    /// The locale for a name like `zh-TW`, Chinese names for any Chinese
+    /// locale and English names otherwise.
+    pub fn from_name(name: &str) -> &'static Locale {
+        if name.to_ascii_lowercase().starts_with("zh") {
+            &Locale::ZH_TW
+        } else {
+            &Locale::EN_US
+        }
+    }
+    /// The locale of the user.
+    pub fn user_default() -> &'static Locale {
+        let mut name = [0u16; LOCALE_NAME_MAX_LENGTH as usize];
+        let len = unsafe { GetUserDefaultLocaleName(&mut name) };
+        if len <= 0 {
+            return &Locale::ZH_TW;
+        }
+        Locale::from_name(&String::from_utf16_lossy(&name[..len as usize - 1]))
+    }
+}
+
+/// Everything a template may refer to.
+#[derive(Debug, Clone, Copy)]
+pub struct TemplateContext<'a> {
+    pub now: LocalTime,
+    pub locale: &'a Locale,
+    /// The phrase committed last
+    pub last_commit: &'a str,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Piece {
+    Text(String),
+    Year,
+    RocYear,
+    Month { padded: bool },
+    Day { padded: bool },
+    Hour { padded: bool },
+    Hour12 { padded: bool },
+    Minute,
+    Second,
+    AmPm,
+    Weekday,
+    ShortWeekday,
+    LastCommit,
+    FullWidth(Vec<Piece>),
+}
+
+/// A parsed text template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    source: String,
+    pieces: Vec<Piece>,
+}
+
+impl Template {
+    /// The template as written in the config.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+    /// The text to insert.
+    pub fn expand(&self, ctx: &TemplateContext<'_>) -> String {
+        let mut text = String::new();
+        expand_pieces(&self.pieces, ctx, &mut text);
+        text
+    }
+}
+
+impl FromStr for Template {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        expect_error("Failed to parse text template", || {
+            let mut pieces = vec![];
+            let mut text = String::new();
+            let mut chars = s.chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => text.push(unescape(&mut chars)?),
+                    '{' => {
+                        let mut field = String::new();
+                        loop {
+                            match chars.next() {
+                                Some('}') => break,
+                                Some(c) => field.push(c),
+                                None => return Err("missing closing }".into()),
+                            }
+                        }
+                        if !text.is_empty() {
+                            pieces.push(Piece::Text(std::mem::take(&mut text)));
+                        }
+                        pieces.extend(parse_field(&field)?);
+                    }
+                    '}' => return Err("unexpected }, use \\} to insert it".into()),
+                    c => text.push(c),
+                }
+            }
+            if !text.is_empty() {
+                pieces.push(Piece::Text(text));
+            }
+            Ok(Template {
+                source: s.to_string(),
+                pieces,
+            })
+        })
+    }
+}
+
+impl Display for Template {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+fn unescape(chars: &mut std::str::Chars<'_>) -> Result<char, String> {
+    match chars.next() {
+        Some('n') => Ok('\n'),
+        Some('t') => Ok('\t'),
+        Some(c @ ('\\' | '{' | '}')) => Ok(c),
+        Some('u') => {
+            let rest = chars.as_str();
+            let (hex, after) = rest
+                .strip_prefix('{')
+                .and_then(|rest| rest.split_once('}'))
+                .ok_or("expected \\u{...}")?;
+            let c = u32::from_str_radix(hex, 16)
+                .ok()
+                .and_then(char::from_u32)
+                .ok_or_else(|| format!("invalid unicode escape \\u{{{hex}}}"))?;
+            *chars = after.chars();
+            Ok(c)
+        }
+        Some(c) => Err(format!("unknown escape \\{c}")),
+        None => Err("template ends with \\".to_string()),
+    }
+}
+
+/// Parses the inside of a placeholder, e.g. `date:%Y|fullwidth`.
+fn parse_field(field: &str) -> Result<Vec<Piece>, String> {
+    let mut parts = field.split('|');
+    let head = parts.next().unwrap_or_default();
+    let (name, format) = match head.split_once(':') {
+        Some((name, format)) => (name.trim(), Some(format)),
+        None => (head.trim(), None),
+    };
+    let mut pieces = match (name, format) {
+        ("date", format) => parse_format(format.unwrap_or(DEFAULT_DATE_FORMAT))?,
+        ("time", format) => parse_format(format.unwrap_or(DEFAULT_TIME_FORMAT))?,
+        ("last", None) => vec![Piece::LastCommit],
+        ("last", Some(_)) => return Err("{last} does not take a format".to_string()),
+        (name, _) => return Err(format!("unknown placeholder {{{name}}}")),
+    };
+    for filter in parts {
+        pieces = match filter.trim() {
+            "fullwidth" => vec![Piece::FullWidth(pieces)],
+            filter => return Err(format!("unknown filter {filter}")),
+        };
+    }
+    Ok(pieces)
+}
+
+fn parse_format(format: &str) -> Result<Vec<Piece>, String> {
+    let mut pieces = vec![];
+    let mut text = String::new();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            text.push(c);
+            continue;
+        }
+        let mut spec = chars.next();
+        let padded = spec != Some('-');
+        if !padded {
+            spec = chars.next();
+        }
+        let piece = match spec {
+            Some('%') if padded => {
+                text.push('%');
+                continue;
+            }
+            Some('m') => Piece::Month { padded },
+            Some('d') => Piece::Day { padded },
+            Some('H') => Piece::Hour { padded },
+            Some('I') => Piece::Hour12 { padded },
+            Some('Y') if padded => Piece::Year,
+            Some('R') if padded => Piece::RocYear,
+            Some('M') if padded => Piece::Minute,
+            Some('S') if padded => Piece::Second,
+            Some('p') if padded => Piece::AmPm,
+            Some('A') if padded => Piece::Weekday,
+            Some('a') if padded => Piece::ShortWeekday,
+            Some(c) => {
+                return Err(format!(
+                    "unknown format %{}{c}",
+                    if padded { "" } else { "-" }
+                ));
+            }
+            None => return Err("format ends with %".to_string()),
+        };
+        if !text.is_empty() {
+            pieces.push(Piece::Text(std::mem::take(&mut text)));
+        }
+        pieces.push(piece);
+    }
+    if !text.is_empty() {
+        pieces.push(Piece::Text(text));
+    }
+    Ok(pieces)
+}
+
+fn expand_pieces(pieces: &[Piece], ctx: &TemplateContext<'_>, text: &mut String) {
+    let now = &ctx.now;
+    let number = |value: u8, padded: bool| {
+        if padded {
+            format!("{value:02}")
+        } else {
+            value.to_string()
+        }
+    };
+    for piece in pieces {
+        match piece {
+            Piece::Text(s) => text.push_str(s),
+            Piece::Year => text.push_str(&now.year.to_string()),
+            Piece::RocYear => text.push_str(&(now.year as i32 - ROC_EPOCH).to_string()),
+            Piece::Month { padded } => text.push_str(&number(now.month, *padded)),
+            Piece::Day { padded } => text.push_str(&number(now.day, *padded)),
+            Piece::Hour { padded } => text.push_str(&number(now.hour, *padded)),
+            Piece::Hour12 { padded } => {
+                let hour = match now.hour % 12 {
+                    0 => 12,
+                    hour => hour,
+                };
+                text.push_str(&number(hour, *padded));
+            }
+            Piece::Minute => text.push_str(&number(now.minute, true)),
+            Piece::Second => text.push_str(&number(now.second, true)),
+            Piece::AmPm => text.push_str(ctx.locale.am_pm[usize::from(now.hour >= 12)]),
+            Piece::Weekday => text.push_str(ctx.locale.weekdays[now.weekday as usize % 7]),
+            Piece::ShortWeekday => {
+                text.push_str(ctx.locale.short_weekdays[now.weekday as usize % 7])
+            }
+            Piece::LastCommit => text.push_str(ctx.last_commit),
+            Piece::FullWidth(pieces) => {
+                let mut inner = String::new();
+                expand_pieces(pieces, ctx, &mut inner);
+                text.extend(inner.chars().map(to_fullwidth));
+            }
+        }
+    }
+}
+
+/// The full width form of ASCII characters.
+fn to_fullwidth(c: char) -> char {
+    match c {
+        ' ' => '\u{3000}',
+        '!'..='~' => char::from_u32(c as u32 + 0xfee0).unwrap_or(c),
+        _ => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LocalTime, Locale, Template, TemplateContext};
+
+    const NOW: LocalTime = LocalTime {
+        year: 2026,
+        month: 10,
+        day: 17,
+        weekday: 6,
+        hour: 21,
+        minute: 5,
+        second: 3,
+    };
+
+    fn expand(template: &str, locale: &Locale) -> String {
+        let ctx = TemplateContext {
+            now: NOW,
+            locale,
+            last_commit: "酷音",
+        };
+        template.parse::<Template>().unwrap().expand(&ctx)
+    }
+
+    #[test]
+    fn plain_text() {
+        assert_eq!("新酷音", expand("新酷音", &Locale::ZH_TW));
+        assert_eq!("", expand("", &Locale::ZH_TW));
+    }
+
+    #[test]
+    fn roc_date() {
+        assert_eq!(
+            "民國115年10月17日",
+            expand("民國{date:%R年%-m月%-d日}", &Locale::ZH_TW)
+        );
+        assert_eq!("115/10/17", expand("{date:%R/%m/%d}", &Locale::ZH_TW));
+    }
+
+    #[test]
+    fn date_and_time() {
+        assert_eq!("2026-10-17", expand("{date}", &Locale::ZH_TW));
+        assert_eq!("21:05:03", expand("{time}", &Locale::ZH_TW));
+        assert_eq!("100%", expand("{date:%m0%%}", &Locale::ZH_TW));
+    }
+
+    #[test]
+    fn locale_names() {
+        assert_eq!(
+            "星期六 週六 下午 9:05",
+            expand("{date:%A %a %p %-I:%M}", &Locale::ZH_TW)
+        );
+        assert_eq!(
+            "Saturday Sat PM 09:05",
+            expand("{date:%A %a %p %I:%M}", &Locale::EN_US)
+        );
+        assert_eq!(&Locale::ZH_TW, Locale::from_name("zh-HK"));
+        assert_eq!(&Locale::EN_US, Locale::from_name("en-GB"));
+    }
+
+    #[test]
+    fn fullwidth() {
+        assert_eq!(
+            "民國１１５年",
+            expand("民國{date:%R|fullwidth}年", &Locale::ZH_TW)
+        );
+        assert_eq!(
+            "２０２６　１０",
+            expand("{date:%Y %m|fullwidth}", &Locale::ZH_TW)
+        );
+    }
+
+    #[test]
+    fn last_commit() {
+        assert_eq!("「酷音」", expand("「{last}」", &Locale::ZH_TW));
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(
+            "{date}\n--\t新酷音😀\\",
+            expand("\\{date\\}\\n--\\t新酷音\\u{1F600}\\\\", &Locale::ZH_TW)
+        );
+    }
+
+    #[test]
+    fn source_round_trip() {
+        let source = "民國{date:%R|fullwidth}年\\n{last}";
+        assert_eq!(source, source.parse::<Template>().unwrap().to_string());
+    }
+
+    #[test]
+    fn invalid_templates() {
+        for template in [
+            "{foo}",
+            "{date",
+            "}",
+            "{date:%Q}",
+            "{date:%-Y}",
+            "{date:%}",
+            "{last:%Y}",
+            "{date|bold}",
+            "\\u{110000}",
+            "\\u1234",
+            "\\x",
+            "\\",
+        ] {
+            assert!(template.parse::<Template>().is_err(), "{template}");
+        }
+    }
+}
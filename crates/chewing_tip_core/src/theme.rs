@@ -0,0 +1,555 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2026 Kan-Ru Chen
+
+//! Color themes of the candidate list and notification windows.
+//!
+//! A theme is either one of the built-in themes, the colors configured by the
+//! individual `*_color` settings, or a JSON file in the `themes` directory
+//! under the user dir. Theme files may set a `base` theme and override only
+//! some of the colors:
+//!
+//! ```json
+//! {
+//!     "base": "dark",
+//!     "candidate": { "highlight_bg_color": "8E44ADFF" },
+//!     "notification": { "bg_color": "2B2B2BFF" }
+//! }
+//! ```
+
+use std::{fmt::Display, path::PathBuf, str::FromStr};
+
+use error_plus::{expect_error, impl_context_error};
+use serde::{Deserialize, Serialize};
+
+use crate::{config::ChewingTsfConfig, shell::user_dir};
+
+/// Text whose contrast ratio against its background is below this value is
+/// reported as hard to read.
+const MIN_CONTRAST_RATIO: f64 = 3.0;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CandidateColors {
+    pub fg_color: String,
+    pub bg_color: String,
+    pub highlight_fg_color: String,
+    pub highlight_bg_color: String,
+    pub selkey_color: String,
+    pub border_color: String,
+    pub page_indicator_color: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotificationColors {
+    pub fg_color: String,
+    pub bg_color: String,
+    pub border_color: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Theme {
+    pub candidate: CandidateColors,
+    pub notification: NotificationColors,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuiltinTheme {
+    Light,
+    Dark,
+    HighContrast,
+}
+
+/// The `Theme` setting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ThemeChoice {
+    /// Use the individual color settings
+    Custom,
+    /// Follow the Windows light, dark and high contrast settings
+    Auto,
+    Builtin(BuiltinTheme),
+    /// Name of a theme file without the `.json` extension
+    File(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A problem found by [`validate_theme`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThemeDiagnostic {
+    pub severity: Severity,
+    /// Dotted path of the offending field, empty for the whole file
+    pub field: String,
+    pub message: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ThemeFile {
+    base: Option<String>,
+    #[serde(default)]
+    candidate: CandidateOverrides,
+    #[serde(default)]
+    notification: NotificationOverrides,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CandidateOverrides {
+    fg_color: Option<String>,
+    bg_color: Option<String>,
+    highlight_fg_color: Option<String>,
+    highlight_bg_color: Option<String>,
+    selkey_color: Option<String>,
+    border_color: Option<String>,
+    page_indicator_color: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NotificationOverrides {
+    fg_color: Option<String>,
+    bg_color: Option<String>,
+    border_color: Option<String>,
+}
+
+impl BuiltinTheme {
+    pub const ALL: [BuiltinTheme; 3] = [
+        BuiltinTheme::Light,
+        BuiltinTheme::Dark,
+        BuiltinTheme::HighContrast,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BuiltinTheme::Light => "light",
+            BuiltinTheme::Dark => "dark",
+            BuiltinTheme::HighContrast => "high-contrast",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<BuiltinTheme> {
+        BuiltinTheme::ALL.into_iter().find(|t| t.name() == name)
+    }
+
+    pub fn theme(self) -> Theme {
+        let (candidate, notification) = match self {
+            BuiltinTheme::Light => (
+                [
+                    "000000FF", "FAFAFAFF", "FFFFFFFF", "000000FF", "0000FFFF", "D6D9DBFF",
+                    "000000FF",
+                ],
+                ["000000FF", "FCFBDAFF", "D6D9DBFF"],
+            ),
+            BuiltinTheme::Dark => (
+                [
+                    "E6E6E6FF", "202020FF", "FFFFFFFF", "0063B1FF", "6CB8FFFF", "3A3A3AFF",
+                    "A0A0A0FF",
+                ],
+                ["E6E6E6FF", "2B2B2BFF", "3A3A3AFF"],
+            ),
+            BuiltinTheme::HighContrast => (
+                [
+                    "FFFFFFFF", "000000FF", "000000FF", "1AEBFFFF", "FFFF00FF", "FFFFFFFF",
+                    "FFFFFFFF",
+                ],
+                ["FFFFFFFF", "000000FF", "FFFFFFFF"],
+            ),
+        };
+        let [
+            fg,
+            bg,
+            highlight_fg,
+            highlight_bg,
+            selkey,
+            border,
+            page_indicator,
+        ] = candidate.map(String::from);
+        let [notify_fg, notify_bg, notify_border] = notification.map(String::from);
+        Theme {
+            candidate: CandidateColors {
+                fg_color: fg,
+                bg_color: bg,
+                highlight_fg_color: highlight_fg,
+                highlight_bg_color: highlight_bg,
+                selkey_color: selkey,
+                border_color: border,
+                page_indicator_color: page_indicator,
+            },
+            notification: NotificationColors {
+                fg_color: notify_fg,
+                bg_color: notify_bg,
+                border_color: notify_border,
+            },
+        }
+    }
+}
+
+impl FromStr for ThemeChoice {
+    type Err = ThemeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        expect_error("Invalid theme name", || {
+            Ok(match s {
+                "" | "custom" => ThemeChoice::Custom,
+                "auto" => ThemeChoice::Auto,
+                name => match BuiltinTheme::from_name(name) {
+                    Some(builtin) => ThemeChoice::Builtin(builtin),
+                    None if is_valid_file_name(name) => ThemeChoice::File(name.to_string()),
+                    None => return Err(format!("{name:?} is not a valid theme name").into()),
+                },
+            })
+        })
+    }
+}
+
+impl Display for ThemeChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ThemeChoice::Custom => f.write_str("custom"),
+            ThemeChoice::Auto => f.write_str("auto"),
+            ThemeChoice::Builtin(builtin) => f.write_str(builtin.name()),
+            ThemeChoice::File(name) => f.write_str(name),
+        }
+    }
+}
+
+impl Display for ThemeDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        if self.field.is_empty() {
+            write!(f, "{severity}: {}", self.message)
+        } else {
+            write!(f, "{severity}: {}: {}", self.field, self.message)
+        }
+    }
+}
+
+impl Theme {
+    /// Colors configured by the individual color settings.
+    pub fn from_config(cfg: &ChewingTsfConfig) -> Theme {
+        Theme {
+            candidate: CandidateColors {
+                fg_color: cfg.font_fg_color.clone(),
+                bg_color: cfg.font_bg_color.clone(),
+                highlight_fg_color: cfg.font_highlight_fg_color.clone(),
+                highlight_bg_color: cfg.font_highlight_bg_color.clone(),
+                selkey_color: cfg.font_number_fg_color.clone(),
+                border_color: cfg.cand_list_border_color.clone(),
+                page_indicator_color: cfg.font_fg_color.clone(),
+            },
+            notification: NotificationColors {
+                fg_color: cfg.notify_fg_color.clone(),
+                bg_color: cfg.notify_bg_color.clone(),
+                border_color: cfg.notify_border_color.clone(),
+            },
+        }
+    }
+
+    /// Parses a theme file, rejecting it if [`validate_theme`] finds errors.
+    pub fn from_json(json: &str) -> Result<Theme, ThemeError> {
+        expect_error("Invalid theme file", || {
+            if let Some(error) = validate_theme(json)
+                .into_iter()
+                .find(|diag| diag.severity == Severity::Error)
+            {
+                return Err(error.to_string().into());
+            }
+            let file: ThemeFile = serde_json::from_str(json)?;
+            Ok(file.resolve())
+        })
+    }
+
+    /// Loads the theme file `name` from the user themes directory.
+    pub fn load(name: &str) -> Result<Theme, ThemeError> {
+        expect_error("Unable to load theme", || {
+            let json = std::fs::read_to_string(theme_path(name)?)?;
+            Ok(Theme::from_json(&json)?)
+        })
+    }
+
+    fn colors(&self) -> [(&'static str, &str); 10] {
+        let cand = &self.candidate;
+        let notify = &self.notification;
+        [
+            ("candidate.fg_color", &cand.fg_color),
+            ("candidate.bg_color", &cand.bg_color),
+            ("candidate.highlight_fg_color", &cand.highlight_fg_color),
+            ("candidate.highlight_bg_color", &cand.highlight_bg_color),
+            ("candidate.selkey_color", &cand.selkey_color),
+            ("candidate.border_color", &cand.border_color),
+            ("candidate.page_indicator_color", &cand.page_indicator_color),
+            ("notification.fg_color", &notify.fg_color),
+            ("notification.bg_color", &notify.bg_color),
+            ("notification.border_color", &notify.border_color),
+        ]
+    }
+
+    /// Foreground and background pairs that must stay readable.
+    fn text_pairs(&self) -> [(&'static str, &str, &str); 5] {
+        let cand = &self.candidate;
+        let notify = &self.notification;
+        [
+            ("candidate.fg_color", &cand.fg_color, &cand.bg_color),
+            (
+                "candidate.highlight_fg_color",
+                &cand.highlight_fg_color,
+                &cand.highlight_bg_color,
+            ),
+            ("candidate.selkey_color", &cand.selkey_color, &cand.bg_color),
+            (
+                "candidate.page_indicator_color",
+                &cand.page_indicator_color,
+                &cand.bg_color,
+            ),
+            ("notification.fg_color", &notify.fg_color, &notify.bg_color),
+        ]
+    }
+}
+
+impl ThemeFile {
+    fn base(&self) -> Option<BuiltinTheme> {
+        match &self.base {
+            None => Some(BuiltinTheme::Light),
+            Some(name) => BuiltinTheme::from_name(name),
+        }
+    }
+
+    fn resolve(self) -> Theme {
+        let mut theme = self.base().unwrap_or(BuiltinTheme::Light).theme();
+        let cand = &mut theme.candidate;
+        let overrides = [
+            (&mut cand.fg_color, self.candidate.fg_color),
+            (&mut cand.bg_color, self.candidate.bg_color),
+            (
+                &mut cand.highlight_fg_color,
+                self.candidate.highlight_fg_color,
+            ),
+            (
+                &mut cand.highlight_bg_color,
+                self.candidate.highlight_bg_color,
+            ),
+            (&mut cand.selkey_color, self.candidate.selkey_color),
+            (&mut cand.border_color, self.candidate.border_color),
+            (
+                &mut cand.page_indicator_color,
+                self.candidate.page_indicator_color,
+            ),
+        ];
+        for (color, value) in overrides {
+            if let Some(value) = value {
+                *color = value;
+            }
+        }
+        let notify = &mut theme.notification;
+        let overrides = [
+            (&mut notify.fg_color, self.notification.fg_color),
+            (&mut notify.bg_color, self.notification.bg_color),
+            (&mut notify.border_color, self.notification.border_color),
+        ];
+        for (color, value) in overrides {
+            if let Some(value) = value {
+                *color = value;
+            }
+        }
+        theme
+    }
+}
+
+/// Path of the theme file `name` in the user themes directory.
+pub fn theme_path(name: &str) -> Result<PathBuf, ThemeError> {
+    expect_error("Unable to determine theme path", || {
+        if !is_valid_file_name(name) {
+            return Err(format!("{name:?} is not a valid theme name").into());
+        }
+        Ok(user_dir()?.join("themes").join(format!("{name}.json")))
+    })
+}
+
+/// Checks a theme file and reports every problem found.
+///
+/// Errors make the theme unusable. Warnings point out colors that are
+/// valid but hard to read.
+pub fn validate_theme(json: &str) -> Vec<ThemeDiagnostic> {
+    let file: ThemeFile = match serde_json::from_str(json) {
+        Ok(file) => file,
+        Err(error) => {
+            return vec![ThemeDiagnostic {
+                severity: Severity::Error,
+                field: String::new(),
+                message: error.to_string(),
+            }];
+        }
+    };
+    let mut diagnostics = vec![];
+    if file.base().is_none() {
+        let names: Vec<_> = BuiltinTheme::ALL.iter().map(|t| t.name()).collect();
+        diagnostics.push(ThemeDiagnostic {
+            severity: Severity::Error,
+            field: "base".to_string(),
+            message: format!("unknown theme, expected one of {}", names.join(", ")),
+        });
+    }
+    let theme = file.resolve();
+    for (field, color) in theme.colors() {
+        if parse_color(color).is_none() {
+            diagnostics.push(ThemeDiagnostic {
+                severity: Severity::Error,
+                field: field.to_string(),
+                message: format!("{color:?} is not a RRGGBB or RRGGBBAA color"),
+            });
+        }
+    }
+    for (field, fg, bg) in theme.text_pairs() {
+        if let (Some(fg), Some(bg)) = (parse_color(fg), parse_color(bg)) {
+            let ratio = contrast_ratio(fg, bg);
+            if ratio < MIN_CONTRAST_RATIO {
+                diagnostics.push(ThemeDiagnostic {
+                    severity: Severity::Warning,
+                    field: field.to_string(),
+                    message: format!("low contrast ratio {ratio:.1}:1 against the background"),
+                });
+            }
+        }
+    }
+    diagnostics
+}
+
+fn is_valid_file_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Parses `RRGGBB` or `RRGGBBAA` into RGB components, ignoring alpha.
+fn parse_color(color: &str) -> Option<[u8; 3]> {
+    if !matches!(color.len(), 6 | 8) || !color.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&color[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// WCAG 2 contrast ratio between two colors, from 1 to 21.
+fn contrast_ratio(a: [u8; 3], b: [u8; 3]) -> f64 {
+    fn luminance(rgb: [u8; 3]) -> f64 {
+        let [r, g, b] = rgb.map(|c| {
+            let c = c as f64 / 255.0;
+            if c <= 0.03928 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        });
+        0.2126 * r + 0.7152 * g + 0.0722 * b
+    }
+    let (la, lb) = (luminance(a), luminance(b));
+    (la.max(lb) + 0.05) / (la.min(lb) + 0.05)
+}
+
+impl_context_error!(pub ThemeError);
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn builtin_themes_are_valid() {
+        for builtin in BuiltinTheme::ALL {
+            let json = serde_json::to_string(&builtin.theme()).unwrap();
+            assert_eq!(
+                Vec::<ThemeDiagnostic>::new(),
+                validate_theme(&json),
+                "{}",
+                builtin.name()
+            );
+        }
+    }
+
+    #[test]
+    fn light_theme_matches_default_config() {
+        let theme = Theme::from_config(&ChewingTsfConfig::default());
+        assert_eq!(BuiltinTheme::Light.theme(), theme);
+    }
+
+    #[test]
+    fn parse_theme_choice() {
+        assert_eq!(ThemeChoice::Custom, ThemeChoice::from_str("").unwrap());
+        assert_eq!(ThemeChoice::Auto, ThemeChoice::from_str("auto").unwrap());
+        assert_eq!(
+            ThemeChoice::Builtin(BuiltinTheme::HighContrast),
+            ThemeChoice::from_str("high-contrast").unwrap()
+        );
+        assert_eq!(
+            ThemeChoice::File("solarized_dark".to_string()),
+            ThemeChoice::from_str("solarized_dark").unwrap()
+        );
+        assert!(ThemeChoice::from_str("../evil").is_err());
+    }
+
+    #[test]
+    fn theme_choice_round_trip() {
+        for name in ["custom", "auto", "light", "dark", "high-contrast", "nord"] {
+            assert_eq!(name, ThemeChoice::from_str(name).unwrap().to_string());
+        }
+    }
+
+    #[test]
+    fn theme_file_overrides_base() {
+        let theme = Theme::from_json(
+            r#"{ "base": "dark", "candidate": { "highlight_bg_color": "8E44AD" } }"#,
+        )
+        .unwrap();
+        let mut expected = BuiltinTheme::Dark.theme();
+        expected.candidate.highlight_bg_color = "8E44AD".to_string();
+        assert_eq!(expected, theme);
+    }
+
+    #[test]
+    fn empty_theme_file_is_light() {
+        assert_eq!(BuiltinTheme::Light.theme(), Theme::from_json("{}").unwrap());
+    }
+
+    #[test]
+    fn validate_reports_errors() {
+        let diagnostics =
+            validate_theme(r#"{ "base": "sepia", "notification": { "fg_color": "black" } }"#);
+        let fields: Vec<_> = diagnostics.iter().map(|d| d.field.as_str()).collect();
+        assert_eq!(vec!["base", "notification.fg_color"], fields);
+        assert!(diagnostics.iter().all(|d| d.severity == Severity::Error));
+        assert!(Theme::from_json(r#"{ "base": "sepia" }"#).is_err());
+    }
+
+    #[test]
+    fn validate_rejects_unknown_fields() {
+        let diagnostics = validate_theme(r#"{ "candidate": { "fg_colour": "000000" } }"#);
+        assert_eq!(1, diagnostics.len());
+        assert_eq!(Severity::Error, diagnostics[0].severity);
+        assert!(diagnostics[0].message.contains("fg_colour"));
+    }
+
+    #[test]
+    fn validate_warns_low_contrast() {
+        let json = r#"{ "candidate": { "fg_color": "EEEEEE" } }"#;
+        let diagnostics = validate_theme(json);
+        assert_eq!(1, diagnostics.len());
+        assert_eq!(Severity::Warning, diagnostics[0].severity);
+        assert_eq!("candidate.fg_color", diagnostics[0].field);
+        // Warnings do not prevent using the theme
+        assert!(Theme::from_json(json).is_ok());
+    }
+
+    #[test]
+    fn contrast_ratio_extremes() {
+        assert_eq!(21.0, contrast_ratio([0, 0, 0], [255, 255, 255]));
+        assert_eq!(1.0, contrast_ratio([18, 52, 86], [18, 52, 86]));
+    }
+}
@@ -1,21 +1,37 @@
 use std::{
     io::{BufRead, BufReader, Write},
     ops::ControlFlow,
+    time::Duration,
 };
 
-use chewing_tip_core::ipc::messages::{OnTestKeyDown, OnTestKeyDownReply, Ping, PingReply};
+use chewing_tip_core::ipc::messages::{
+    ListCoveringFonts, ListCoveringFontsReply, LookupReadings, LookupReadingsReply, OnTestKeyDown,
+    OnTestKeyDownReply, Ping, PingReply,
+};
 use chewing_tip_core::ipc::{
-    messages::{CheckUpdate, HideCandidateList, ShowCandidateList, ShowNotification, Stop},
+    messages::{
+        CheckUpdate, HideCandidateList, HideComposition, ShowCandidateList, ShowComposition,
+        ShowNotification, SkipUpdate, SnoozeUpdate, Stop, UnwatchCandidateEvents,
+        WatchCandidateEvents,
+    },
     varlink::{MethodCall, MethodReply},
 };
 use error_plus::{ErrorExt, expect_error, impl_context_error};
 use interprocess::os::windows::named_pipe::{PipeListener, PipeStream, pipe_mode::Bytes};
 use log::{debug, error, warn};
 
+use self::candidate_events::CANDIDATE_EVENTS;
 use crate::{
-    text_service::chewing::TipSession, ui::event_loop::MainLoopHandle, update::check_for_update,
+    text_service::chewing::TipSession,
+    ui::{event_loop::MainLoopHandle, fonts::covering_fonts},
+    update::{check_for_update, skip_offered_update, snooze_update},
 };
 
+pub(crate) mod candidate_events;
+
+/// How often a candidate event stream checks that its client is still there
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+
 pub(crate) fn run_ipc_listener(
     listener: PipeListener<Bytes, Bytes>,
     mh: MainLoopHandle,
@@ -79,24 +95,87 @@ fn ipc_loop_once(
             ShowNotification::METHOD
             | ShowCandidateList::METHOD
             | HideCandidateList::METHOD
+            | ShowComposition::METHOD
+            | HideComposition::METHOD
             | Stop::METHOD => {
                 mh.send(call)?;
                 if !oneway {
                     sender.write_all(c"{}".to_bytes_with_nul())?;
                 }
             }
+            WatchCandidateEvents::METHOD => {
+                let params: WatchCandidateEvents = serde_json::from_value(call.parameters)?;
+                debug!(
+                    "Start streaming candidate events to session {}",
+                    params.session
+                );
+                // Blocks until the session is unwatched or the client is gone
+                let result = CANDIDATE_EVENTS.stream(params.session, HEARTBEAT_INTERVAL, |event| {
+                    let reply = MethodReply {
+                        // Heartbeats have no parameters
+                        parameters: match event {
+                            Some(event) => serde_json::to_value(event)?,
+                            None => serde_json::Value::Null,
+                        },
+                        continues: Some(true),
+                        error: None,
+                    };
+                    sender.write_all(&reply.to_bytes()?)?;
+                    Ok::<_, error_plus::Error>(())
+                });
+                if let Err(error) = result {
+                    debug!("{}", error.error_report());
+                }
+                debug!(
+                    "Stop streaming candidate events to session {}",
+                    params.session
+                );
+                return Ok(ControlFlow::Break(()));
+            }
+            UnwatchCandidateEvents::METHOD => {
+                let params: UnwatchCandidateEvents = serde_json::from_value(call.parameters)?;
+                CANDIDATE_EVENTS.unwatch(params.session);
+                if !oneway {
+                    sender.write_all(c"{}".to_bytes_with_nul())?;
+                }
+            }
             CheckUpdate::METHOD => {
-                check_for_update();
+                let result = check_for_update();
+                debug!("Manual update check finished: {result:?}");
                 if !oneway {
                     sender.write_all(c"{}".to_bytes_with_nul())?;
                 }
             }
+            SkipUpdate::METHOD => {
+                skip_offered_update();
+                if !oneway {
+                    sender.write_all(c"{}".to_bytes_with_nul())?;
+                }
+            }
+            SnoozeUpdate::METHOD => {
+                let params: SnoozeUpdate = serde_json::from_value(call.parameters)?;
+                snooze_update(params.days);
+                if !oneway {
+                    sender.write_all(c"{}".to_bytes_with_nul())?;
+                }
+            }
+            ListCoveringFonts::METHOD => {
+                let params: ListCoveringFonts = serde_json::from_value(call.parameters)?;
+                let families = covering_fonts(&params.text)?;
+                let reply = MethodReply {
+                    parameters: serde_json::to_value(ListCoveringFontsReply { families })?,
+                    continues: None,
+                    error: None,
+                };
+                if !oneway {
+                    sender.write_all(&reply.to_bytes()?)?;
+                }
+            }
             OnTestKeyDown::METHOD => {
                 let params: OnTestKeyDown = serde_json::from_value(call.parameters)?;
                 let handled = tip_session.on_test_keydown(
                     params.is_context_mutable,
                     params.is_composing,
-                    params.shift_key_state,
                     params.event.try_into()?,
                 )?;
                 let reply = MethodReply {
@@ -108,6 +187,18 @@ fn ipc_loop_once(
                     sender.write_all(&reply.to_bytes()?)?;
                 }
             }
+            LookupReadings::METHOD => {
+                let params: LookupReadings = serde_json::from_value(call.parameters)?;
+                let readings = tip_session.lookup_readings(&params.text)?;
+                let reply = MethodReply {
+                    parameters: serde_json::to_value(LookupReadingsReply { readings })?,
+                    continues: None,
+                    error: None,
+                };
+                if !oneway {
+                    sender.write_all(&reply.to_bytes()?)?;
+                }
+            }
             _ => {
                 warn!("Unknown method: {call:?}");
             }
@@ -0,0 +1,220 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2026 Kan-Ru Chen
+
+use std::{
+    collections::HashMap,
+    sync::{
+        LazyLock, Mutex,
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    },
+    time::Duration,
+};
+
+use chewing_tip_core::ipc::values::CandidateEvent;
+
+/// Routes candidate window events to the TIP session that showed the window.
+pub(crate) static CANDIDATE_EVENTS: LazyLock<CandidateEventHub> =
+    LazyLock::new(CandidateEventHub::default);
+
+#[derive(Debug, Default)]
+pub(crate) struct CandidateEventHub {
+    state: Mutex<HubState>,
+}
+
+#[derive(Debug, Default)]
+struct HubState {
+    owner: u64,
+    /// Tells apart the watches of a session, so closing a replaced watch
+    /// does not unwatch the new one
+    generation: u64,
+    watchers: HashMap<u64, (u64, Sender<CandidateEvent>)>,
+}
+
+/// Events of a session, until it is unwatched or watched again.
+#[derive(Debug)]
+pub(crate) struct Watch {
+    session: u64,
+    generation: u64,
+    pub(crate) events: Receiver<CandidateEvent>,
+}
+
+impl CandidateEventHub {
+    /// Registers a watcher for `session`, replacing any previous one.
+    pub(crate) fn watch(&self, session: u64) -> Watch {
+        let (sender, events) = mpsc::channel();
+        let mut generation = 0;
+        if let Ok(mut state) = self.state.lock() {
+            state.generation += 1;
+            generation = state.generation;
+            state.watchers.insert(session, (generation, sender));
+        }
+        Watch {
+            session,
+            generation,
+            events,
+        }
+    }
+    pub(crate) fn unwatch(&self, session: u64) {
+        if let Ok(mut state) = self.state.lock() {
+            state.watchers.remove(&session);
+        }
+    }
+    /// Unwatches the session of `watch` unless it was watched again.
+    pub(crate) fn close(&self, watch: &Watch) {
+        if let Ok(mut state) = self.state.lock()
+            && state
+                .watchers
+                .get(&watch.session)
+                .is_some_and(|(generation, _)| *generation == watch.generation)
+        {
+            state.watchers.remove(&watch.session);
+        }
+    }
+    /// Watches `session` and passes its events to `send`, blocking until the
+    /// session is unwatched or `send` fails.
+    ///
+    /// `send` also gets `None` after `heartbeat` without events, so a client
+    /// that went away without unwatching is noticed.
+    pub(crate) fn stream<E>(
+        &self,
+        session: u64,
+        heartbeat: Duration,
+        mut send: impl FnMut(Option<CandidateEvent>) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let watch = self.watch(session);
+        loop {
+            let event = match watch.events.recv_timeout(heartbeat) {
+                Ok(event) => Some(event),
+                Err(RecvTimeoutError::Timeout) => None,
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            };
+            if let Err(error) = send(event) {
+                self.close(&watch);
+                return Err(error);
+            }
+        }
+    }
+    pub(crate) fn set_owner(&self, session: u64) {
+        if let Ok(mut state) = self.state.lock() {
+            state.owner = session;
+        }
+    }
+    /// Sends `event` to the owner of the candidate window.
+    ///
+    /// Returns false if nobody is watching.
+    pub(crate) fn publish(&self, event: CandidateEvent) -> bool {
+        let Ok(mut state) = self.state.lock() else {
+            return false;
+        };
+        let owner = state.owner;
+        match state.watchers.get(&owner) {
+            Some((_, sender)) if sender.send(event).is_ok() => true,
+            Some(_) => {
+                state.watchers.remove(&owner);
+                false
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chewing_tip_core::ipc::values::CandidateEvent;
+
+    use super::CandidateEventHub;
+
+    const CLICK: CandidateEvent = CandidateEvent::CandidateClicked { index: 1 };
+
+    #[test]
+    fn publish_to_owner() {
+        let hub = CandidateEventHub::default();
+        let a = hub.watch(1);
+        let b = hub.watch(2);
+        hub.set_owner(2);
+        assert!(hub.publish(CLICK));
+        assert_eq!(Ok(CLICK), b.events.try_recv());
+        assert!(a.events.try_recv().is_err());
+    }
+
+    #[test]
+    fn publish_without_watcher() {
+        let hub = CandidateEventHub::default();
+        hub.set_owner(1);
+        assert!(!hub.publish(CLICK));
+    }
+
+    #[test]
+    fn unwatch_closes_stream() {
+        let hub = CandidateEventHub::default();
+        let a = hub.watch(1);
+        hub.unwatch(1);
+        assert!(a.events.recv().is_err());
+    }
+
+    #[test]
+    fn rewatch_replaces_stream() {
+        let hub = CandidateEventHub::default();
+        let old = hub.watch(1);
+        let new = hub.watch(1);
+        hub.set_owner(1);
+        assert!(old.events.recv().is_err());
+        assert!(hub.publish(CLICK));
+        assert_eq!(Ok(CLICK), new.events.try_recv());
+    }
+
+    #[test]
+    fn dropped_watcher_is_removed() {
+        let hub = CandidateEventHub::default();
+        drop(hub.watch(1));
+        hub.set_owner(1);
+        assert!(!hub.publish(CLICK));
+    }
+
+    #[test]
+    fn closing_replaced_watch_keeps_new_one() {
+        let hub = CandidateEventHub::default();
+        let old = hub.watch(1);
+        let new = hub.watch(1);
+        hub.set_owner(1);
+        hub.close(&old);
+        assert!(hub.publish(CLICK));
+        assert_eq!(Ok(CLICK), new.events.try_recv());
+        hub.close(&new);
+        assert!(!hub.publish(CLICK));
+    }
+
+    #[test]
+    fn stream_stops_when_client_is_gone() {
+        let hub = CandidateEventHub::default();
+        hub.set_owner(1);
+        let mut sent = vec![];
+        // The client closed its pipe, so the first heartbeat fails
+        let result = hub.stream(1, Duration::from_millis(1), |event| {
+            sent.push(event);
+            Err("broken pipe")
+        });
+        assert_eq!(Err("broken pipe"), result);
+        assert_eq!(vec![None], sent);
+        assert!(!hub.publish(CLICK));
+    }
+
+    #[test]
+    fn stream_stops_when_unwatched() {
+        let hub = CandidateEventHub::default();
+        hub.set_owner(1);
+        let mut sent = vec![];
+        let result: Result<(), ()> = hub.stream(1, Duration::from_millis(1), |event| {
+            sent.push(event);
+            if event.is_none() {
+                assert!(hub.publish(CLICK));
+                hub.unwatch(1);
+            }
+            Ok(())
+        });
+        assert_eq!(Ok(()), result);
+        assert_eq!(vec![None, Some(CLICK)], sent);
+    }
+}
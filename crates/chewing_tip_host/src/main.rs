@@ -2,7 +2,10 @@
 
 use std::{path::PathBuf, process::Command, thread};
 
-use chewing_tip_core::ipc::named_pipe::{create_pipe_listener, named_pipe_path};
+use chewing_tip_core::{
+    ipc::named_pipe::{create_pipe_listener, named_pipe_path},
+    theme::{Severity, validate_theme},
+};
 use error_plus::{ErrorExt, expect_error};
 use log::{error, info};
 use logforth::record::{Level, LevelFilter};
@@ -14,7 +17,11 @@ use windows::Win32::{
     UI::HiDpi::{DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2, SetProcessDpiAwarenessContext},
 };
 
-use crate::{ipc::run_ipc_listener, ui::event_loop::MainLoop};
+use crate::{
+    ipc::run_ipc_listener,
+    ui::event_loop::MainLoop,
+    update::scheduler::{SystemClock, SystemNetworkStatus, run_update_scheduler},
+};
 
 mod ipc;
 mod text_service;
@@ -22,6 +29,21 @@ mod ui;
 mod ui_elements;
 mod update;
 
+/// Prints problems found in a theme file, failing if it cannot be used.
+fn validate_theme_file(path: &str) -> Result<(), error_plus::Error> {
+    expect_error("Theme validation failed", || {
+        let json = std::fs::read_to_string(path)?;
+        let diagnostics = validate_theme(&json);
+        for diagnostic in &diagnostics {
+            println!("{path}: {diagnostic}");
+        }
+        if diagnostics.iter().any(|d| d.severity == Severity::Error) {
+            return Err(format!("{path} is not a valid theme").into());
+        }
+        Ok(())
+    })
+}
+
 fn main() -> Result<(), error_plus::Error> {
     expect_error("Running chewing_tip_host failed", || {
         if std::env::args().any(|arg| arg == "-d") {
@@ -35,6 +57,15 @@ fn main() -> Result<(), error_plus::Error> {
                 let _ = AttachConsole(ATTACH_PARENT_PROCESS);
             }
         }
+        let args: Vec<String> = std::env::args().collect();
+        if let Some(pos) = args.iter().position(|arg| arg == "--validate-theme") {
+            // Print to the console of the shell we were started from
+            unsafe {
+                let _ = AttachConsole(ATTACH_PARENT_PROCESS);
+            }
+            let path = args.get(pos + 1).ok_or("Missing theme file path")?;
+            return Ok(validate_theme_file(path)?);
+        }
         logforth::starter_log::stdout()
             .filter(LevelFilter::MoreSevereEqual(Level::Debug))
             .apply();
@@ -79,6 +110,9 @@ fn main() -> Result<(), error_plus::Error> {
         info!("Spawn IPC thread");
         thread::spawn(move || run_ipc_listener(listener, mh));
 
+        info!("Spawn update scheduler thread");
+        thread::spawn(|| run_update_scheduler(SystemClock, SystemNetworkStatus));
+
         info!("Starting main loop");
         main_loop.run();
 
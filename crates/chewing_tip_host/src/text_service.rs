@@ -1,3 +1,2 @@
 pub(crate) mod chewing;
-pub(crate) mod keybind;
 pub(crate) mod keyevent;
@@ -1,3 +1,5 @@
+use std::time::{Duration, Instant};
+
 use chewing::{
     conversion::{ChewingEngine, FuzzyChewingEngine, SimpleEngine},
     dictionary::{DEFAULT_DICT_NAMES, LookupStrategy},
@@ -7,28 +9,41 @@ use chewing::{
     },
     input::{
         KeyState,
-        keysym::{SYM_CAPSLOCK, SYM_LEFTSHIFT, SYM_RIGHTSHIFT, SYM_SPACE},
+        keysym::{SYM_CAPSLOCK, SYM_SPACE},
     },
 };
 use chewing_tip_core::{
+    action::{ActionHandler, dispatch},
     config::{ChewingTsfConfig, Config},
-    ipc::values::IpcShiftKeyState,
+    keybind::{EditState, InputState, KeyMatch, KeybindingMatcher, parse_keybindings},
+    lang_toggle::{LangToggle, ToggleMatch},
+    reading::{ReadingDictionaries, Readings, load_dictionaries},
+    script::ScriptVariant,
     shell::{program_dir, user_dir},
 };
 use error_plus::{ErrorExt, expect_error, impl_context_error};
 
-use crate::text_service::{keybind::Keybinding, keyevent::SystemKeyboardEvent};
+use crate::text_service::keyevent::SystemKeyboardEvent;
 
 #[derive(Debug)]
 pub(crate) struct TipSession {
     // FIXME: use global override
-    output_simp_chinese: bool,
+    output_variant: ScriptVariant,
     // FIXME: use global cfg
     cfg: Config,
     lang_mode: TsfLangMode,
     kbtype: KeyboardLayoutCompat,
-    keybindings: Vec<Keybinding>,
+    keybindings: KeybindingMatcher,
+    lang_toggle: LangToggle,
+    /// Keys typed since the composition started, for `commit_raw_keys`
+    raw_keys: String,
+    /// Text waiting to be committed to the document
+    commit: String,
+    /// Text committed last by the editor, for `{last}` in text templates
+    last_commit: String,
     chewing_editor: Editor,
+    /// Loaded on the first reading lookup
+    reading_dicts: Option<ReadingDictionaries>,
 }
 
 impl TipSession {
@@ -43,12 +58,17 @@ impl TipSession {
         });
         let editor = Editor::chewing(None, None, DEFAULT_DICT_NAMES);
         TipSession {
-            output_simp_chinese: cfg.chewing_tsf.output_simp_chinese,
+            output_variant: configured_variant(&cfg.chewing_tsf),
             cfg,
             lang_mode: TsfLangMode::English,
             kbtype: KeyboardLayoutCompat::Default,
-            keybindings: vec![],
+            keybindings: KeybindingMatcher::default(),
+            lang_toggle: LangToggle::default(),
+            raw_keys: String::new(),
+            commit: String::new(),
+            last_commit: String::new(),
             chewing_editor: editor,
+            reading_dicts: None,
         }
     }
     pub(crate) fn init_chewing_context(&mut self) {
@@ -69,7 +89,7 @@ impl TipSession {
     fn apply_init_config(&mut self) -> Result<(), TipError> {
         expect_error("Failed to apply initial config", || {
             let cfg = &self.cfg.chewing_tsf;
-            self.output_simp_chinese = cfg.output_simp_chinese;
+            self.output_variant = configured_variant(cfg);
             self.chewing_editor.set_editor_options(|opt| {
                 if self.cfg.chewing_tsf.default_full_space {
                     opt.character_form = CharacterForm::Fullwidth;
@@ -102,12 +122,20 @@ impl TipSession {
             self.kbtype = KeyboardLayoutCompat::try_from(cfg.keyboard_layout as u8)
                 .unwrap_or(KeyboardLayoutCompat::Default);
             self.chewing_editor = build_editor_from_cfg(cfg)?;
-            let keybindings = cfg
-                .keybind
-                .iter()
-                .filter_map(|kb| Keybinding::try_from(kb).ok())
-                .collect();
-            self.keybindings = keybindings;
+            // Reload with the editor to pick up new dictionary files
+            self.reading_dicts = None;
+            let (keybindings, issues) = parse_keybindings(cfg);
+            for issue in issues {
+                log::error!("{issue}");
+            }
+            self.keybindings = KeybindingMatcher::new(
+                keybindings,
+                Duration::from_millis(cfg.shift_key_sensitivity as u64),
+            );
+            self.lang_toggle = LangToggle::from_config(cfg).unwrap_or_else(|error| {
+                log::error!("{}", error.error_report());
+                LangToggle::default()
+            });
             Ok(())
         })
     }
@@ -124,7 +152,6 @@ impl TipSession {
         &mut self,
         is_context_mutable: bool,
         is_composing: bool,
-        shift_key_state: IpcShiftKeyState,
         ev: SystemKeyboardEvent,
     ) -> Result<bool, TipError> {
         expect_error("Failed to handle OnTestKeyDown", || {
@@ -134,6 +161,8 @@ impl TipSession {
 
             let evt = ev.to_keyboard_event(self.cfg.chewing_tsf.simulate_english_layout);
             let simulate_english_layout = self.cfg.chewing_tsf.simulate_english_layout != 0;
+            let press = ev.to_key_press(self.cfg.chewing_tsf.simulate_english_layout);
+            let lang_toggle = self.lang_toggle.test_key_down(press, Instant::now());
             //
             // Step 1. apply any config changes
             //
@@ -147,24 +176,29 @@ impl TipSession {
             if self.lang_mode.is_disabled() {
                 return Ok(false);
             }
+            // Also tracks modifier taps, so test before any early return
+            let keybinding = self.keybindings.test_key_down(
+                self.input_state(is_composing),
+                press,
+                Instant::now(),
+            );
             //
-            // Step 2.1 handle switch lang with Shift
+            // Step 2.1 handle the language toggle key
             //
-            if (evt.ksym == SYM_LEFTSHIFT || evt.ksym == SYM_RIGHTSHIFT)
-                && self.cfg.chewing_tsf.switch_lang_with_shift
-                && matches!(shift_key_state, IpcShiftKeyState::Up)
-            {
-                return Ok(false);
+            match lang_toggle {
+                ToggleMatch::None => {}
+                ToggleMatch::Tap => return Ok(false),
+                ToggleMatch::Press => return Ok(true),
             }
             //
             // Step 2.2 handle any keybindings
             //
-            if self.keybindings.iter().any(|kb| kb.matches(&evt)) {
+            if keybinding != KeyMatch::None {
                 return Ok(true);
             }
             //
             // Step 2.3 ignore CapsLock if disabled
-            if evt.ksym == SYM_CAPSLOCK && !self.cfg.chewing_tsf.enable_caps_lock {
+            if evt.ksym == SYM_CAPSLOCK && !self.caps_lock_locks_lang() {
                 return Ok(false);
             }
             //
@@ -195,7 +229,7 @@ impl TipSession {
                     return Ok(false);
                 }
             }
-            if self.cfg.chewing_tsf.enable_caps_lock
+            if self.caps_lock_locks_lang()
                 && !self.cfg.chewing_tsf.lock_chinese_on_caps_lock
                 && evt.ksym.is_unicode()
             {
@@ -237,14 +271,157 @@ impl TipSession {
         })
     }
     pub(crate) fn on_keydown(&mut self, ev: SystemKeyboardEvent) -> Result<bool, TipError> {
+        let press = ev.to_key_press(self.cfg.chewing_tsf.simulate_english_layout);
+        if self.lang_toggle.key_down(press, Instant::now()) {
+            self.switch_lang_with_key()?;
+            return Ok(true);
+        }
+        let state = self.input_state(!self.chewing_editor.is_empty());
+        match self.keybindings.key_down(state, press, Instant::now()) {
+            KeyMatch::None => {}
+            KeyMatch::Pending => return Ok(true),
+            KeyMatch::Complete(action) => {
+                if dispatch(self, &action)? {
+                    return Ok(true);
+                }
+            }
+        }
         Ok(true)
     }
     pub(crate) fn on_test_keyup(&mut self, ev: SystemKeyboardEvent) -> Result<bool, TipError> {
         Ok(true)
     }
     pub(crate) fn on_keyup(&mut self, ev: SystemKeyboardEvent) -> Result<bool, TipError> {
+        let evt = ev.to_keyboard_event(self.cfg.chewing_tsf.simulate_english_layout);
+        let state = self.input_state(!self.chewing_editor.is_empty());
+        if let Some(action) = self.keybindings.key_up(state, evt.ksym, Instant::now()) {
+            dispatch(self, &action)?;
+        }
+        if self.lang_toggle.key_up(evt.ksym, Instant::now()) {
+            self.switch_lang_with_key()?;
+        }
         Ok(true)
     }
+    /// Switches the language after the toggle key, unless CapsLock locks it.
+    fn switch_lang_with_key(&mut self) -> Result<(), TipError> {
+        if self.caps_lock_locks_lang() {
+            return Ok(());
+        }
+        self.toggle_language()
+    }
+    /// Whether the CapsLock state decides the language. CapsLock as the
+    /// toggle key only switches it.
+    fn caps_lock_locks_lang(&self) -> bool {
+        self.cfg.chewing_tsf.enable_caps_lock && !self.lang_toggle.uses_caps_lock()
+    }
+    /// Zhuyin readings of `text` in the system and user dictionaries.
+    pub(crate) fn lookup_readings(&mut self, text: &str) -> Result<Vec<Readings>, TipError> {
+        expect_error("Failed to look up readings", || {
+            let dicts = match &mut self.reading_dicts {
+                Some(dicts) => dicts,
+                slot @ None => slot.insert(load_dictionaries()?),
+            };
+            Ok(dicts.lookup(Some(&*self.chewing_editor.user_dict()), text))
+        })
+    }
+    /// The state keybindings are scoped to.
+    fn input_state(&self, is_composing: bool) -> InputState {
+        let edit = if self.chewing_editor.is_selecting() {
+            EditState::Selecting
+        } else if is_composing {
+            EditState::Composing
+        } else {
+            EditState::Idle
+        };
+        InputState {
+            edit,
+            english: self.lang_mode == TsfLangMode::English,
+        }
+    }
+}
+
+impl ActionHandler for TipSession {
+    type Error = TipError;
+
+    fn toggle_language(&mut self) -> Result<(), TipError> {
+        self.lang_mode = match self.lang_mode {
+            TsfLangMode::English => TsfLangMode::Chinese,
+            TsfLangMode::Chinese => TsfLangMode::English,
+            TsfLangMode::DisabledEnglish => TsfLangMode::DisabledChinese,
+            TsfLangMode::DisabledChinese => TsfLangMode::DisabledEnglish,
+        };
+        self.chewing_editor.clear_syllable_editor();
+        Ok(())
+    }
+    fn toggle_shape(&mut self) -> Result<(), TipError> {
+        self.chewing_editor.set_editor_options(|opt| {
+            opt.character_form = match opt.character_form {
+                CharacterForm::Fullwidth => CharacterForm::Halfwidth,
+                CharacterForm::Halfwidth => CharacterForm::Fullwidth,
+            }
+        });
+        Ok(())
+    }
+    fn toggle_simplified_chinese(&mut self) -> Result<(), TipError> {
+        self.output_variant = self
+            .output_variant
+            .toggle_simplified(configured_variant(&self.cfg.chewing_tsf));
+        Ok(())
+    }
+    fn set_output_variant(&mut self, variant: ScriptVariant) -> Result<(), TipError> {
+        self.output_variant = variant;
+        Ok(())
+    }
+    fn output_variant(&self) -> ScriptVariant {
+        self.output_variant
+    }
+    fn set_keyboard_layout(&mut self, layout: KeyboardLayoutCompat) -> Result<(), TipError> {
+        self.kbtype = layout;
+        self.chewing_editor
+            .set_syllable_editor(syl_editor_from_kbtype(layout));
+        Ok(())
+    }
+    fn keyboard_layout(&self) -> KeyboardLayoutCompat {
+        self.kbtype
+    }
+    fn toggle_auto_learn(&mut self) -> Result<(), TipError> {
+        self.chewing_editor.set_editor_options(|opt| {
+            opt.disable_auto_learn_phrase = !opt.disable_auto_learn_phrase;
+        });
+        Ok(())
+    }
+    fn commit_raw_keys(&mut self) -> Result<(), TipError> {
+        self.commit.push_str(&self.raw_keys);
+        self.clear_buffer()
+    }
+    fn clear_buffer(&mut self) -> Result<(), TipError> {
+        self.raw_keys.clear();
+        self.chewing_editor.clear();
+        Ok(())
+    }
+    fn commit_text(&mut self, text: &str) -> Result<(), TipError> {
+        expect_error("Failed to commit text", || {
+            if !self.chewing_editor.is_empty() {
+                self.chewing_editor.commit()?;
+                self.last_commit = self.chewing_editor.display_commit().to_owned();
+                self.commit.push_str(&self.last_commit);
+                self.chewing_editor.ack();
+            }
+            self.commit.push_str(text);
+            self.raw_keys.clear();
+            Ok(())
+        })
+    }
+    fn last_commit(&self) -> &str {
+        &self.last_commit
+    }
+}
+
+fn configured_variant(cfg: &ChewingTsfConfig) -> ScriptVariant {
+    ScriptVariant::from_config(cfg).unwrap_or_else(|error| {
+        log::error!("{}", error.error_report());
+        ScriptVariant::default()
+    })
 }
 
 fn build_editor_from_cfg(cfg: &ChewingTsfConfig) -> Result<Editor, TipError> {
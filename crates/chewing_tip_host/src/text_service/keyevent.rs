@@ -9,9 +9,13 @@ use chewing::input::keymap::{
     INVERTED_DVORAK_MAP, INVERTED_QGMLWY_MAP, INVERTED_WORKMAN_MAP, map_keycode,
 };
 use chewing::input::{KeyboardEvent, keysym::*};
-use chewing_tip_core::ipc::values::IpcKeyEvent;
+use chewing_tip_core::{
+    ipc::values::IpcKeyEvent,
+    keybind::{KeyPress, Modifiers},
+};
 use windows::Win32::UI::Input::KeyboardAndMouse::{
-    VIRTUAL_KEY, VK_CAPITAL, VK_CONTROL, VK_LWIN, VK_MENU, VK_NUMLOCK, VK_SHIFT,
+    VIRTUAL_KEY, VK_CAPITAL, VK_CONTROL, VK_LCONTROL, VK_LMENU, VK_LSHIFT, VK_LWIN, VK_MENU,
+    VK_NUMLOCK, VK_RCONTROL, VK_RMENU, VK_RSHIFT, VK_RWIN, VK_SHIFT,
 };
 
 #[derive(Debug, Clone, Copy)]
@@ -45,6 +49,28 @@ impl SystemKeyboardEvent {
     fn is_key_toggled(&self, vk: VIRTUAL_KEY) -> bool {
         self.key_state[vk.0 as usize] & 1 != 0
     }
+    fn modifiers(&self) -> Modifiers {
+        [
+            (VK_LCONTROL, Modifiers::LEFT_CTRL),
+            (VK_RCONTROL, Modifiers::RIGHT_CTRL),
+            (VK_LSHIFT, Modifiers::LEFT_SHIFT),
+            (VK_RSHIFT, Modifiers::RIGHT_SHIFT),
+            (VK_LMENU, Modifiers::LEFT_ALT),
+            (VK_RMENU, Modifiers::RIGHT_ALT),
+            (VK_LWIN, Modifiers::LEFT_SUPER),
+            (VK_RWIN, Modifiers::RIGHT_SUPER),
+        ]
+        .into_iter()
+        .filter(|&(vk, _)| self.is_key_down(vk))
+        .fold(Modifiers::NONE, |held, (_, modifier)| held | modifier)
+    }
+    /// The key press for matching keybindings.
+    pub(crate) fn to_key_press(self, kbtype: i32) -> KeyPress {
+        KeyPress {
+            ksym: self.to_keyboard_event(kbtype).ksym,
+            modifiers: self.modifiers(),
+        }
+    }
     pub(crate) fn to_keyboard_event(self, kbtype: i32) -> KeyboardEvent {
         let keycode = SCANCODE_MAP
             .binary_search_by_key(&self.scan_code, |&(w, _)| w)
@@ -66,6 +92,13 @@ impl SystemKeyboardEvent {
                     Keysym(self.ascii_code as u32)
                 }
             });
+        // Windows reports both sides of a modifier with the same virtual key
+        let keysym = match (keysym, keycode.0) {
+            (SYM_LEFTSHIFT, 62) => SYM_RIGHTSHIFT,
+            (SYM_LEFTCTRL, 105) => SYM_RIGHTCTRL,
+            (SYM_LEFTALT, 108) => SYM_RIGHTALT,
+            _ => keysym,
+        };
         KeyboardEvent::builder()
             .code(keycode)
             .ksym(keysym)
@@ -213,6 +246,7 @@ const VKEY_MAP: &[(u16, Keysym)] = &[
     (0x26, SYM_UP),
     (0x27, SYM_RIGHT),
     (0x28, SYM_DOWN),
+    (0x2D, SYM_INSERT),
     (0x2E, SYM_DELETE),
     (0x5B, SYM_LEFTMETA),
     (0x5C, SYM_RIGHTMETA),
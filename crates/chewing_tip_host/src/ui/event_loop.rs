@@ -1,17 +1,20 @@
 use std::{
     rc::Rc,
     sync::mpsc::{Receiver, SyncSender, sync_channel},
-    time::Duration,
 };
 
 use chewing_tip_core::ipc::{
-    messages::{HideCandidateList, ShowCandidateList, ShowNotification, Stop},
+    messages::{
+        HideCandidateList, HideComposition, ShowCandidateList, ShowComposition, ShowNotification,
+        Stop,
+    },
+    values::ScreenRect,
     varlink::MethodCall,
 };
 use error_plus::{ErrorExt, expect_error};
 use log::{debug, error, info, warn};
 use windows::Win32::{
-    Foundation::{HWND, LPARAM, LRESULT, WPARAM},
+    Foundation::{HWND, LPARAM, LRESULT, RECT, WPARAM},
     System::{LibraryLoader::GetModuleHandleW, Threading::GetCurrentThreadId},
     UI::WindowsAndMessaging::{
         CreateWindowExW, DefWindowProcW, DispatchMessageW, GetMessageW, HCURSOR, HWND_DESKTOP, MSG,
@@ -23,10 +26,12 @@ use windows::Win32::{
 use windows_core::{HSTRING, PCWSTR, w};
 
 use crate::{
-    ui::{UiError, gfx::color_s},
+    ipc::candidate_events::CANDIDATE_EVENTS,
+    ui::{UiError, fonts::DEFAULT_LOCALE, gfx::color_s},
     ui_elements::{
         candidate_list::{CandidateList, CandidateListModel},
-        notification::{Notification, NotificationModel},
+        composition_window::{CompositionModel, CompositionWindow},
+        notification::Notification,
     },
 };
 
@@ -39,6 +44,7 @@ pub(crate) struct MainLoop {
     sender: SyncSender<MethodCall>,
 
     candidate_list: Rc<CandidateList>,
+    composition: Rc<CompositionWindow>,
     notification: Rc<Notification>,
 }
 
@@ -53,6 +59,7 @@ impl MainLoop {
         let hinst = unsafe { GetModuleHandleW(None).unwrap_or_default() };
         let _ = Notification::window_register_class(hinst.into());
         let _ = CandidateList::window_register_class(hinst.into());
+        let _ = CompositionWindow::window_register_class(hinst.into());
 
         unsafe {
             let wc = WNDCLASSEXW {
@@ -86,6 +93,7 @@ impl MainLoop {
 
         let notification = Notification::new().expect("failed to create notification window");
         let candidate_list = CandidateList::new().expect("failed to create candidate list window");
+        let composition = CompositionWindow::new().expect("failed to create composition window");
 
         let (sender, receiver) = sync_channel(130);
         MainLoop {
@@ -93,6 +101,7 @@ impl MainLoop {
             sender,
             notification,
             candidate_list,
+            composition,
         }
     }
     pub(crate) fn get_handle(&self) -> MainLoopHandle {
@@ -177,27 +186,35 @@ impl MainLoop {
             match cmd.method.as_str() {
                 ShowNotification::METHOD => {
                     let params: ShowNotification = serde_json::from_value(cmd.parameters)?;
-                    self.notification.set_model(NotificationModel {
-                        text: HSTRING::from(params.text),
-                        font_family: HSTRING::from(params.font_family),
-                        font_size: params.font_size,
-                        fg_color: color_s(&params.fg_color),
-                        bg_color: color_s(&params.bg_color),
-                        border_color: color_s(&params.border_color),
-                    });
-                    self.notification
-                        .set_position(params.position.x, params.position.y);
-                    self.notification.show();
-                    self.notification.set_timer(Duration::from_millis(500));
+                    self.notification.push(params);
                 }
                 ShowCandidateList::METHOD => {
                     let params: ShowCandidateList = serde_json::from_value(cmd.parameters)?;
+                    CANDIDATE_EVENTS.set_owner(params.session);
+                    // Older TIPs do not send the page indicator color
+                    let page_indicator_color = if params.page_indicator_color.is_empty() {
+                        &params.fg_color
+                    } else {
+                        &params.page_indicator_color
+                    };
+                    // Keep the floating preedit visible next to the candidates
+                    let composition = self.composition.visible_rect();
+                    let caret = match (params.caret, composition) {
+                        (Some(caret), Some(rect)) => Some(union_rect(caret, rect)),
+                        (caret, _) => caret,
+                    };
+                    let y = match composition {
+                        Some(rect) => params.position.y.max(rect.bottom),
+                        None => params.position.y,
+                    };
                     self.candidate_list.set_model(CandidateListModel {
                         items: params.items,
                         selkeys: params.selkeys,
                         total_page: params.total_page,
                         current_page: params.current_page,
                         font_family: HSTRING::from(params.font_family),
+                        font_fallback: params.font_fallback,
+                        locale: text_locale(&params.locale),
                         font_size: params.font_size,
                         cand_per_row: params.cand_per_row,
                         use_cursor: params.use_cursor,
@@ -208,15 +225,47 @@ impl MainLoop {
                         highlight_fg_color: color_s(&params.highlight_fg_color),
                         highlight_bg_color: color_s(&params.highlight_bg_color),
                         border_color: color_s(&params.border_color),
+                        page_indicator_color: color_s(page_indicator_color),
+                        layout: params.layout,
+                        show_page_arrows: params.show_page_arrows,
+                        title: params.title,
+                        compact: params.compact,
+                        annotations: params.annotations,
+                        hover: None,
+                        caret,
                     });
-                    self.candidate_list
-                        .set_position(params.position.x, params.position.y);
+                    self.candidate_list.set_position(params.position.x, y);
                     self.candidate_list.show();
                 }
                 HideCandidateList::METHOD => {
                     let _params: HideCandidateList = serde_json::from_value(cmd.parameters)?;
                     self.candidate_list.hide();
                 }
+                ShowComposition::METHOD => {
+                    let params: ShowComposition = serde_json::from_value(cmd.parameters)?;
+                    self.composition.set_model(CompositionModel {
+                        preedit: params.preedit,
+                        segments: params.segments,
+                        bopomofo: params.bopomofo,
+                        cursor: params.cursor,
+                        font_family: HSTRING::from(params.font_family),
+                        font_fallback: params.font_fallback,
+                        locale: text_locale(&params.locale),
+                        font_size: params.font_size,
+                        fg_color: color_s(&params.fg_color),
+                        bg_color: color_s(&params.bg_color),
+                        highlight_bg_color: color_s(&params.highlight_bg_color),
+                        border_color: color_s(&params.border_color),
+                        caret: params.caret,
+                    });
+                    self.composition
+                        .set_position(params.position.x, params.position.y);
+                    self.composition.show();
+                }
+                HideComposition::METHOD => {
+                    let _params: HideComposition = serde_json::from_value(cmd.parameters)?;
+                    self.composition.hide();
+                }
                 _ => {
                     warn!("Unknown method: {cmd:?}");
                 }
@@ -225,3 +274,22 @@ impl MainLoop {
         })
     }
 }
+
+/// Older TIPs do not send the locale of the text.
+fn text_locale(locale: &str) -> HSTRING {
+    if locale.is_empty() {
+        HSTRING::from(DEFAULT_LOCALE)
+    } else {
+        HSTRING::from(locale)
+    }
+}
+
+/// Smallest rectangle covering both the caret and the window at `rect`.
+fn union_rect(caret: ScreenRect, rect: RECT) -> ScreenRect {
+    ScreenRect {
+        left: caret.left.min(rect.left),
+        top: caret.top.min(rect.top),
+        right: caret.right.max(rect.right),
+        bottom: caret.bottom.max(rect.bottom),
+    }
+}
@@ -0,0 +1,191 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2026 Kan-Ru Chen
+
+//! Font fallback and font coverage queries.
+
+use error_plus::expect_error;
+use windows::Win32::Graphics::DirectWrite::{
+    DWRITE_FACTORY_TYPE_SHARED, DWRITE_FONT_STRETCH_NORMAL, DWRITE_FONT_STYLE_NORMAL,
+    DWRITE_FONT_WEIGHT_NORMAL, DWRITE_UNICODE_RANGE, DWriteCreateFactory, IDWriteFactory1,
+    IDWriteFactory2, IDWriteFontCollection, IDWriteFontFallback, IDWriteLocalizedStrings,
+};
+use windows::core::{BOOL, HSTRING, Interface, PCWSTR, w};
+
+use crate::ui::UiError;
+
+/// Locale of the text when the client does not send one
+pub(crate) const DEFAULT_LOCALE: &str = "zh-TW";
+
+/// Builds a fallback that tries `families` in order before the system
+/// fallback. Returns `None` when the system fallback alone is enough.
+pub(crate) fn font_fallback(
+    factory: &IDWriteFactory1,
+    families: &[String],
+    locale: &HSTRING,
+) -> Result<Option<IDWriteFontFallback>, UiError> {
+    expect_error("Failed to build font fallback", || {
+        if families.is_empty() {
+            return Ok(None);
+        }
+        let factory: IDWriteFactory2 = factory.cast()?;
+        let names: Vec<HSTRING> = families.iter().map(HSTRING::from).collect();
+        let names: Vec<*const u16> = names.iter().map(|name| name.as_ptr()).collect();
+        let all = DWRITE_UNICODE_RANGE {
+            first: 0,
+            last: char::MAX.into(),
+        };
+        unsafe {
+            let builder = factory.CreateFontFallbackBuilder()?;
+            builder.AddMapping(&[all], &names, None, locale, PCWSTR::null(), 1.0)?;
+            builder.AddMappings(&factory.GetSystemFontFallback()?)?;
+            Ok(Some(builder.CreateFontFallback()?))
+        }
+    })
+}
+
+/// Installed font families that have glyphs for every character of `text`.
+pub(crate) fn covering_fonts(text: &str) -> Result<Vec<String>, UiError> {
+    expect_error("Failed to list fonts covering text", || {
+        let mut families = vec![];
+        unsafe {
+            let factory: IDWriteFactory2 = DWriteCreateFactory(DWRITE_FACTORY_TYPE_SHARED)?;
+            let mut collection: Option<IDWriteFontCollection> = None;
+            factory.GetSystemFontCollection(&mut collection, false)?;
+            let collection = collection.ok_or("No system font collection")?;
+            for i in 0..collection.GetFontFamilyCount() {
+                let family = collection.GetFontFamily(i)?;
+                let font = family.GetFirstMatchingFont(
+                    DWRITE_FONT_WEIGHT_NORMAL,
+                    DWRITE_FONT_STRETCH_NORMAL,
+                    DWRITE_FONT_STYLE_NORMAL,
+                )?;
+                let name = localized_name(&family.GetFamilyNames()?)?;
+                let has_char = move |c: char| {
+                    font.HasCharacter(c.into())
+                        .is_ok_and(|has_char: BOOL| has_char.as_bool())
+                };
+                families.push((name, has_char));
+            }
+        }
+        Ok(covering_families(families, text))
+    })
+}
+
+/// The Traditional Chinese name of a font family, or its first name.
+fn localized_name(names: &IDWriteLocalizedStrings) -> Result<String, UiError> {
+    expect_error("Failed to read font family name", || unsafe {
+        let mut index = 0;
+        let mut exists = BOOL::default();
+        names.FindLocaleName(w!("zh-tw"), &mut index, &mut exists)?;
+        if !exists.as_bool() {
+            names.FindLocaleName(w!("en-us"), &mut index, &mut exists)?;
+        }
+        if !exists.as_bool() {
+            index = 0;
+        }
+        let mut name = vec![0; names.GetStringLength(index)? as usize + 1];
+        names.GetString(index, &mut name)?;
+        name.pop();
+        Ok(String::from_utf16_lossy(&name))
+    })
+}
+
+/// Names of the families with a glyph for every visible character of `text`,
+/// sorted and without duplicates.
+fn covering_families<F>(families: impl IntoIterator<Item = (String, F)>, text: &str) -> Vec<String>
+where
+    F: Fn(char) -> bool,
+{
+    let chars: Vec<char> = text.chars().filter(|&c| needs_glyph(c)).collect();
+    let mut names: Vec<String> = families
+        .into_iter()
+        .filter(|(_, has_char)| chars.iter().all(|&c| has_char(c)))
+        .map(|(name, _)| name)
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Whether `c` is drawn on its own rather than modifying its neighbours.
+fn needs_glyph(c: char) -> bool {
+    !(c.is_whitespace()
+        || c.is_control()
+        // Zero width joiner of emoji sequences
+        || c == '\u{200d}'
+        // Variation selectors, e.g. emoji presentation
+        || ('\u{fe00}'..='\u{fe0f}').contains(&c)
+        || ('\u{e0100}'..='\u{e01ef}').contains(&c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{covering_families, needs_glyph};
+
+    /// Name and character coverage of a font family
+    type FakeFamily = (String, fn(char) -> bool);
+
+    fn families() -> Vec<FakeFamily> {
+        vec![
+            ("Segoe UI".to_string(), |c| c.is_ascii()),
+            ("Microsoft JhengHei".to_string(), |c| {
+                c.is_ascii() || ('\u{4e00}'..='\u{9fff}').contains(&c)
+            }),
+            ("MingLiU-ExtB".to_string(), |c| {
+                ('\u{20000}'..='\u{2a6df}').contains(&c)
+            }),
+            ("Segoe UI Emoji".to_string(), |c| {
+                c.is_ascii_digit() || ('\u{1f300}'..='\u{1faff}').contains(&c)
+            }),
+        ]
+    }
+
+    #[test]
+    fn cjk_and_ascii() {
+        assert_eq!(
+            vec!["Microsoft JhengHei"],
+            covering_families(families(), "新酷音 chewing")
+        );
+        assert_eq!(
+            vec!["Microsoft JhengHei", "Segoe UI"],
+            covering_families(families(), "abc")
+        );
+    }
+
+    #[test]
+    fn extension_b() {
+        assert_eq!(vec!["MingLiU-ExtB"], covering_families(families(), "𠀀𪘀"));
+        assert!(covering_families(families(), "𠀀字").is_empty());
+    }
+
+    #[test]
+    fn emoji_sequences() {
+        // Family emoji joined with zero width joiners
+        assert_eq!(
+            vec!["Segoe UI Emoji"],
+            covering_families(families(), "👨\u{200d}👩\u{200d}👧")
+        );
+        // Digit with emoji presentation
+        assert_eq!(
+            vec!["Microsoft JhengHei", "Segoe UI", "Segoe UI Emoji"],
+            covering_families(families(), "1\u{fe0f}")
+        );
+    }
+
+    #[test]
+    fn empty_text_is_covered_by_all() {
+        assert_eq!(4, covering_families(families(), "").len());
+        assert_eq!(4, covering_families(families(), " \u{fe0f}").len());
+    }
+
+    #[test]
+    fn invisible_characters() {
+        assert!(needs_glyph('測'));
+        assert!(needs_glyph('😀'));
+        assert!(!needs_glyph(' '));
+        assert!(!needs_glyph('\u{3000}'));
+        assert!(!needs_glyph('\u{200d}'));
+        assert!(!needs_glyph('\u{fe0e}'));
+        assert!(!needs_glyph('\u{e0101}'));
+    }
+}
@@ -3,6 +3,7 @@
 
 use std::sync::LazyLock;
 
+use chewing_tip_core::ipc::values::ScreenRect;
 use error_plus::expect_error;
 use error_plus::expect_error_fn;
 use error_plus::impl_context_error;
@@ -14,13 +15,18 @@ use windows::Win32::Graphics::Direct3D11::*;
 use windows::Win32::Graphics::DirectComposition::*;
 use windows::Win32::Graphics::Dxgi::Common::*;
 use windows::Win32::Graphics::Dxgi::*;
+use windows::Win32::Graphics::Gdi::EnumDisplayMonitors;
 use windows::Win32::Graphics::Gdi::GetMonitorInfoW;
+use windows::Win32::Graphics::Gdi::HDC;
+use windows::Win32::Graphics::Gdi::HMONITOR;
 use windows::Win32::Graphics::Gdi::MONITOR_DEFAULTTONEAREST;
 use windows::Win32::Graphics::Gdi::MONITORINFO;
 use windows::Win32::Graphics::Gdi::MonitorFromPoint;
 use windows::Win32::Graphics::Gdi::MonitorFromWindow;
 use windows::Win32::UI::HiDpi::*;
-use windows::core::Interface;
+use windows::core::{BOOL, Interface};
+
+use crate::ui::placement::Monitor;
 
 static DEVICE: LazyLock<Result<ID3D11Device, GfxError>> =
     LazyLock::new(|| create_device_with_type(D3D_DRIVER_TYPE_WARP));
@@ -190,6 +196,48 @@ pub(crate) fn clamp_point_to_monitor(mut x: i32, mut y: i32, w: i32, h: i32) ->
     (x, y)
 }
 
+/// All monitors of the desktop and their work areas.
+pub(crate) fn monitors() -> Vec<Monitor> {
+    unsafe extern "system" fn push_monitor(
+        monitor: HMONITOR,
+        _hdc: HDC,
+        _rect: *mut RECT,
+        data: LPARAM,
+    ) -> BOOL {
+        let monitors = unsafe { &mut *(data.0 as *mut Vec<Monitor>) };
+        let mut mi = MONITORINFO {
+            cbSize: size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        if unsafe { GetMonitorInfoW(monitor, &mut mi) }.as_bool() {
+            monitors.push(Monitor {
+                bounds: screen_rect(mi.rcMonitor),
+                work: screen_rect(mi.rcWork),
+            });
+        }
+        TRUE
+    }
+    let mut monitors = vec![];
+    unsafe {
+        let _ = EnumDisplayMonitors(
+            None,
+            None,
+            Some(push_monitor),
+            LPARAM(&mut monitors as *mut Vec<Monitor> as isize),
+        );
+    }
+    monitors
+}
+
+fn screen_rect(rc: RECT) -> ScreenRect {
+    ScreenRect {
+        left: rc.left,
+        top: rc.top,
+        right: rc.right,
+        bottom: rc.bottom,
+    }
+}
+
 pub fn color_s(rgb: &str) -> D2D1_COLOR_F {
     let mut rgb_u32 = u32::from_str_radix(rgb, 16).unwrap_or(0);
     let a = if rgb.len() > 6 {
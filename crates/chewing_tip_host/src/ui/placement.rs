@@ -0,0 +1,238 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2026 Kan-Ru Chen
+
+//! Where popup windows go relative to the caret.
+
+use chewing_tip_core::ipc::values::ScreenRect;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Monitor {
+    /// The whole monitor
+    pub(crate) bounds: ScreenRect,
+    /// The monitor without the taskbar and docked toolbars
+    pub(crate) work: ScreenRect,
+}
+
+/// Which side of the caret the popup is on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Side {
+    Below,
+    Above,
+}
+
+/// Places a `width` x `height` popup next to `caret`.
+///
+/// The popup goes below the caret and flips above it when there is no room
+/// in the work area of the monitor showing the caret. It is never moved to
+/// another monitor. `prefer` keeps the side of the previous placement, so
+/// the popup does not jump around when its size changes, e.g. while paging.
+pub(crate) fn place_popup(
+    caret: ScreenRect,
+    width: i32,
+    height: i32,
+    monitors: &[Monitor],
+    prefer: Option<Side>,
+) -> (i32, i32, Side) {
+    let Some(work) = monitor_of(caret, monitors).map(|monitor| monitor.work) else {
+        return (caret.left, caret.bottom, Side::Below);
+    };
+    let room_below = work.bottom - caret.bottom;
+    let room_above = caret.top - work.top;
+    let fits = |side| match side {
+        Side::Below => height <= room_below,
+        Side::Above => height <= room_above,
+    };
+    let side = match prefer {
+        Some(side) if fits(side) => side,
+        _ if fits(Side::Below) => Side::Below,
+        _ if fits(Side::Above) => Side::Above,
+        // Covers part of the caret on the side with more room
+        _ if room_above > room_below => Side::Above,
+        _ => Side::Below,
+    };
+    let y = match side {
+        Side::Below => caret.bottom,
+        Side::Above => caret.top - height,
+    };
+    let x = clamp(caret.left, work.left, work.right - width);
+    let y = clamp(y, work.top, work.bottom - height);
+    (x, y, side)
+}
+
+/// The monitor with the largest part of the caret, or the closest one.
+fn monitor_of(caret: ScreenRect, monitors: &[Monitor]) -> Option<&Monitor> {
+    let overlap = |monitor: &&Monitor| {
+        let rect = monitor.bounds;
+        let width = caret.right.min(rect.right) - caret.left.max(rect.left);
+        let height = caret.bottom.min(rect.bottom) - caret.top.max(rect.top);
+        // An empty caret still touches the monitor it is on
+        (width >= 0 && height >= 0).then_some(i64::from(width) * i64::from(height))
+    };
+    let distance = |monitor: &&Monitor| {
+        let rect = monitor.bounds;
+        let dx = (rect.left - caret.left).max(caret.left - rect.right).max(0);
+        let dy = (rect.top - caret.top).max(caret.top - rect.bottom).max(0);
+        i64::from(dx) * i64::from(dx) + i64::from(dy) * i64::from(dy)
+    };
+    monitors
+        .iter()
+        .filter(|monitor| overlap(monitor).is_some())
+        .max_by_key(|monitor| overlap(monitor))
+        .or_else(|| monitors.iter().min_by_key(distance))
+}
+
+/// Like `i32::clamp` but prefers `min` when the range is empty.
+fn clamp(value: i32, min: i32, max: i32) -> i32 {
+    value.min(max).max(min)
+}
+
+#[cfg(test)]
+mod tests {
+    use chewing_tip_core::ipc::values::ScreenRect;
+
+    use super::{Monitor, Side, place_popup};
+
+    fn rect(left: i32, top: i32, right: i32, bottom: i32) -> ScreenRect {
+        ScreenRect {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    /// A 1920x1080 monitor at `(x, y)` with a 40px taskbar at the bottom.
+    fn monitor(x: i32, y: i32) -> Monitor {
+        Monitor {
+            bounds: rect(x, y, x + 1920, y + 1080),
+            work: rect(x, y, x + 1920, y + 1040),
+        }
+    }
+
+    fn caret(x: i32, y: i32) -> ScreenRect {
+        rect(x, y, x + 2, y + 20)
+    }
+
+    #[test]
+    fn below_caret() {
+        let monitors = [monitor(0, 0)];
+        assert_eq!(
+            (100, 120, Side::Below),
+            place_popup(caret(100, 100), 200, 300, &monitors, None)
+        );
+    }
+
+    #[test]
+    fn flips_above_near_the_bottom() {
+        let monitors = [monitor(0, 0)];
+        assert_eq!(
+            (100, 600, Side::Above),
+            place_popup(caret(100, 900), 200, 300, &monitors, None)
+        );
+    }
+
+    #[test]
+    fn avoids_the_taskbar() {
+        let monitors = [monitor(0, 0)];
+        // Fits above the bottom of the monitor but not above the taskbar
+        assert_eq!(
+            (100, 730, Side::Above),
+            place_popup(caret(100, 1030), 200, 300, &monitors, None)
+        );
+        assert_eq!(
+            (100, 720, Side::Below),
+            place_popup(caret(100, 700), 200, 300, &monitors, None)
+        );
+    }
+
+    #[test]
+    fn clamped_to_the_right_edge() {
+        let monitors = [monitor(0, 0)];
+        assert_eq!(
+            (1720, 120, Side::Below),
+            place_popup(caret(1900, 100), 200, 300, &monitors, None)
+        );
+    }
+
+    #[test]
+    fn stays_on_the_monitor_of_the_caret() {
+        let monitors = [monitor(0, 0), monitor(1920, 0)];
+        // Would overlap the second monitor if only clamped to the first
+        assert_eq!(
+            (1720, 120, Side::Below),
+            place_popup(caret(1910, 100), 200, 300, &monitors, None)
+        );
+        assert_eq!(
+            (1930, 120, Side::Below),
+            place_popup(caret(1930, 100), 200, 300, &monitors, None)
+        );
+    }
+
+    #[test]
+    fn stacked_monitors() {
+        let monitors = [monitor(0, 0), monitor(0, 1080)];
+        // Flips above instead of spilling onto the lower monitor
+        assert_eq!(
+            (100, 700, Side::Above),
+            place_popup(caret(100, 1000), 200, 300, &monitors, None)
+        );
+        assert_eq!(
+            (100, 1200, Side::Below),
+            place_popup(caret(100, 1180), 200, 300, &monitors, None)
+        );
+    }
+
+    #[test]
+    fn monitor_on_the_left_of_the_primary() {
+        let monitors = [monitor(0, 0), monitor(-1920, 0)];
+        assert_eq!(
+            (-1920, 120, Side::Below),
+            place_popup(caret(-2000, 100), 200, 300, &monitors, None)
+        );
+    }
+
+    #[test]
+    fn keeps_the_preferred_side() {
+        let monitors = [monitor(0, 0)];
+        let caret = caret(100, 500);
+        assert_eq!(
+            (100, 520, Side::Below),
+            place_popup(caret, 200, 300, &monitors, None)
+        );
+        assert_eq!(
+            (100, 200, Side::Above),
+            place_popup(caret, 200, 300, &monitors, Some(Side::Above))
+        );
+        // A taller page that no longer fits above
+        assert_eq!(
+            (100, 520, Side::Below),
+            place_popup(caret, 200, 510, &monitors, Some(Side::Above))
+        );
+    }
+
+    #[test]
+    fn too_tall_for_either_side() {
+        let monitors = [monitor(0, 0)];
+        assert_eq!(
+            (100, 0, Side::Above),
+            place_popup(caret(100, 600), 200, 700, &monitors, None)
+        );
+        assert_eq!(
+            (100, 320, Side::Below),
+            place_popup(caret(100, 300), 200, 700, &monitors, None)
+        );
+        // Taller than the work area
+        assert_eq!(
+            (100, 0, Side::Above),
+            place_popup(caret(100, 600), 200, 1200, &monitors, None)
+        );
+    }
+
+    #[test]
+    fn no_monitors() {
+        assert_eq!(
+            (100, 120, Side::Below),
+            place_popup(caret(100, 100), 200, 300, &[], None)
+        );
+    }
+}
@@ -0,0 +1,553 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2026 Kan-Ru Chen
+
+//! UI Automation providers for the host windows.
+//!
+//! The providers only translate the trees built in
+//! [`crate::ui_elements::accessibility`] and may be called from any thread.
+
+use std::{
+    ptr::null_mut,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use log::error;
+use windows::Win32::{
+    Foundation::{HWND, LPARAM, LRESULT, RECT, WPARAM},
+    System::{
+        Com::SAFEARRAY,
+        Ole::{SafeArrayCreateVector, SafeArrayPutElement},
+        Variant::{VT_I4, VT_UNKNOWN},
+    },
+    UI::{
+        Accessibility::{
+            Assertive, IInvokeProvider, IInvokeProvider_Impl, IRawElementProviderFragment,
+            IRawElementProviderFragment_Impl, IRawElementProviderFragmentRoot,
+            IRawElementProviderFragmentRoot_Impl, IRawElementProviderSimple,
+            IRawElementProviderSimple_Impl, ISelectionItemProvider, ISelectionItemProvider_Impl,
+            ISelectionProvider, ISelectionProvider_Impl, NavigateDirection,
+            NavigateDirection_FirstChild, NavigateDirection_LastChild,
+            NavigateDirection_NextSibling, NavigateDirection_Parent,
+            NavigateDirection_PreviousSibling, Polite, ProviderOptions,
+            ProviderOptions_ServerSideProvider, StructureChangeType_ChildrenInvalidated,
+            UIA_AutomationIdPropertyId, UIA_ControlTypePropertyId, UIA_E_INVALIDOPERATION,
+            UIA_HelpTextPropertyId, UIA_InvokePatternId, UIA_IsKeyboardFocusablePropertyId,
+            UIA_ListControlTypeId, UIA_ListItemControlTypeId, UIA_LiveRegionChangedEventId,
+            UIA_LiveSettingPropertyId, UIA_NamePropertyId, UIA_PATTERN_ID, UIA_PROPERTY_ID,
+            UIA_PositionInSetPropertyId, UIA_SelectionItem_ElementSelectedEventId,
+            UIA_SelectionItemPatternId, UIA_SelectionPatternId, UIA_SizeOfSetPropertyId,
+            UIA_TextControlTypeId, UiaAppendRuntimeId, UiaClientsAreListening,
+            UiaHostProviderFromHwnd, UiaRaiseAutomationEvent,
+            UiaRaiseAutomationPropertyChangedEvent, UiaRaiseStructureChangedEvent, UiaRect,
+            UiaReturnRawElementProvider, UiaRootObjectId,
+        },
+        WindowsAndMessaging::GetWindowRect,
+    },
+};
+use windows_core::{
+    BOOL, BSTR, ComObjectInner, Error, HRESULT, IUnknown, Interface, Result, VARIANT, implement,
+};
+
+use crate::{
+    ui::gfx::get_dpi_for_window,
+    ui_elements::{
+        accessibility::{
+            AccessibilityEvent, Announcement, CandidateTree, Politeness, candidate_events,
+        },
+        candidate_layout::Rect,
+    },
+};
+
+/// Exposes the candidate window as a list to UI Automation clients.
+#[derive(Debug)]
+pub(crate) struct CandidateListAccessibility {
+    hwnd: HWND,
+    tree: Arc<Mutex<CandidateTree>>,
+    provider: IRawElementProviderSimple,
+    on_invoke: fn(usize),
+}
+
+impl CandidateListAccessibility {
+    /// `on_invoke` is called with the index of a candidate chosen by an
+    /// assistive technology.
+    pub(crate) fn new(hwnd: HWND, on_invoke: fn(usize)) -> CandidateListAccessibility {
+        let tree = Arc::new(Mutex::new(CandidateTree::default()));
+        let provider = CandidateListProvider {
+            hwnd,
+            tree: tree.clone(),
+            on_invoke,
+        }
+        .into_object()
+        .into_interface();
+        CandidateListAccessibility {
+            hwnd,
+            tree,
+            provider,
+            on_invoke,
+        }
+    }
+    /// Answers WM_GETOBJECT for the window.
+    pub(crate) fn get_object(&self, hwnd: HWND, wparam: WPARAM, lparam: LPARAM) -> Option<LRESULT> {
+        return_provider(hwnd, wparam, lparam, &self.provider)
+    }
+    /// Replaces the tree and notifies listening clients of the changes.
+    pub(crate) fn update(&self, tree: CandidateTree) {
+        let (events, old_name, new_name) = {
+            let mut current = lock(&self.tree);
+            let events = candidate_events(&current, &tree);
+            let old_name = current.name.clone();
+            let new_name = tree.name.clone();
+            *current = tree;
+            (events, old_name, new_name)
+        };
+        if events.is_empty() || !unsafe { UiaClientsAreListening() }.as_bool() {
+            return;
+        }
+        let provider = &self.provider;
+        for event in events {
+            let result = unsafe {
+                match event {
+                    AccessibilityEvent::StructureChanged => UiaRaiseStructureChangedEvent(
+                        provider,
+                        StructureChangeType_ChildrenInvalidated,
+                        null_mut(),
+                        0,
+                    ),
+                    AccessibilityEvent::NameChanged => UiaRaiseAutomationPropertyChangedEvent(
+                        provider,
+                        UIA_NamePropertyId,
+                        &VARIANT::from(BSTR::from(old_name.as_str())),
+                        &VARIANT::from(BSTR::from(new_name.as_str())),
+                    ),
+                    AccessibilityEvent::ElementSelected(index) => {
+                        self.provider.cast().and_then(|root| {
+                            let item: IRawElementProviderSimple = CandidateItemProvider {
+                                hwnd: self.hwnd,
+                                tree: self.tree.clone(),
+                                root,
+                                index,
+                                on_invoke: self.on_invoke,
+                            }
+                            .into_object()
+                            .into_interface();
+                            UiaRaiseAutomationEvent(&item, UIA_SelectionItem_ElementSelectedEventId)
+                        })
+                    }
+                }
+            };
+            if let Err(error) = result {
+                error!("Failed to raise UI Automation event {event:?}: {error}");
+            }
+        }
+    }
+}
+
+/// Exposes the notification window as a live region.
+#[derive(Debug)]
+pub(crate) struct NotificationAccessibility {
+    announcement: Arc<Mutex<Option<Announcement>>>,
+    provider: IRawElementProviderSimple,
+}
+
+impl NotificationAccessibility {
+    pub(crate) fn new(hwnd: HWND) -> NotificationAccessibility {
+        let announcement = Arc::new(Mutex::new(None));
+        let provider = NotificationProvider {
+            hwnd,
+            announcement: announcement.clone(),
+        }
+        .into_object()
+        .into_interface();
+        NotificationAccessibility {
+            announcement,
+            provider,
+        }
+    }
+    /// Answers WM_GETOBJECT for the window.
+    pub(crate) fn get_object(&self, hwnd: HWND, wparam: WPARAM, lparam: LPARAM) -> Option<LRESULT> {
+        return_provider(hwnd, wparam, lparam, &self.provider)
+    }
+    /// Asks screen readers to read the notification.
+    pub(crate) fn announce(&self, announcement: Announcement) {
+        *lock(&self.announcement) = Some(announcement);
+        if !unsafe { UiaClientsAreListening() }.as_bool() {
+            return;
+        }
+        if let Err(error) =
+            unsafe { UiaRaiseAutomationEvent(&self.provider, UIA_LiveRegionChangedEventId) }
+        {
+            error!("Failed to raise live region event: {error}");
+        }
+    }
+}
+
+fn return_provider(
+    hwnd: HWND,
+    wparam: WPARAM,
+    lparam: LPARAM,
+    provider: &IRawElementProviderSimple,
+) -> Option<LRESULT> {
+    // The object id is a 32-bit value in the low half of lparam
+    if lparam.0 as i32 != UiaRootObjectId {
+        return None;
+    }
+    Some(unsafe { UiaReturnRawElementProvider(hwnd, wparam, lparam, provider) })
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    // Nothing is left half updated if a holder panicked
+    mutex.lock().unwrap_or_else(|error| error.into_inner())
+}
+
+/// Screen coordinates of `bounds` given in DIPs relative to the window.
+fn screen_rect(hwnd: HWND, bounds: &Rect) -> UiaRect {
+    let mut rect = RECT::default();
+    let _ = unsafe { GetWindowRect(hwnd, &mut rect) };
+    let scale = get_dpi_for_window(hwnd) / 96.0;
+    UiaRect {
+        left: (rect.left as f32 + bounds.left * scale).into(),
+        top: (rect.top as f32 + bounds.top * scale).into(),
+        width: (bounds.width() * scale).into(),
+        height: (bounds.height() * scale).into(),
+    }
+}
+
+/// Converts screen coordinates to DIPs relative to the window.
+fn client_point(hwnd: HWND, x: f64, y: f64) -> (f32, f32) {
+    let mut rect = RECT::default();
+    let _ = unsafe { GetWindowRect(hwnd, &mut rect) };
+    let scale = get_dpi_for_window(hwnd) / 96.0;
+    (
+        (x as f32 - rect.left as f32) / scale,
+        (y as f32 - rect.top as f32) / scale,
+    )
+}
+
+fn runtime_id(parts: &[i32]) -> *mut SAFEARRAY {
+    unsafe {
+        let array = SafeArrayCreateVector(VT_I4, 0, parts.len() as u32);
+        if !array.is_null() {
+            for (i, part) in parts.iter().enumerate() {
+                let _ = SafeArrayPutElement(array, &(i as i32), (part as *const i32).cast());
+            }
+        }
+        array
+    }
+}
+
+fn provider_array(providers: &[IRawElementProviderSimple]) -> *mut SAFEARRAY {
+    unsafe {
+        let array = SafeArrayCreateVector(VT_UNKNOWN, 0, providers.len() as u32);
+        if !array.is_null() {
+            for (i, provider) in providers.iter().enumerate() {
+                // The array keeps its own reference of each provider
+                let _ = SafeArrayPutElement(array, &(i as i32), provider.as_raw());
+            }
+        }
+        array
+    }
+}
+
+#[implement(
+    IRawElementProviderSimple,
+    IRawElementProviderFragment,
+    IRawElementProviderFragmentRoot,
+    ISelectionProvider
+)]
+struct CandidateListProvider {
+    hwnd: HWND,
+    tree: Arc<Mutex<CandidateTree>>,
+    on_invoke: fn(usize),
+}
+
+impl CandidateListProvider_Impl {
+    fn item(&self, index: usize) -> Option<CandidateItemProvider> {
+        if index >= lock(&self.tree).items.len() {
+            return None;
+        }
+        Some(CandidateItemProvider {
+            hwnd: self.hwnd,
+            tree: self.tree.clone(),
+            root: self.to_interface(),
+            index,
+            on_invoke: self.on_invoke,
+        })
+    }
+}
+
+impl IRawElementProviderSimple_Impl for CandidateListProvider_Impl {
+    fn ProviderOptions(&self) -> Result<ProviderOptions> {
+        Ok(ProviderOptions_ServerSideProvider)
+    }
+
+    fn GetPatternProvider(&self, patternid: UIA_PATTERN_ID) -> Result<IUnknown> {
+        match patternid {
+            UIA_SelectionPatternId => Ok(self.to_interface()),
+            _ => Err(Error::empty()),
+        }
+    }
+
+    fn GetPropertyValue(&self, propertyid: UIA_PROPERTY_ID) -> Result<VARIANT> {
+        let value = match propertyid {
+            UIA_ControlTypePropertyId => VARIANT::from(UIA_ListControlTypeId.0),
+            UIA_NamePropertyId => VARIANT::from(BSTR::from(lock(&self.tree).name.as_str())),
+            UIA_AutomationIdPropertyId => VARIANT::from(BSTR::from("ChewingCandidateList")),
+            UIA_IsKeyboardFocusablePropertyId => VARIANT::from(false),
+            _ => VARIANT::default(),
+        };
+        Ok(value)
+    }
+
+    fn HostRawElementProvider(&self) -> Result<IRawElementProviderSimple> {
+        unsafe { UiaHostProviderFromHwnd(self.hwnd) }
+    }
+}
+
+impl IRawElementProviderFragment_Impl for CandidateListProvider_Impl {
+    fn Navigate(&self, direction: NavigateDirection) -> Result<IRawElementProviderFragment> {
+        let len = lock(&self.tree).items.len();
+        let index = match direction {
+            NavigateDirection_FirstChild => 0,
+            NavigateDirection_LastChild => len.wrapping_sub(1),
+            _ => return Err(Error::empty()),
+        };
+        match self.item(index) {
+            Some(item) => Ok(item.into_object().into_interface()),
+            None => Err(Error::empty()),
+        }
+    }
+
+    fn GetRuntimeId(&self) -> Result<*mut SAFEARRAY> {
+        // Provided by the host provider of the window
+        Ok(null_mut())
+    }
+
+    fn BoundingRectangle(&self) -> Result<UiaRect> {
+        // Provided by the host provider of the window
+        Ok(UiaRect::default())
+    }
+
+    fn GetEmbeddedFragmentRoots(&self) -> Result<*mut SAFEARRAY> {
+        Ok(null_mut())
+    }
+
+    fn SetFocus(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn FragmentRoot(&self) -> Result<IRawElementProviderFragmentRoot> {
+        Ok(self.to_interface())
+    }
+}
+
+impl IRawElementProviderFragmentRoot_Impl for CandidateListProvider_Impl {
+    fn ElementProviderFromPoint(&self, x: f64, y: f64) -> Result<IRawElementProviderFragment> {
+        let (x, y) = client_point(self.hwnd, x, y);
+        let index = lock(&self.tree)
+            .items
+            .iter()
+            .position(|item| item.bounds.contains(x, y));
+        match index.and_then(|index| self.item(index)) {
+            Some(item) => Ok(item.into_object().into_interface()),
+            None => Err(Error::empty()),
+        }
+    }
+
+    fn GetFocus(&self) -> Result<IRawElementProviderFragment> {
+        // The candidate window never takes the keyboard focus
+        Err(Error::empty())
+    }
+}
+
+impl ISelectionProvider_Impl for CandidateListProvider_Impl {
+    fn GetSelection(&self) -> Result<*mut SAFEARRAY> {
+        let selected = lock(&self.tree).selected;
+        let items: Vec<IRawElementProviderSimple> = selected
+            .and_then(|index| self.item(index))
+            .map(|item| item.into_object().into_interface())
+            .into_iter()
+            .collect();
+        Ok(provider_array(&items))
+    }
+
+    fn CanSelectMultiple(&self) -> Result<BOOL> {
+        Ok(false.into())
+    }
+
+    fn IsSelectionRequired(&self) -> Result<BOOL> {
+        Ok(false.into())
+    }
+}
+
+#[implement(
+    IRawElementProviderSimple,
+    IRawElementProviderFragment,
+    ISelectionItemProvider,
+    IInvokeProvider
+)]
+struct CandidateItemProvider {
+    hwnd: HWND,
+    tree: Arc<Mutex<CandidateTree>>,
+    root: IRawElementProviderFragmentRoot,
+    index: usize,
+    on_invoke: fn(usize),
+}
+
+impl CandidateItemProvider_Impl {
+    fn sibling(&self, index: Option<usize>) -> Result<IRawElementProviderFragment> {
+        let len = lock(&self.tree).items.len();
+        match index.filter(|&index| index < len) {
+            Some(index) => Ok(CandidateItemProvider {
+                hwnd: self.hwnd,
+                tree: self.tree.clone(),
+                root: self.root.clone(),
+                index,
+                on_invoke: self.on_invoke,
+            }
+            .into_object()
+            .into_interface()),
+            None => Err(Error::empty()),
+        }
+    }
+}
+
+impl IRawElementProviderSimple_Impl for CandidateItemProvider_Impl {
+    fn ProviderOptions(&self) -> Result<ProviderOptions> {
+        Ok(ProviderOptions_ServerSideProvider)
+    }
+
+    fn GetPatternProvider(&self, patternid: UIA_PATTERN_ID) -> Result<IUnknown> {
+        match patternid {
+            UIA_SelectionItemPatternId | UIA_InvokePatternId => Ok(self.to_interface()),
+            _ => Err(Error::empty()),
+        }
+    }
+
+    fn GetPropertyValue(&self, propertyid: UIA_PROPERTY_ID) -> Result<VARIANT> {
+        let tree = lock(&self.tree);
+        let Some(item) = tree.items.get(self.index) else {
+            return Ok(VARIANT::default());
+        };
+        let value = match propertyid {
+            UIA_ControlTypePropertyId => VARIANT::from(UIA_ListItemControlTypeId.0),
+            UIA_NamePropertyId => VARIANT::from(BSTR::from(item.name.as_str())),
+            UIA_HelpTextPropertyId if !item.description.is_empty() => {
+                VARIANT::from(BSTR::from(item.description.as_str()))
+            }
+            UIA_IsKeyboardFocusablePropertyId => VARIANT::from(false),
+            UIA_PositionInSetPropertyId => VARIANT::from(self.index as i32 + 1),
+            UIA_SizeOfSetPropertyId => VARIANT::from(tree.items.len() as i32),
+            _ => VARIANT::default(),
+        };
+        Ok(value)
+    }
+
+    fn HostRawElementProvider(&self) -> Result<IRawElementProviderSimple> {
+        Err(Error::empty())
+    }
+}
+
+impl IRawElementProviderFragment_Impl for CandidateItemProvider_Impl {
+    fn Navigate(&self, direction: NavigateDirection) -> Result<IRawElementProviderFragment> {
+        match direction {
+            NavigateDirection_Parent => self.root.cast(),
+            NavigateDirection_NextSibling => self.sibling(self.index.checked_add(1)),
+            NavigateDirection_PreviousSibling => self.sibling(self.index.checked_sub(1)),
+            _ => Err(Error::empty()),
+        }
+    }
+
+    fn GetRuntimeId(&self) -> Result<*mut SAFEARRAY> {
+        Ok(runtime_id(&[UiaAppendRuntimeId as i32, self.index as i32]))
+    }
+
+    fn BoundingRectangle(&self) -> Result<UiaRect> {
+        match lock(&self.tree).items.get(self.index) {
+            Some(item) => Ok(screen_rect(self.hwnd, &item.bounds)),
+            None => Ok(UiaRect::default()),
+        }
+    }
+
+    fn GetEmbeddedFragmentRoots(&self) -> Result<*mut SAFEARRAY> {
+        Ok(null_mut())
+    }
+
+    fn SetFocus(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn FragmentRoot(&self) -> Result<IRawElementProviderFragmentRoot> {
+        Ok(self.root.clone())
+    }
+}
+
+impl ISelectionItemProvider_Impl for CandidateItemProvider_Impl {
+    fn Select(&self) -> Result<()> {
+        // The keyboard cursor is owned by the text service
+        Err(HRESULT(UIA_E_INVALIDOPERATION as i32).into())
+    }
+
+    fn AddToSelection(&self) -> Result<()> {
+        Err(HRESULT(UIA_E_INVALIDOPERATION as i32).into())
+    }
+
+    fn RemoveFromSelection(&self) -> Result<()> {
+        Err(HRESULT(UIA_E_INVALIDOPERATION as i32).into())
+    }
+
+    fn IsSelected(&self) -> Result<BOOL> {
+        Ok((lock(&self.tree).selected == Some(self.index)).into())
+    }
+
+    fn SelectionContainer(&self) -> Result<IRawElementProviderSimple> {
+        self.root.cast()
+    }
+}
+
+impl IInvokeProvider_Impl for CandidateItemProvider_Impl {
+    fn Invoke(&self) -> Result<()> {
+        (self.on_invoke)(self.index);
+        Ok(())
+    }
+}
+
+#[implement(IRawElementProviderSimple)]
+struct NotificationProvider {
+    hwnd: HWND,
+    announcement: Arc<Mutex<Option<Announcement>>>,
+}
+
+impl IRawElementProviderSimple_Impl for NotificationProvider_Impl {
+    fn ProviderOptions(&self) -> Result<ProviderOptions> {
+        Ok(ProviderOptions_ServerSideProvider)
+    }
+
+    fn GetPatternProvider(&self, _patternid: UIA_PATTERN_ID) -> Result<IUnknown> {
+        Err(Error::empty())
+    }
+
+    fn GetPropertyValue(&self, propertyid: UIA_PROPERTY_ID) -> Result<VARIANT> {
+        let announcement = lock(&self.announcement);
+        let value = match (propertyid, announcement.as_ref()) {
+            (UIA_ControlTypePropertyId, _) => VARIANT::from(UIA_TextControlTypeId.0),
+            (UIA_AutomationIdPropertyId, _) => VARIANT::from(BSTR::from("ChewingNotification")),
+            (UIA_IsKeyboardFocusablePropertyId, _) => VARIANT::from(false),
+            (UIA_NamePropertyId, Some(announcement)) => {
+                VARIANT::from(BSTR::from(announcement.text.as_str()))
+            }
+            (UIA_LiveSettingPropertyId, Some(announcement)) => {
+                let setting = match announcement.politeness {
+                    Politeness::Polite => Polite,
+                    Politeness::Assertive => Assertive,
+                };
+                VARIANT::from(setting.0)
+            }
+            _ => VARIANT::default(),
+        };
+        Ok(value)
+    }
+
+    fn HostRawElementProvider(&self) -> Result<IRawElementProviderSimple> {
+        unsafe { UiaHostProviderFromHwnd(self.hwnd) }
+    }
+}
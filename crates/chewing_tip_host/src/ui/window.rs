@@ -117,6 +117,18 @@ impl Window {
         }
     }
 
+    /// Screen rect of the window, or None if it is hidden.
+    pub(crate) fn visible_rect(&self) -> Option<RECT> {
+        unsafe {
+            if self.hwnd().is_invalid() || !IsWindowVisible(self.hwnd()).as_bool() {
+                return None;
+            }
+            let mut rect = RECT::default();
+            GetWindowRect(self.hwnd(), &mut rect).ok()?;
+            Some(rect)
+        }
+    }
+
     pub(crate) fn refresh(&self) {
         unsafe {
             if !self.hwnd().is_invalid() {
@@ -1,2 +1,7 @@
+pub(crate) mod accessibility;
+pub(crate) mod candidate_layout;
 pub(crate) mod candidate_list;
+pub(crate) mod composition_layout;
+pub(crate) mod composition_window;
 pub(crate) mod notification;
+pub(crate) mod notification_queue;
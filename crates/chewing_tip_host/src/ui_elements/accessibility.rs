@@ -0,0 +1,311 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2026 Kan-Ru Chen
+
+//! What assistive technologies see of the host windows.
+//!
+//! The trees here are independent of UI Automation so that what is announced
+//! can be tested without a screen reader.
+
+use chewing_tip_core::ipc::values::NotificationKind;
+
+use crate::ui_elements::{
+    candidate_layout::{CandidateLayout, Rect, annotation_label},
+    candidate_list::CandidateListModel,
+};
+
+/// The candidate window as a list of selectable items.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct CandidateTree {
+    /// Name of the list, includes the title and the page
+    pub(crate) name: String,
+    pub(crate) items: Vec<CandidateNode>,
+    /// Candidate under the keyboard cursor
+    pub(crate) selected: Option<usize>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct CandidateNode {
+    /// The selection key and the candidate, e.g. "1 測試"
+    pub(crate) name: String,
+    /// Annotation of the candidate, if any
+    pub(crate) description: String,
+    /// Area of the candidate in DIPs
+    pub(crate) bounds: Rect,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AccessibilityEvent {
+    /// The items were replaced, e.g. on a new page
+    StructureChanged,
+    /// The name of the list changed
+    NameChanged,
+    /// The keyboard cursor moved to the item
+    ElementSelected(usize),
+}
+
+/// How urgently an announcement is read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Politeness {
+    /// Read after the current speech
+    Polite,
+    /// Interrupts the current speech
+    Assertive,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Announcement {
+    pub(crate) text: String,
+    pub(crate) politeness: Politeness,
+}
+
+pub(crate) fn candidate_tree(
+    model: &CandidateListModel,
+    layout: &CandidateLayout,
+) -> CandidateTree {
+    if model.items.is_empty() {
+        return CandidateTree::default();
+    }
+    let mut name = "候選字".to_string();
+    if !model.title.is_empty() {
+        name = format!("{} {name}", model.title);
+    }
+    if model.total_page > 1 {
+        name.push_str(&format!(
+            " 第 {} 頁，共 {} 頁",
+            model.current_page, model.total_page
+        ));
+    }
+    let items = model
+        .items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let name = match model
+                .selkeys
+                .get(i)
+                .and_then(|&key| char::from_u32(key.into()))
+            {
+                Some(key) => format!("{key} {item}"),
+                None => item.clone(),
+            };
+            CandidateNode {
+                name,
+                description: model
+                    .annotations
+                    .get(i)
+                    .map(annotation_label)
+                    .unwrap_or_default(),
+                bounds: layout.candidates.get(i).copied().unwrap_or_default(),
+            }
+        })
+        .collect();
+    let selected = model
+        .use_cursor
+        .then_some(model.current_sel)
+        .filter(|&sel| sel < model.items.len());
+    CandidateTree {
+        name,
+        items,
+        selected,
+    }
+}
+
+/// Events to raise when the candidate window changes from `old` to `new`.
+pub(crate) fn candidate_events(
+    old: &CandidateTree,
+    new: &CandidateTree,
+) -> Vec<AccessibilityEvent> {
+    let mut events = vec![];
+    let items_changed = old.items.len() != new.items.len()
+        || old
+            .items
+            .iter()
+            .zip(&new.items)
+            .any(|(old, new)| old.name != new.name);
+    if items_changed {
+        events.push(AccessibilityEvent::StructureChanged);
+    }
+    if old.name != new.name {
+        events.push(AccessibilityEvent::NameChanged);
+    }
+    if let Some(sel) = new.selected
+        && (items_changed || old.selected != new.selected)
+    {
+        events.push(AccessibilityEvent::ElementSelected(sel));
+    }
+    events
+}
+
+/// Text read by screen readers when a notification is shown.
+pub(crate) fn announcement(kind: NotificationKind, text: &str) -> Announcement {
+    let (prefix, politeness) = match kind {
+        NotificationKind::ModeChange => ("", Politeness::Assertive),
+        // The indicator alone, e.g. "中", is too terse to be read
+        NotificationKind::ModeIndicator => ("輸入模式：", Politeness::Polite),
+        NotificationKind::Info => ("", Politeness::Polite),
+        NotificationKind::Warning => ("警告：", Politeness::Assertive),
+        NotificationKind::Error => ("錯誤：", Politeness::Assertive),
+        NotificationKind::UpdateAvailable => ("", Politeness::Polite),
+    };
+    Announcement {
+        text: format!("{prefix}{text}"),
+        politeness,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chewing_tip_core::ipc::values::{CandidateAnnotation, NotificationKind};
+
+    use super::{
+        AccessibilityEvent, Announcement, CandidateTree, Politeness, announcement,
+        candidate_events, candidate_tree,
+    };
+    use crate::ui_elements::{
+        candidate_layout::{CandidateLayout, Rect},
+        candidate_list::CandidateListModel,
+    };
+
+    fn model(items: &[&str]) -> CandidateListModel {
+        CandidateListModel {
+            items: items.iter().map(|s| s.to_string()).collect(),
+            selkeys: "1234567890".encode_utf16().collect(),
+            total_page: 1,
+            current_page: 1,
+            ..Default::default()
+        }
+    }
+
+    fn tree(model: &CandidateListModel) -> CandidateTree {
+        let layout = CandidateLayout {
+            candidates: (0..model.items.len())
+                .map(|i| Rect::new(10.0, 10.0 + 20.0 * i as f32, 40.0, 15.0))
+                .collect(),
+            ..Default::default()
+        };
+        candidate_tree(model, &layout)
+    }
+
+    fn names(tree: &CandidateTree) -> Vec<&str> {
+        tree.items.iter().map(|item| item.name.as_str()).collect()
+    }
+
+    #[test]
+    fn candidate_items() {
+        let mut model = model(&["測試", "策試"]);
+        model.annotations = vec![CandidateAnnotation {
+            reading: "ㄘㄜˋ ㄕˋ".to_string(),
+            ..Default::default()
+        }];
+        let tree = tree(&model);
+        assert_eq!("候選字", tree.name);
+        assert_eq!(vec!["1 測試", "2 策試"], names(&tree));
+        assert_eq!("ㄘㄜˋ ㄕˋ", tree.items[0].description);
+        assert_eq!("", tree.items[1].description);
+        assert_eq!(Rect::new(10.0, 30.0, 40.0, 15.0), tree.items[1].bounds);
+        assert_eq!(None, tree.selected);
+    }
+
+    #[test]
+    fn title_and_page_in_name() {
+        let mut model = model(&["測"]);
+        model.title = "ㄘㄜˋ".to_string();
+        model.current_page = 2;
+        model.total_page = 3;
+        assert_eq!("ㄘㄜˋ 候選字 第 2 頁，共 3 頁", tree(&model).name);
+    }
+
+    #[test]
+    fn selection_follows_cursor() {
+        let mut model = model(&["一", "二", "三"]);
+        model.use_cursor = true;
+        model.current_sel = 2;
+        // Hovering does not move the keyboard cursor
+        model.hover = Some(0);
+        assert_eq!(Some(2), tree(&model).selected);
+        model.current_sel = 3;
+        assert_eq!(None, tree(&model).selected);
+    }
+
+    #[test]
+    fn empty_model() {
+        assert_eq!(CandidateTree::default(), tree(&model(&[])));
+    }
+
+    #[test]
+    fn events_on_show_and_hide() {
+        let mut model = model(&["一", "二"]);
+        model.use_cursor = true;
+        let shown = tree(&model);
+        assert_eq!(
+            vec![
+                AccessibilityEvent::StructureChanged,
+                AccessibilityEvent::NameChanged,
+                AccessibilityEvent::ElementSelected(0),
+            ],
+            candidate_events(&CandidateTree::default(), &shown)
+        );
+        assert_eq!(
+            vec![
+                AccessibilityEvent::StructureChanged,
+                AccessibilityEvent::NameChanged,
+            ],
+            candidate_events(&shown, &CandidateTree::default())
+        );
+    }
+
+    #[test]
+    fn events_on_cursor_move_and_page_change() {
+        let mut model = model(&["一", "二"]);
+        model.use_cursor = true;
+        model.total_page = 2;
+        let old = tree(&model);
+        model.current_sel = 1;
+        let new = tree(&model);
+        assert_eq!(
+            vec![AccessibilityEvent::ElementSelected(1)],
+            candidate_events(&old, &new)
+        );
+        assert_eq!(
+            Vec::<AccessibilityEvent>::new(),
+            candidate_events(&new, &new)
+        );
+
+        model.items = vec!["三".to_string(), "四".to_string()];
+        model.current_page = 2;
+        assert_eq!(
+            vec![
+                AccessibilityEvent::StructureChanged,
+                AccessibilityEvent::NameChanged,
+                AccessibilityEvent::ElementSelected(1),
+            ],
+            candidate_events(&new, &tree(&model))
+        );
+    }
+
+    #[test]
+    fn notification_announcements() {
+        assert_eq!(
+            Announcement {
+                text: "英數模式".to_string(),
+                politeness: Politeness::Assertive,
+            },
+            announcement(NotificationKind::ModeChange, "英數模式")
+        );
+        assert_eq!(
+            Announcement {
+                text: "錯誤：無法刪除：測試".to_string(),
+                politeness: Politeness::Assertive,
+            },
+            announcement(NotificationKind::Error, "無法刪除：測試")
+        );
+        assert_eq!(
+            Politeness::Polite,
+            announcement(NotificationKind::Info, "刪除：測試").politeness
+        );
+        assert_eq!(
+            "輸入模式：中全",
+            announcement(NotificationKind::ModeIndicator, "中全").text
+        );
+    }
+}
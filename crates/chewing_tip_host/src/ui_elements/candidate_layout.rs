@@ -0,0 +1,693 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2026 Kan-Ru Chen
+
+//! Platform independent layout of the candidate window.
+//!
+//! The layout only depends on text measurements so it can be tested without
+//! DirectWrite. The result is a display list that the renderer rasterizes.
+
+use chewing_tip_core::ipc::values::{CandidateAnnotation, CandidateLayoutMode};
+use error_plus::expect_error;
+
+use crate::{ui::UiError, ui_elements::candidate_list::CandidateListModel};
+
+/// Gap between the candidate box and the page indicator box
+const PAGE_GAP: f32 = 2.0;
+/// Extra room in HW pixels for the drop shadow of the message boxes
+const SHADOW_PADDING: f32 = 25.0;
+const PREV_PAGE_ARROW: &str = "◀";
+const NEXT_PAGE_ARROW: &str = "▶";
+/// Marks phrases learned by the user
+const USER_PHRASE_MARKER: &str = "★";
+
+/// Spacing between the elements in DIPs
+struct Spacing {
+    margin: f32,
+    row: f32,
+    col: f32,
+}
+
+const REGULAR: Spacing = Spacing {
+    margin: 10.0,
+    row: 4.0,
+    col: 8.0,
+};
+const COMPACT: Spacing = Spacing {
+    margin: 4.0,
+    row: 1.0,
+    col: 4.0,
+};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) struct Rect {
+    pub(crate) left: f32,
+    pub(crate) top: f32,
+    pub(crate) right: f32,
+    pub(crate) bottom: f32,
+}
+
+impl Rect {
+    pub(crate) fn new(left: f32, top: f32, width: f32, height: f32) -> Rect {
+        Rect {
+            left,
+            top,
+            right: left + width,
+            bottom: top + height,
+        }
+    }
+    pub(crate) fn width(&self) -> f32 {
+        self.right - self.left
+    }
+    pub(crate) fn height(&self) -> f32 {
+        self.bottom - self.top
+    }
+    pub(crate) fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.left && x < self.right && y >= self.top && y < self.bottom
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) struct TextSize {
+    pub(crate) width: f32,
+    pub(crate) height: f32,
+}
+
+/// Measures a single line of text in device independent pixels.
+pub(crate) trait TextMeasure {
+    fn measure(&self, text: &str, font_size: f32) -> Result<TextSize, UiError>;
+}
+
+/// Colors are resolved by the renderer from the model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Paint {
+    SelKey,
+    Text,
+    HighlightText,
+    HighlightBackground,
+    PageIndicator,
+    Annotation,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum DisplayItem {
+    /// Rounded box with background, border and drop shadow
+    MessageBox {
+        rect: Rect,
+    },
+    FillRect {
+        rect: Rect,
+        paint: Paint,
+    },
+    Text {
+        text: String,
+        rect: Rect,
+        font_size: f32,
+        paint: Paint,
+    },
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct CandidateLayout {
+    /// Size of the window content in DIPs
+    pub(crate) width: f32,
+    pub(crate) height: f32,
+    /// Size of the window in HW pixels
+    pub(crate) hw_width: u32,
+    pub(crate) hw_height: u32,
+    /// Area of each candidate, including the selection key
+    pub(crate) candidates: Vec<Rect>,
+    pub(crate) highlight: Option<Rect>,
+    pub(crate) title: Option<Rect>,
+    pub(crate) page_box: Rect,
+    pub(crate) page_indicator: Rect,
+    pub(crate) prev_page: Option<Rect>,
+    pub(crate) next_page: Option<Rect>,
+    pub(crate) display_list: Vec<DisplayItem>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum HitTarget {
+    Candidate(usize),
+    PrevPage,
+    NextPage,
+    PageIndicator,
+}
+
+impl CandidateLayout {
+    /// Finds what is under the point `(x, y)` in DIPs.
+    pub(crate) fn hit_test(&self, x: f32, y: f32) -> Option<HitTarget> {
+        if let Some(index) = self.candidates.iter().position(|rect| rect.contains(x, y)) {
+            return Some(HitTarget::Candidate(index));
+        }
+        if self.candidates.is_empty() || !self.page_box.contains(x, y) {
+            return None;
+        }
+        if self.prev_page.is_some_and(|rect| x < rect.right) {
+            return Some(HitTarget::PrevPage);
+        }
+        if self.next_page.is_some_and(|rect| x >= rect.left) {
+            return Some(HitTarget::NextPage);
+        }
+        Some(HitTarget::PageIndicator)
+    }
+}
+
+pub(crate) fn selkey_label(model: &CandidateListModel, index: usize) -> String {
+    let key = model
+        .selkeys
+        .get(index)
+        .and_then(|&key| char::from_u32(key.into()))
+        .unwrap_or('?');
+    format!("{key}.")
+}
+
+pub(crate) fn page_number_font_size(font_size: f32) -> f32 {
+    (font_size / 1.5).max(0.0)
+}
+
+/// Text shown after a candidate, such as "ㄘㄜˋ ★ 3".
+pub(crate) fn annotation_label(annotation: &CandidateAnnotation) -> String {
+    let frequency = annotation.frequency.map(|freq| freq.to_string());
+    [
+        annotation.gloss.as_str(),
+        annotation.reading.as_str(),
+        if annotation.user_phrase {
+            USER_PHRASE_MARKER
+        } else {
+            ""
+        },
+        frequency.as_deref().unwrap_or_default(),
+    ]
+    .into_iter()
+    .filter(|part| !part.is_empty())
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+pub(crate) fn layout_candidates(
+    model: &CandidateListModel,
+    measure: &impl TextMeasure,
+    dpi: f32,
+) -> Result<CandidateLayout, UiError> {
+    expect_error("Failed to layout candidate list", || {
+        let scale = dpi / 96.0;
+        let spacing = if model.compact { &COMPACT } else { &REGULAR };
+        let mut selkey_width: f32 = 0.0;
+        let mut text_width: f32 = 0.0;
+        let mut item_height: f32 = 0.0;
+        for (i, text) in model.items.iter().enumerate() {
+            let selkey_size = measure.measure(&selkey_label(model, i), model.font_size)?;
+            let item_size = measure.measure(text, model.font_size)?;
+            selkey_width = selkey_width.max(selkey_size.width);
+            text_width = text_width.max(item_size.width);
+            item_height = item_height.max(item_size.height).max(selkey_size.height);
+        }
+        selkey_width += 1.0;
+
+        // Annotations use the smaller font of the page indicator
+        let annotation_font_size = page_number_font_size(model.font_size);
+        let mut annotations = vec![];
+        for i in 0..model.items.len() {
+            let label = model
+                .annotations
+                .get(i)
+                .map(annotation_label)
+                .unwrap_or_default();
+            let size = if label.is_empty() {
+                TextSize::default()
+            } else {
+                measure.measure(&label, annotation_font_size)?
+            };
+            annotations.push((label, size));
+        }
+        let annotation_width = annotations
+            .iter()
+            .map(|(_, size)| size.width)
+            .fold(0.0, f32::max);
+        if annotation_width > 0.0 {
+            text_width += spacing.col / 2.0 + annotation_width;
+        }
+
+        let title_size = if model.title.is_empty() {
+            TextSize::default()
+        } else {
+            measure.measure(&model.title, model.font_size)?
+        };
+        let title_height = if model.title.is_empty() {
+            0.0
+        } else {
+            title_size.height + spacing.row
+        };
+
+        let page_number = format!("{} / {}", model.current_page, model.total_page);
+        let page_font_size = page_number_font_size(model.font_size);
+        let page_size = measure.measure(&page_number, page_font_size)?;
+        let show_arrows = model.show_page_arrows && model.total_page > 1;
+        let arrow_size = if show_arrows {
+            let prev = measure.measure(PREV_PAGE_ARROW, page_font_size)?;
+            let next = measure.measure(NEXT_PAGE_ARROW, page_font_size)?;
+            TextSize {
+                width: prev.width.max(next.width),
+                height: prev.height.max(next.height),
+            }
+        } else {
+            TextSize::default()
+        };
+
+        let cand_per_row = match model.layout {
+            CandidateLayoutMode::Grid => model.cand_per_row as usize,
+            CandidateLayoutMode::Vertical => 1,
+            CandidateLayoutMode::Horizontal => model.items.len(),
+        }
+        .max(1);
+        let cols = model.items.len().clamp(1, cand_per_row) as f32;
+        let rows = model.items.len().div_ceil(cand_per_row).max(1) as f32;
+        let grid_width = cols * (selkey_width + text_width) + (cols - 1.0) * spacing.col;
+        let box_width = grid_width.max(title_size.width) + 2.0 * spacing.margin;
+        let box_height =
+            title_height + rows * item_height + (rows - 1.0) * spacing.row + 2.0 * spacing.margin;
+
+        // The page indicator box is aligned to the right of the candidate box
+        let arrow_gap = spacing.margin / 2.0;
+        let page_content_width = if show_arrows {
+            page_size.width + 2.0 * (arrow_size.width + arrow_gap)
+        } else {
+            page_size.width
+        };
+        let page_content_height = page_size.height.max(arrow_size.height);
+        let page_box_width = page_content_width + spacing.margin;
+        let page_box = Rect::new(
+            (box_width - page_box_width).max(0.0),
+            box_height + PAGE_GAP,
+            page_box_width,
+            page_content_height + spacing.margin,
+        );
+        let mut x = page_box.left + spacing.margin / 2.0;
+        let y = page_box.top + spacing.margin / 2.0;
+        let mut prev_page = None;
+        let mut next_page = None;
+        if show_arrows {
+            prev_page = Some(Rect::new(x, y, arrow_size.width, page_content_height));
+            x += arrow_size.width + arrow_gap;
+        }
+        let page_indicator = Rect::new(x, y, page_size.width, page_content_height);
+        if show_arrows {
+            x += page_size.width + arrow_gap;
+            next_page = Some(Rect::new(x, y, arrow_size.width, page_content_height));
+        }
+
+        let width = box_width.max(page_box.right);
+        let height = page_box.bottom;
+        let mut layout = CandidateLayout {
+            width,
+            height,
+            hw_width: (width * scale + SHADOW_PADDING).ceil() as u32,
+            hw_height: (height * scale + SHADOW_PADDING).ceil() as u32,
+            page_box,
+            page_indicator,
+            prev_page,
+            next_page,
+            ..Default::default()
+        };
+        if model.items.is_empty() {
+            return Ok(layout);
+        }
+
+        let display_list = &mut layout.display_list;
+        display_list.push(DisplayItem::MessageBox {
+            rect: Rect::new(0.0, 0.0, box_width, box_height),
+        });
+        display_list.push(DisplayItem::MessageBox { rect: page_box });
+        if let Some(rect) = prev_page {
+            display_list.push(DisplayItem::Text {
+                text: PREV_PAGE_ARROW.to_string(),
+                rect,
+                font_size: page_font_size,
+                paint: Paint::PageIndicator,
+            });
+        }
+        display_list.push(DisplayItem::Text {
+            text: page_number,
+            rect: page_indicator,
+            font_size: page_font_size,
+            paint: Paint::PageIndicator,
+        });
+        if let Some(rect) = next_page {
+            display_list.push(DisplayItem::Text {
+                text: NEXT_PAGE_ARROW.to_string(),
+                rect,
+                font_size: page_font_size,
+                paint: Paint::PageIndicator,
+            });
+        }
+        if !model.title.is_empty() {
+            let rect = Rect::new(
+                spacing.margin,
+                spacing.margin,
+                title_size.width,
+                title_size.height,
+            );
+            display_list.push(DisplayItem::Text {
+                text: model.title.clone(),
+                rect,
+                font_size: model.font_size,
+                paint: Paint::Text,
+            });
+            layout.title = Some(rect);
+        }
+
+        // Hovering the mouse takes precedence over the keyboard cursor
+        let highlighted = model
+            .hover
+            .or(model.use_cursor.then_some(model.current_sel));
+        for (i, item) in model.items.iter().enumerate() {
+            let col = (i % cand_per_row) as f32;
+            let row = (i / cand_per_row) as f32;
+            let x = spacing.margin + col * (selkey_width + text_width + spacing.col);
+            let y = spacing.margin + title_height + row * (item_height + spacing.row);
+            let cell = Rect::new(x, y, selkey_width + text_width, item_height);
+            let text_rect = Rect::new(x + selkey_width, y, text_width, item_height);
+            display_list.push(DisplayItem::Text {
+                text: selkey_label(model, i),
+                rect: cell,
+                font_size: model.font_size,
+                paint: Paint::SelKey,
+            });
+            let is_highlighted = highlighted == Some(i);
+            let paint = if is_highlighted {
+                display_list.push(DisplayItem::FillRect {
+                    rect: text_rect,
+                    paint: Paint::HighlightBackground,
+                });
+                layout.highlight = Some(text_rect);
+                Paint::HighlightText
+            } else {
+                Paint::Text
+            };
+            display_list.push(DisplayItem::Text {
+                text: item.clone(),
+                rect: text_rect,
+                font_size: model.font_size,
+                paint,
+            });
+            let (label, size) = &annotations[i];
+            if !label.is_empty() {
+                // Column after the candidate text, vertically centered
+                let rect = Rect::new(
+                    text_rect.right - annotation_width,
+                    y + (item_height - size.height) / 2.0,
+                    annotation_width,
+                    size.height,
+                );
+                display_list.push(DisplayItem::Text {
+                    text: label.clone(),
+                    rect,
+                    font_size: annotation_font_size,
+                    paint: if is_highlighted {
+                        Paint::HighlightText
+                    } else {
+                        Paint::Annotation
+                    },
+                });
+            }
+            layout.candidates.push(cell);
+        }
+        Ok(layout)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write;
+
+    use chewing_tip_core::ipc::values::{CandidateAnnotation, CandidateLayoutMode};
+
+    use super::{
+        CandidateLayout, DisplayItem, HitTarget, Rect, TextMeasure, TextSize, annotation_label,
+        layout_candidates,
+    };
+    use crate::{ui::UiError, ui_elements::candidate_list::CandidateListModel};
+
+    /// ASCII characters are half width, everything else is full width.
+    struct FakeMetrics;
+
+    impl TextMeasure for FakeMetrics {
+        fn measure(&self, text: &str, font_size: f32) -> Result<TextSize, UiError> {
+            let width = text
+                .chars()
+                .map(|c| if c.is_ascii() { 0.5 } else { 1.0 })
+                .sum::<f32>();
+            Ok(TextSize {
+                width: width * font_size,
+                height: font_size * 1.25,
+            })
+        }
+    }
+
+    fn model(items: &[&str], cand_per_row: u32) -> CandidateListModel {
+        CandidateListModel {
+            items: items.iter().map(|s| s.to_string()).collect(),
+            selkeys: "1234567890".encode_utf16().collect(),
+            total_page: 2,
+            current_page: 1,
+            font_size: 12.0,
+            cand_per_row,
+            ..Default::default()
+        }
+    }
+
+    fn rect(r: &Rect) -> String {
+        format!("{},{} {}x{}", r.left, r.top, r.width(), r.height())
+    }
+
+    fn snapshot(layout: &CandidateLayout) -> String {
+        let mut out = format!(
+            "size {}x{} hw {}x{}\n",
+            layout.width, layout.height, layout.hw_width, layout.hw_height
+        );
+        for item in &layout.display_list {
+            match item {
+                DisplayItem::MessageBox { rect: r } => writeln!(out, "box {}", rect(r)),
+                DisplayItem::FillRect { rect: r, paint } => {
+                    writeln!(out, "fill {} {paint:?}", rect(r))
+                }
+                DisplayItem::Text {
+                    text,
+                    rect: r,
+                    font_size,
+                    paint,
+                } => writeln!(out, "text {text:?} {} {font_size} {paint:?}", rect(r)),
+            }
+            .unwrap();
+        }
+        out
+    }
+
+    #[test]
+    fn single_row() {
+        let layout = layout_candidates(&model(&["測", "策試"], 10), &FakeMetrics, 96.0).unwrap();
+        assert_eq!(
+            "\
+size 102x57 hw 127x82
+box 0,0 102x35
+box 72,37 30x20
+text \"1 / 2\" 77,42 20x10 8 PageIndicator
+text \"1.\" 10,10 37x15 12 SelKey
+text \"測\" 23,10 24x15 12 Text
+text \"2.\" 55,10 37x15 12 SelKey
+text \"策試\" 68,10 24x15 12 Text
+",
+            snapshot(&layout)
+        );
+        assert_eq!(None, layout.highlight);
+        assert_eq!(2, layout.candidates.len());
+    }
+
+    #[test]
+    fn multiple_rows_with_cursor() {
+        let mut model = model(&["一", "二", "三"], 2);
+        model.use_cursor = true;
+        model.current_sel = 2;
+        let layout = layout_candidates(&model, &FakeMetrics, 144.0).unwrap();
+        assert_eq!(
+            "\
+size 78x76 hw 142x139
+box 0,0 78x54
+box 48,56 30x20
+text \"1 / 2\" 53,61 20x10 8 PageIndicator
+text \"1.\" 10,10 25x15 12 SelKey
+text \"一\" 23,10 12x15 12 Text
+text \"2.\" 43,10 25x15 12 SelKey
+text \"二\" 56,10 12x15 12 Text
+text \"3.\" 10,29 25x15 12 SelKey
+fill 23,29 12x15 HighlightBackground
+text \"三\" 23,29 12x15 12 HighlightText
+",
+            snapshot(&layout)
+        );
+        assert_eq!(Some(Rect::new(23.0, 29.0, 12.0, 15.0)), layout.highlight);
+    }
+
+    #[test]
+    fn empty_model_has_no_display_items() {
+        let layout = layout_candidates(&model(&[], 10), &FakeMetrics, 96.0).unwrap();
+        assert!(layout.display_list.is_empty());
+        assert!(layout.candidates.is_empty());
+    }
+
+    #[test]
+    fn hover_overrides_cursor() {
+        let mut model = model(&["一", "二", "三"], 2);
+        model.use_cursor = true;
+        model.current_sel = 2;
+        model.hover = Some(1);
+        let layout = layout_candidates(&model, &FakeMetrics, 96.0).unwrap();
+        assert_eq!(Some(Rect::new(56.0, 10.0, 12.0, 15.0)), layout.highlight);
+    }
+
+    #[test]
+    fn hit_test() {
+        let layout = layout_candidates(&model(&["一", "二", "三"], 2), &FakeMetrics, 96.0).unwrap();
+        assert_eq!(Some(HitTarget::Candidate(0)), layout.hit_test(10.0, 10.0));
+        assert_eq!(Some(HitTarget::Candidate(0)), layout.hit_test(34.9, 24.9));
+        // spacing between columns and rows
+        assert_eq!(None, layout.hit_test(38.0, 10.0));
+        assert_eq!(None, layout.hit_test(10.0, 26.0));
+        assert_eq!(Some(HitTarget::Candidate(1)), layout.hit_test(43.0, 10.0));
+        assert_eq!(Some(HitTarget::Candidate(2)), layout.hit_test(20.0, 30.0));
+        // margin
+        assert_eq!(None, layout.hit_test(5.0, 5.0));
+        assert_eq!(Some(HitTarget::PageIndicator), layout.hit_test(60.0, 65.0));
+        assert_eq!(None, layout.hit_test(100.0, 100.0));
+    }
+
+    #[test]
+    fn hit_test_empty_model() {
+        let layout = layout_candidates(&model(&[], 10), &FakeMetrics, 96.0).unwrap();
+        assert_eq!(
+            None,
+            layout.hit_test(layout.page_box.left, layout.page_box.top)
+        );
+    }
+
+    #[test]
+    fn vertical_with_title() {
+        let mut model = model(&["測", "策試"], 10);
+        model.layout = CandidateLayoutMode::Vertical;
+        model.title = "ㄘㄜˋ".to_string();
+        let layout = layout_candidates(&model, &FakeMetrics, 96.0).unwrap();
+        assert_eq!(
+            "\
+size 57x95 hw 82x120
+box 0,0 57x73
+box 27,75 30x20
+text \"1 / 2\" 32,80 20x10 8 PageIndicator
+text \"ㄘㄜˋ\" 10,10 36x15 12 Text
+text \"1.\" 10,29 37x15 12 SelKey
+text \"測\" 23,29 24x15 12 Text
+text \"2.\" 10,48 37x15 12 SelKey
+text \"策試\" 23,48 24x15 12 Text
+",
+            snapshot(&layout)
+        );
+    }
+
+    #[test]
+    fn horizontal_compact_with_arrows() {
+        let mut model = model(&["一", "二", "三"], 2);
+        model.layout = CandidateLayoutMode::Horizontal;
+        model.compact = true;
+        model.show_page_arrows = true;
+        let layout = layout_candidates(&model, &FakeMetrics, 96.0).unwrap();
+        assert_eq!(
+            "\
+size 91x39 hw 116x64
+box 0,0 91x23
+box 47,25 44x14
+text \"◀\" 49,27 8x10 8 PageIndicator
+text \"1 / 2\" 59,27 20x10 8 PageIndicator
+text \"▶\" 81,27 8x10 8 PageIndicator
+text \"1.\" 4,4 25x15 12 SelKey
+text \"一\" 17,4 12x15 12 Text
+text \"2.\" 33,4 25x15 12 SelKey
+text \"二\" 46,4 12x15 12 Text
+text \"3.\" 62,4 25x15 12 SelKey
+text \"三\" 75,4 12x15 12 Text
+",
+            snapshot(&layout)
+        );
+    }
+
+    #[test]
+    fn no_arrows_on_single_page() {
+        let mut model = model(&["一"], 2);
+        model.show_page_arrows = true;
+        model.total_page = 1;
+        let layout = layout_candidates(&model, &FakeMetrics, 96.0).unwrap();
+        assert_eq!(None, layout.prev_page);
+        assert_eq!(None, layout.next_page);
+    }
+
+    #[test]
+    fn hit_test_page_arrows() {
+        let mut model = model(&["一", "二", "三"], 2);
+        model.show_page_arrows = true;
+        let layout = layout_candidates(&model, &FakeMetrics, 96.0).unwrap();
+        let prev = layout.prev_page.unwrap();
+        let next = layout.next_page.unwrap();
+        let y = layout.page_indicator.top;
+        assert_eq!(Some(HitTarget::PrevPage), layout.hit_test(prev.left, y));
+        // The padding around an arrow belongs to the arrow
+        assert_eq!(
+            Some(HitTarget::PrevPage),
+            layout.hit_test(layout.page_box.left, layout.page_box.top)
+        );
+        assert_eq!(Some(HitTarget::NextPage), layout.hit_test(next.left, y));
+        assert_eq!(
+            Some(HitTarget::PageIndicator),
+            layout.hit_test(layout.page_indicator.left, y)
+        );
+    }
+
+    #[test]
+    fn annotation_label_parts() {
+        assert_eq!("", annotation_label(&CandidateAnnotation::default()));
+        assert_eq!(
+            "黑星 ★ 3",
+            annotation_label(&CandidateAnnotation {
+                gloss: "黑星".to_string(),
+                user_phrase: true,
+                frequency: Some(3),
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn annotations() {
+        let mut model = model(&["測", "策"], 1);
+        model.use_cursor = true;
+        // Only the first candidate is annotated
+        model.annotations = vec![CandidateAnnotation {
+            reading: "ㄘㄜˋ".to_string(),
+            user_phrase: true,
+            ..Default::default()
+        }];
+        let layout = layout_candidates(&model, &FakeMetrics, 96.0).unwrap();
+        assert_eq!(
+            "\
+size 85x76 hw 110x101
+box 0,0 85x54
+box 55,56 30x20
+text \"1 / 2\" 60,61 20x10 8 PageIndicator
+text \"1.\" 10,10 65x15 12 SelKey
+fill 23,10 52x15 HighlightBackground
+text \"測\" 23,10 52x15 12 HighlightText
+text \"ㄘㄜˋ ★\" 39,12.5 36x10 8 HighlightText
+text \"2.\" 10,29 65x15 12 SelKey
+text \"策\" 23,29 52x15 12 Text
+",
+            snapshot(&layout)
+        );
+    }
+}
@@ -1,50 +1,78 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 // Copyright (c) 2026 Kan-Ru Chen
 
-use std::{cell::RefCell, ops::Div, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
 
+use chewing_tip_core::ipc::values::{
+    CandidateAnnotation, CandidateEvent, CandidateLayoutMode, ScreenRect,
+};
 use error_plus::expect_error;
+use log::debug;
 use windows::Win32::{
     Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, POINT, WPARAM},
     Graphics::{
         Direct2D::{
             Common::{D2D_RECT_F, D2D1_COLOR_F},
-            D2D1_DRAW_TEXT_OPTIONS_NONE, D2D1_FACTORY_TYPE_SINGLE_THREADED, D2D1CreateFactory,
-            ID2D1DeviceContext, ID2D1Factory1,
+            D2D1_DRAW_TEXT_OPTIONS_ENABLE_COLOR_FONT, D2D1_FACTORY_TYPE_SINGLE_THREADED,
+            D2D1CreateFactory, ID2D1DeviceContext, ID2D1Factory1,
         },
         DirectComposition::IDCompositionTarget,
         DirectWrite::{
             DWRITE_FACTORY_TYPE_SHARED, DWRITE_FONT_STRETCH_NORMAL, DWRITE_FONT_STYLE_NORMAL,
             DWRITE_FONT_WEIGHT_NORMAL, DWRITE_MEASURING_MODE_NATURAL, DWRITE_TEXT_METRICS,
-            DWriteCreateFactory, IDWriteFactory1,
+            DWRITE_WORD_WRAPPING_NO_WRAP, DWriteCreateFactory, IDWriteFactory1,
+            IDWriteFontFallback, IDWriteTextFormat, IDWriteTextFormat1,
         },
         Dxgi::{
             Common::DXGI_FORMAT_B8G8R8A8_UNORM, DXGI_PRESENT, DXGI_SWAP_CHAIN_FLAG, IDXGISwapChain1,
         },
         Gdi::{BeginPaint, EndPaint, PAINTSTRUCT},
     },
-    UI::WindowsAndMessaging::{
-        CS_IME, GWLP_USERDATA, GetWindowLongPtrW, HWND_DESKTOP, IDC_ARROW, LoadCursorW,
-        RegisterClassExW, WINDOWPOS, WM_PAINT, WM_WINDOWPOSCHANGING, WNDCLASSEXW, WS_CLIPCHILDREN,
-        WS_EX_NOREDIRECTIONBITMAP, WS_EX_TOOLWINDOW, WS_EX_TOPMOST, WS_POPUP,
+    UI::{
+        Input::KeyboardAndMouse::{TME_LEAVE, TRACKMOUSEEVENT, TrackMouseEvent},
+        WindowsAndMessaging::{
+            CS_IME, GWLP_USERDATA, GetWindowLongPtrW, HWND_DESKTOP, IDC_ARROW, LoadCursorW,
+            MA_NOACTIVATE, RegisterClassExW, WINDOWPOS, WM_GETOBJECT, WM_LBUTTONUP,
+            WM_MOUSEACTIVATE, WM_MOUSELEAVE, WM_MOUSEMOVE, WM_MOUSEWHEEL, WM_PAINT,
+            WM_WINDOWPOSCHANGING, WNDCLASSEXW, WS_CLIPCHILDREN, WS_EX_NOREDIRECTIONBITMAP,
+            WS_EX_TOOLWINDOW, WS_EX_TOPMOST, WS_POPUP,
+        },
     },
 };
-use windows_core::{HSTRING, PCWSTR, w};
-
-use crate::ui::{
-    UiError,
-    gfx::{
-        clamp_point_to_monitor, create_render_target, create_swapchain, create_swapchain_bitmap,
-        d3d11_device, get_dpi_for_point, get_dpi_for_window, setup_direct_composition,
+use windows_core::{HSTRING, Interface, PCWSTR, w};
+
+use crate::{
+    ipc::candidate_events::CANDIDATE_EVENTS,
+    ui::{
+        UiError, fonts,
+        gfx::{
+            clamp_point_to_monitor, create_render_target, create_swapchain,
+            create_swapchain_bitmap, d3d11_device, get_dpi_for_point, get_dpi_for_window, monitors,
+            setup_direct_composition,
+        },
+        message_box::draw_message_box,
+        placement::{Side, place_popup},
+        uia::CandidateListAccessibility,
+        window::Window,
+    },
+    ui_elements::{
+        accessibility::{CandidateTree, candidate_tree},
+        candidate_layout::{
+            CandidateLayout, DisplayItem, HitTarget, Paint, Rect, TextMeasure, TextSize,
+            layout_candidates,
+        },
     },
-    message_box::draw_message_box,
-    window::Window,
 };
 
 #[derive(Debug)]
 pub(crate) struct CandidateList {
     model: RefCell<CandidateListModel>,
     view: RefCell<RenderedView>,
+    /// Side of the caret the window is on while it stays visible
+    side: Cell<Option<Side>>,
 }
 
 #[derive(Default, Debug)]
@@ -54,6 +82,10 @@ pub(crate) struct CandidateListModel {
     pub(crate) total_page: u32,
     pub(crate) current_page: u32,
     pub(crate) font_family: HSTRING,
+    /// Families tried before the system fallback
+    pub(crate) font_fallback: Vec<String>,
+    /// Locale of the candidates, picks the regional glyphs of Han characters
+    pub(crate) locale: HSTRING,
     pub(crate) font_size: f32,
     pub(crate) cand_per_row: u32,
     pub(crate) use_cursor: bool,
@@ -64,6 +96,17 @@ pub(crate) struct CandidateListModel {
     pub(crate) highlight_fg_color: D2D1_COLOR_F,
     pub(crate) highlight_bg_color: D2D1_COLOR_F,
     pub(crate) border_color: D2D1_COLOR_F,
+    pub(crate) page_indicator_color: D2D1_COLOR_F,
+    pub(crate) layout: CandidateLayoutMode,
+    pub(crate) show_page_arrows: bool,
+    pub(crate) title: String,
+    pub(crate) compact: bool,
+    /// Parallel to `items`, may be shorter
+    pub(crate) annotations: Vec<CandidateAnnotation>,
+    /// Candidate under the mouse pointer
+    pub(crate) hover: Option<usize>,
+    /// Caret to place the window around
+    pub(crate) caret: Option<ScreenRect>,
 }
 
 // pub(crate) enum FilterKeyResult {
@@ -97,18 +140,89 @@ extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM
                 let view = this.view.borrow();
                 let model = this.model.borrow();
                 let dpi = get_dpi_for_point(POINT { x: pos.x, y: pos.y });
-                if let Ok(size) = view.calculate_client_rect(&model, dpi) {
-                    pos.cx = size.hw_width as i32;
-                    pos.cy = size.hw_height as i32;
-                    (pos.x, pos.y) = clamp_point_to_monitor(pos.x, pos.y, pos.cx, pos.cy);
+                if let Ok(layout) = view.layout(&model, dpi) {
+                    pos.cx = layout.hw_width as i32;
+                    pos.cy = layout.hw_height as i32;
+                    (pos.x, pos.y) = match model.caret {
+                        Some(caret) => this.place(caret, pos.cx, pos.cy),
+                        None => clamp_point_to_monitor(pos.x, pos.y, pos.cx, pos.cy),
+                    };
                 }
             }
             LRESULT(0)
         }
+        WM_MOUSEACTIVATE => {
+            // Never steal focus from the application
+            LRESULT(MA_NOACTIVATE as isize)
+        }
+        WM_GETOBJECT => {
+            let this = get_this();
+            let view = this.view.borrow();
+            match view.accessibility.get_object(hwnd, wparam, lparam) {
+                Some(result) => result,
+                None => crate::ui::window::wnd_proc(hwnd, msg, wparam, lparam),
+            }
+        }
+        WM_MOUSEMOVE => {
+            let this = get_this();
+            let hover = match this.hit_test(lparam) {
+                Some(HitTarget::Candidate(index)) => Some(index),
+                _ => None,
+            };
+            if hover.is_some() {
+                let mut tme = TRACKMOUSEEVENT {
+                    cbSize: size_of::<TRACKMOUSEEVENT>() as u32,
+                    dwFlags: TME_LEAVE,
+                    hwndTrack: hwnd,
+                    dwHoverTime: 0,
+                };
+                let _ = unsafe { TrackMouseEvent(&mut tme) };
+            }
+            this.set_hover(hover);
+            LRESULT(0)
+        }
+        WM_MOUSELEAVE => {
+            get_this().set_hover(None);
+            LRESULT(0)
+        }
+        WM_LBUTTONUP => {
+            let event = match get_this().hit_test(lparam) {
+                Some(HitTarget::Candidate(index)) => Some(CandidateEvent::CandidateClicked {
+                    index: index as u32,
+                }),
+                Some(HitTarget::PrevPage) => {
+                    Some(CandidateEvent::CandidatePageRequested { delta: -1 })
+                }
+                Some(HitTarget::NextPage | HitTarget::PageIndicator) => {
+                    Some(CandidateEvent::CandidatePageRequested { delta: 1 })
+                }
+                None => None,
+            };
+            if let Some(event) = event {
+                publish_event(event);
+            }
+            LRESULT(0)
+        }
+        WM_MOUSEWHEEL => {
+            let wheel_delta = (wparam.0 >> 16) as u16 as i16;
+            if wheel_delta != 0 {
+                // Scrolling up goes back to the previous page
+                let delta = if wheel_delta > 0 { -1 } else { 1 };
+                publish_event(CandidateEvent::CandidatePageRequested { delta });
+            }
+            LRESULT(0)
+        }
         _ => crate::ui::window::wnd_proc(hwnd, msg, wparam, lparam),
     }
 }
 
+fn publish_event(event: CandidateEvent) {
+    debug!("Candidate window event: {event:?}");
+    if !CANDIDATE_EVENTS.publish(event) {
+        debug!("No TIP session is watching candidate events");
+    }
+}
+
 #[derive(Debug)]
 struct RenderedView {
     _factory: ID2D1Factory1,
@@ -117,17 +231,9 @@ struct RenderedView {
     target: ID2D1DeviceContext,
     swapchain: IDXGISwapChain1,
     window: Window,
-}
-
-#[derive(Debug)]
-struct RenderedMetrics {
-    width: f32,
-    height: f32,
-    hw_width: f32,
-    hw_height: f32,
-    selkey_width: f32,
-    text_width: f32,
-    item_height: f32,
+    /// Layout of the last painted frame, used for hit testing
+    last_layout: RefCell<CandidateLayout>,
+    accessibility: CandidateListAccessibility,
 }
 
 impl RenderedView {
@@ -151,6 +257,11 @@ impl RenderedView {
                 let swapchain = create_swapchain(&device, 10, 10)?;
                 create_swapchain_bitmap(&swapchain, &target)?;
                 let dcomptarget = setup_direct_composition(&device, window.hwnd(), &swapchain)?;
+                let accessibility = CandidateListAccessibility::new(window.hwnd(), |index| {
+                    publish_event(CandidateEvent::CandidateClicked {
+                        index: index as u32,
+                    })
+                });
                 Ok(RenderedView {
                     _factory: factory,
                     _dcomptarget: dcomptarget,
@@ -158,161 +269,133 @@ impl RenderedView {
                     target,
                     swapchain,
                     window,
+                    last_layout: RefCell::default(),
+                    accessibility,
                 })
             }
         })
     }
 }
 
-const ROW_SPACING: f32 = 4.0;
-const COL_SPACING: f32 = 8.0;
+/// DirectWrite text formats of one font family, cached by font size.
+pub(crate) struct DWriteText<'a> {
+    factory: &'a IDWriteFactory1,
+    font_family: &'a HSTRING,
+    locale: &'a HSTRING,
+    font_fallback: Option<IDWriteFontFallback>,
+    formats: RefCell<Vec<(f32, IDWriteTextFormat)>>,
+}
 
-impl RenderedView {
-    fn window(&self) -> &Window {
-        &self.window
+impl DWriteText<'_> {
+    pub(crate) fn new<'a>(
+        factory: &'a IDWriteFactory1,
+        font_family: &'a HSTRING,
+        font_fallback: &[String],
+        locale: &'a HSTRING,
+    ) -> Result<DWriteText<'a>, UiError> {
+        Ok(DWriteText {
+            factory,
+            font_family,
+            locale,
+            font_fallback: fonts::font_fallback(factory, font_fallback, locale)?,
+            formats: RefCell::new(vec![]),
+        })
     }
-    fn calculate_client_rect(
-        &self,
-        model: &CandidateListModel,
-        dpi: f32,
-    ) -> Result<RenderedMetrics, UiError> {
-        expect_error("Failed to calculate client area", || {
-            // Create a text format for the candidate list
-            let scale = dpi / 96.0;
-            let text_format = unsafe {
-                self.dwrite_factory.CreateTextFormat(
-                    &model.font_family,
-                    None,
-                    DWRITE_FONT_WEIGHT_NORMAL,
-                    DWRITE_FONT_STYLE_NORMAL,
-                    DWRITE_FONT_STRETCH_NORMAL,
-                    model.font_size,
-                    w!("zh-TW"),
-                )?
-            };
-            let page_number_format = unsafe {
-                self.dwrite_factory.CreateTextFormat(
-                    &model.font_family,
+    pub(crate) fn text_format(&self, font_size: f32) -> Result<IDWriteTextFormat, UiError> {
+        expect_error("Failed to create text format", || {
+            if let Some((_, format)) = self
+                .formats
+                .borrow()
+                .iter()
+                .find(|(size, _)| *size == font_size)
+            {
+                return Ok(format.clone());
+            }
+            let format = unsafe {
+                let format = self.factory.CreateTextFormat(
+                    self.font_family,
                     None,
                     DWRITE_FONT_WEIGHT_NORMAL,
                     DWRITE_FONT_STYLE_NORMAL,
                     DWRITE_FONT_STRETCH_NORMAL,
-                    model.font_size.div(1.5).clamp(0.0, f32::MAX),
-                    w!("zh-TW"),
-                )?
-            };
-            // Recalculate the size of the window
-            let margin: f32 = 10.0;
-            let mut selkey_width: f32 = 0.0;
-            let mut text_width: f32 = 0.0;
-            let mut item_height: f32 = 0.0;
-            let mut selkey = "?.".to_string().encode_utf16().collect::<Vec<_>>();
-            for (key, text) in model.selkeys.iter().zip(model.items.iter()) {
-                let mut selkey_metrics = DWRITE_TEXT_METRICS::default();
-                let mut item_metrics = DWRITE_TEXT_METRICS::default();
-
-                selkey[0] = *key;
-                unsafe {
-                    self.dwrite_factory
-                        .CreateTextLayout(&selkey, &text_format, f32::MAX, f32::MAX)?
-                        .GetMetrics(&mut selkey_metrics)?;
-
-                    self.dwrite_factory
-                        .CreateTextLayout(&HSTRING::from(text), &text_format, f32::MAX, f32::MAX)?
-                        .GetMetrics(&mut item_metrics)?;
+                    font_size,
+                    self.locale,
+                )?;
+                format.SetWordWrapping(DWRITE_WORD_WRAPPING_NO_WRAP)?;
+                if let Some(fallback) = &self.font_fallback {
+                    format
+                        .cast::<IDWriteTextFormat1>()?
+                        .SetFontFallback(fallback)?;
                 }
+                format
+            };
+            self.formats.borrow_mut().push((font_size, format.clone()));
+            Ok(format)
+        })
+    }
+}
 
-                selkey_width = selkey_width.max(selkey_metrics.widthIncludingTrailingWhitespace);
-                text_width = text_width.max(item_metrics.widthIncludingTrailingWhitespace);
-                item_height = item_height
-                    .max(item_metrics.height)
-                    .max(selkey_metrics.height);
-            }
-            selkey_width += 1.0;
-
-            let page_number =
-                HSTRING::from(format!("{} / {}", model.current_page, model.total_page));
-
-            // Calculate the size of the page_numer box
+impl TextMeasure for DWriteText<'_> {
+    fn measure(&self, text: &str, font_size: f32) -> Result<TextSize, UiError> {
+        expect_error("Failed to measure text", || {
+            let text_format = self.text_format(font_size)?;
             let mut metrics = DWRITE_TEXT_METRICS::default();
             unsafe {
-                self.dwrite_factory
-                    .CreateTextLayout(&page_number, &page_number_format, f32::MAX, f32::MAX)?
+                self.factory
+                    .CreateTextLayout(&HSTRING::from(text), &text_format, f32::MAX, f32::MAX)?
                     .GetMetrics(&mut metrics)?;
             }
-
-            let items_len = model.items.len() as f32;
-            let cand_per_row = items_len
-                .min(model.cand_per_row as f32)
-                .clamp(1.0, f32::MAX);
-            let rows = (items_len / cand_per_row).clamp(1.0, f32::MAX).ceil();
-            let width = cand_per_row * (selkey_width + text_width)
-                + (cand_per_row - 1.0) * COL_SPACING
-                + 2.0 * margin;
-            let height = rows * item_height + (rows - 1.0) * ROW_SPACING + 2.0 * margin;
-            let window_width = width.max(metrics.width + margin);
-            let window_height = height + metrics.height + margin + 2.0;
-
-            // Convert to HW pixels
-            let hw_width = (window_width * scale + 25.0).ceil();
-            let hw_height = (window_height * scale + 25.0).ceil();
-            Ok(RenderedMetrics {
-                width,
-                height,
-                hw_width,
-                hw_height,
-                selkey_width,
-                text_width,
-                item_height,
+            Ok(TextSize {
+                width: metrics.widthIncludingTrailingWhitespace,
+                height: metrics.height,
             })
         })
     }
+}
+
+pub(crate) fn d2d_rect(rect: &Rect) -> D2D_RECT_F {
+    D2D_RECT_F {
+        left: rect.left,
+        top: rect.top,
+        right: rect.right,
+        bottom: rect.bottom,
+    }
+}
+
+impl RenderedView {
+    fn window(&self) -> &Window {
+        &self.window
+    }
+    fn layout(&self, model: &CandidateListModel, dpi: f32) -> Result<CandidateLayout, UiError> {
+        let text = DWriteText::new(
+            &self.dwrite_factory,
+            &model.font_family,
+            &model.font_fallback,
+            &model.locale,
+        )?;
+        layout_candidates(model, &text, dpi)
+    }
     fn on_paint(&self, model: &CandidateListModel) -> Result<(), UiError> {
         expect_error("Failed to paint UI", || {
             if model.items.is_empty() {
+                self.last_layout.take();
+                self.accessibility.update(CandidateTree::default());
                 return Ok(());
             }
-            // Create a text format for the candidate list
-            let text_format = unsafe {
-                self.dwrite_factory.CreateTextFormat(
-                    &model.font_family,
-                    None,
-                    DWRITE_FONT_WEIGHT_NORMAL,
-                    DWRITE_FONT_STYLE_NORMAL,
-                    DWRITE_FONT_STRETCH_NORMAL,
-                    model.font_size,
-                    w!("zh-TW"),
-                )?
-            };
-            let page_number_format = unsafe {
-                self.dwrite_factory.CreateTextFormat(
-                    &model.font_family,
-                    None,
-                    DWRITE_FONT_WEIGHT_NORMAL,
-                    DWRITE_FONT_STYLE_NORMAL,
-                    DWRITE_FONT_STRETCH_NORMAL,
-                    model.font_size.div(1.5).clamp(0.0, f32::MAX),
-                    w!("zh-TW"),
-                )?
-            };
-
             let dpi = get_dpi_for_window(self.window.hwnd());
-            let rm = self.calculate_client_rect(model, dpi)?;
-            let RenderedMetrics {
-                width,
-                height,
-                hw_width,
-                hw_height,
-                selkey_width,
-                text_width,
-                item_height,
-            } = rm;
+            let text = DWriteText::new(
+                &self.dwrite_factory,
+                &model.font_family,
+                &model.font_fallback,
+                &model.locale,
+            )?;
+            let layout = layout_candidates(model, &text, dpi)?;
             unsafe {
                 self.target.SetTarget(None);
                 self.swapchain.ResizeBuffers(
                     0,
-                    hw_width as u32,
-                    hw_height as u32,
+                    layout.hw_width,
+                    layout.hw_height,
                     DXGI_FORMAT_B8G8R8A8_UNORM,
                     DXGI_SWAP_CHAIN_FLAG(0),
                 )?;
@@ -325,116 +408,52 @@ impl RenderedView {
             unsafe {
                 dc.BeginDraw();
 
-                let mut col = 0;
-                let margin = 10.0;
-                let mut x = margin;
-                let mut y = margin;
-
-                let page_number =
-                    HSTRING::from(format!("{} / {}", model.current_page, model.total_page));
-
-                // Calculate the size of the page_numer box
-                let mut metrics = DWRITE_TEXT_METRICS::default();
-                self.dwrite_factory
-                    .CreateTextLayout(&page_number, &page_number_format, f32::MAX, f32::MAX)?
-                    .GetMetrics(&mut metrics)?;
-
-                // Draw the background of the main message box
-                draw_message_box(
-                    dc,
-                    0.0,
-                    0.0,
-                    width,
-                    height,
-                    model.bg_color,
-                    model.border_color,
-                )?;
-                // Draw the background of the page_number box
-                draw_message_box(
-                    dc,
-                    width - metrics.width - margin,
-                    height + 2.0,
-                    metrics.width + margin,
-                    metrics.height + margin,
-                    model.bg_color,
-                    model.border_color,
-                )?;
-
                 let selkey_brush = dc.CreateSolidColorBrush(&model.selkey_color, None)?;
                 let text_brush = dc.CreateSolidColorBrush(&model.fg_color, None)?;
                 let highlight_brush = dc.CreateSolidColorBrush(&model.highlight_bg_color, None)?;
                 let selected_text_brush =
                     dc.CreateSolidColorBrush(&model.highlight_fg_color, None)?;
-
-                dc.DrawText(
-                    &page_number,
-                    &page_number_format,
-                    &D2D_RECT_F {
-                        left: width - metrics.width - margin / 2.0,
-                        top: height + 2.0 + margin / 2.0,
-                        right: f32::MAX,
-                        bottom: f32::MAX,
-                    },
-                    &text_brush,
-                    D2D1_DRAW_TEXT_OPTIONS_NONE,
-                    DWRITE_MEASURING_MODE_NATURAL,
-                );
-
-                for i in 0..model.items.len() {
-                    let mut text_rect = D2D_RECT_F {
-                        left: x,
-                        top: y,
-                        right: x + selkey_width + text_width,
-                        bottom: y + item_height,
-                    };
-                    let mut selkey = "?.".to_string().encode_utf16().collect::<Vec<_>>();
-                    selkey[0] = model.selkeys.get(i.clamp(0, 9)).cloned().unwrap_or(0x3F);
-
-                    dc.DrawText(
-                        &selkey,
-                        &text_format,
-                        &text_rect,
-                        &selkey_brush,
-                        D2D1_DRAW_TEXT_OPTIONS_NONE,
-                        DWRITE_MEASURING_MODE_NATURAL,
-                    );
-
-                    text_rect.left += selkey_width;
-                    text_rect.right = text_rect.left + text_width;
-
-                    if let Some(item) = model.items.get(i) {
-                        let text = HSTRING::from(item);
-
-                        if model.use_cursor && i == model.current_sel {
-                            dc.FillRectangle(&text_rect, &highlight_brush);
-                            dc.DrawText(
-                                &text,
-                                &text_format,
-                                &text_rect,
-                                &selected_text_brush,
-                                D2D1_DRAW_TEXT_OPTIONS_NONE,
-                                DWRITE_MEASURING_MODE_NATURAL,
-                            );
-                        } else {
+                let page_indicator_brush =
+                    dc.CreateSolidColorBrush(&model.page_indicator_color, None)?;
+                let brush = |paint| match paint {
+                    Paint::SelKey => &selkey_brush,
+                    Paint::Text => &text_brush,
+                    Paint::HighlightText => &selected_text_brush,
+                    Paint::HighlightBackground => &highlight_brush,
+                    Paint::PageIndicator | Paint::Annotation => &page_indicator_brush,
+                };
+
+                for item in &layout.display_list {
+                    match item {
+                        DisplayItem::MessageBox { rect } => draw_message_box(
+                            dc,
+                            rect.left,
+                            rect.top,
+                            rect.width(),
+                            rect.height(),
+                            model.bg_color,
+                            model.border_color,
+                        )?,
+                        DisplayItem::FillRect { rect, paint } => {
+                            dc.FillRectangle(&d2d_rect(rect), brush(*paint));
+                        }
+                        DisplayItem::Text {
+                            text: content,
+                            rect,
+                            font_size,
+                            paint,
+                        } => {
                             dc.DrawText(
-                                &text,
-                                &text_format,
-                                &text_rect,
-                                &text_brush,
-                                D2D1_DRAW_TEXT_OPTIONS_NONE,
+                                &HSTRING::from(content),
+                                &text.text_format(*font_size)?,
+                                &d2d_rect(rect),
+                                brush(*paint),
+                                // Emoji are drawn in color
+                                D2D1_DRAW_TEXT_OPTIONS_ENABLE_COLOR_FONT,
                                 DWRITE_MEASURING_MODE_NATURAL,
                             );
                         }
                     }
-
-                    col += 1;
-                    if col >= model.cand_per_row {
-                        col = 0;
-                        x = margin;
-                        y += item_height + ROW_SPACING;
-                    } else {
-                        x += selkey_width + text_width + COL_SPACING;
-                    }
                 }
 
                 dc.EndDraw(None, None)?;
@@ -442,6 +461,8 @@ impl RenderedView {
                 // Present the draw buffer
                 self.swapchain.Present(1, DXGI_PRESENT(0)).ok()?;
             }
+            self.accessibility.update(candidate_tree(model, &layout));
+            self.last_layout.replace(layout);
 
             Ok(())
         })
@@ -473,6 +494,7 @@ impl CandidateList {
                 .write(CandidateList {
                     model: RefCell::new(CandidateListModel::default()),
                     view: RefCell::new(RenderedView::new(user_data.cast())?),
+                    side: Cell::new(None),
                 });
             // SAFETY: candidate list is unconditionally initialized
             unsafe { Ok(candidate_list.assume_init()) }
@@ -481,6 +503,31 @@ impl CandidateList {
     pub(crate) fn set_model(&self, model: CandidateListModel) {
         *self.model.borrow_mut() = model;
     }
+    /// Places the window around `caret`, keeping it on the same side while
+    /// paging changes its height.
+    fn place(&self, caret: ScreenRect, width: i32, height: i32) -> (i32, i32) {
+        let (x, y, side) = place_popup(caret, width, height, &monitors(), self.side.get());
+        self.side.set(Some(side));
+        (x, y)
+    }
+    /// Hit tests the client coordinates packed in `lparam` of a mouse message.
+    fn hit_test(&self, lparam: LPARAM) -> Option<HitTarget> {
+        let view = self.view.borrow();
+        let x = (lparam.0 & 0xffff) as u16 as i16 as f32;
+        let y = ((lparam.0 >> 16) & 0xffff) as u16 as i16 as f32;
+        let scale = get_dpi_for_window(view.window().hwnd()) / 96.0;
+        view.last_layout.borrow().hit_test(x / scale, y / scale)
+    }
+    fn set_hover(&self, hover: Option<usize>) {
+        {
+            let mut model = self.model.borrow_mut();
+            if model.hover == hover {
+                return;
+            }
+            model.hover = hover;
+        }
+        self.view.borrow().window().refresh();
+    }
     // pub(crate) fn filter_key_event(&self, ksym: Keysym) -> FilterKeyResult {
     //     let mut res = FilterKeyResult::NotHandled;
     //     {
@@ -567,5 +614,7 @@ impl CandidateList {
         let view = self.view.borrow();
         let window = view.window();
         window.hide();
+        view.accessibility.update(CandidateTree::default());
+        self.side.set(None);
     }
 }
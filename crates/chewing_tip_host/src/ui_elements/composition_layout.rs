@@ -0,0 +1,178 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2026 Kan-Ru Chen
+
+//! Platform independent layout of the floating composition window.
+
+use error_plus::expect_error;
+
+use crate::{
+    ui::UiError,
+    ui_elements::{
+        candidate_layout::{Rect, TextMeasure},
+        composition_window::CompositionModel,
+    },
+};
+
+const MARGIN: f32 = 6.0;
+const UNDERLINE_WIDTH: f32 = 1.0;
+/// Space between the underlines of adjacent segments
+const SEGMENT_GAP: f32 = 2.0;
+const CARET_WIDTH: f32 = 1.0;
+/// Extra room in HW pixels for the drop shadow of the message box
+const SHADOW_PADDING: f32 = 25.0;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct CompositionLayout {
+    /// Size of the window content in DIPs
+    pub(crate) width: f32,
+    pub(crate) height: f32,
+    /// Size of the window in HW pixels
+    pub(crate) hw_width: u32,
+    pub(crate) hw_height: u32,
+    pub(crate) text: Rect,
+    /// Background of the unfinished zhuyin syllable
+    pub(crate) bopomofo: Option<Rect>,
+    /// One underline below each phrase segment
+    pub(crate) underlines: Vec<Rect>,
+    pub(crate) caret: Rect,
+}
+
+pub(crate) fn layout_composition(
+    model: &CompositionModel,
+    measure: &impl TextMeasure,
+    dpi: f32,
+) -> Result<CompositionLayout, UiError> {
+    expect_error("Failed to layout composition", || {
+        let scale = dpi / 96.0;
+        // Offsets of every char boundary, measured with the preceding text so
+        // kerning and shaping are taken into account
+        let mut prefix = String::new();
+        let mut offsets = vec![0.0];
+        for ch in model.preedit.chars() {
+            prefix.push(ch);
+            offsets.push(measure.measure(&prefix, model.font_size)?.width);
+        }
+        let text_size = measure.measure(&model.preedit, model.font_size)?;
+        let x = |index: usize| MARGIN + offsets[index.min(offsets.len() - 1)];
+
+        let text = Rect::new(MARGIN, MARGIN, text_size.width, text_size.height);
+        let bopomofo = model
+            .bopomofo
+            .filter(|(start, end)| start < end)
+            .map(|(start, end)| Rect::new(x(start), text.top, x(end) - x(start), text.height()));
+        let underlines = model
+            .segments
+            .iter()
+            .filter(|(start, end)| start < end)
+            .map(|&(start, end)| {
+                Rect::new(
+                    x(start) + SEGMENT_GAP / 2.0,
+                    text.bottom + UNDERLINE_WIDTH,
+                    (x(end) - x(start) - SEGMENT_GAP).max(UNDERLINE_WIDTH),
+                    UNDERLINE_WIDTH,
+                )
+            })
+            .collect();
+        let caret = Rect::new(x(model.cursor), text.top, CARET_WIDTH, text.height());
+
+        // Leave room for the caret after the last char
+        let width = text.width() + CARET_WIDTH + 2.0 * MARGIN;
+        let height = text.height() + 2.0 * MARGIN;
+        Ok(CompositionLayout {
+            width,
+            height,
+            hw_width: (width * scale + SHADOW_PADDING).ceil() as u32,
+            hw_height: (height * scale + SHADOW_PADDING).ceil() as u32,
+            text,
+            bopomofo,
+            underlines,
+            caret,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CompositionLayout, layout_composition};
+    use crate::{
+        ui::UiError,
+        ui_elements::{
+            candidate_layout::{Rect, TextMeasure, TextSize},
+            composition_window::CompositionModel,
+        },
+    };
+
+    struct FakeMetrics;
+
+    impl TextMeasure for FakeMetrics {
+        fn measure(&self, text: &str, font_size: f32) -> Result<TextSize, UiError> {
+            let width = text
+                .chars()
+                .map(|c| if c.is_ascii() { 0.5 } else { 1.0 })
+                .sum::<f32>();
+            Ok(TextSize {
+                width: width * font_size,
+                height: font_size * 1.25,
+            })
+        }
+    }
+
+    fn layout(
+        preedit: &str,
+        segments: &[(usize, usize)],
+        bopomofo: Option<(usize, usize)>,
+        cursor: usize,
+    ) -> CompositionLayout {
+        let model = CompositionModel {
+            preedit: preedit.to_string(),
+            segments: segments.to_vec(),
+            bopomofo,
+            cursor,
+            font_size: 10.0,
+            ..Default::default()
+        };
+        layout_composition(&model, &FakeMetrics, 96.0).unwrap()
+    }
+
+    #[test]
+    fn segments_and_caret() {
+        let layout = layout("測試新酷音", &[(0, 2), (2, 5)], None, 2);
+        assert_eq!(63.0, layout.width);
+        assert_eq!(24.5, layout.height);
+        assert_eq!((88, 50), (layout.hw_width, layout.hw_height));
+        assert_eq!(Rect::new(6.0, 6.0, 50.0, 12.5), layout.text);
+        assert_eq!(
+            vec![
+                Rect::new(7.0, 19.5, 18.0, 1.0),
+                Rect::new(27.0, 19.5, 28.0, 1.0)
+            ],
+            layout.underlines
+        );
+        assert_eq!(Rect::new(26.0, 6.0, 1.0, 12.5), layout.caret);
+        assert_eq!(None, layout.bopomofo);
+    }
+
+    #[test]
+    fn bopomofo_splits_segment() {
+        let layout = layout("測ㄕ試", &[(0, 1), (1, 2), (2, 3)], Some((1, 2)), 1);
+        assert_eq!(Some(Rect::new(16.0, 6.0, 10.0, 12.5)), layout.bopomofo);
+        assert_eq!(3, layout.underlines.len());
+        assert_eq!(Rect::new(16.0, 6.0, 1.0, 12.5), layout.caret);
+    }
+
+    #[test]
+    fn caret_at_end() {
+        let layout = layout("ab", &[(0, 2)], None, 2);
+        assert_eq!(Rect::new(16.0, 6.0, 1.0, 12.5), layout.caret);
+        // Out of range cursor is clamped
+        assert_eq!(layout.caret, self::layout("ab", &[(0, 2)], None, 9).caret);
+    }
+
+    #[test]
+    fn empty_preedit() {
+        let layout = layout("", &[], Some((0, 0)), 0);
+        assert_eq!(Rect::new(6.0, 6.0, 0.0, 12.5), layout.text);
+        assert_eq!(None, layout.bopomofo);
+        assert!(layout.underlines.is_empty());
+    }
+}
@@ -0,0 +1,323 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2026 Kan-Ru Chen
+
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use chewing_tip_core::ipc::values::ScreenRect;
+use error_plus::expect_error;
+use windows::Win32::{
+    Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, POINT, RECT, WPARAM},
+    Graphics::{
+        Direct2D::{
+            Common::D2D1_COLOR_F, D2D1_DRAW_TEXT_OPTIONS_ENABLE_COLOR_FONT,
+            D2D1_FACTORY_TYPE_SINGLE_THREADED, D2D1CreateFactory, ID2D1DeviceContext,
+            ID2D1Factory1,
+        },
+        DirectComposition::IDCompositionTarget,
+        DirectWrite::{
+            DWRITE_FACTORY_TYPE_SHARED, DWRITE_MEASURING_MODE_NATURAL, DWriteCreateFactory,
+            IDWriteFactory1,
+        },
+        Dxgi::{
+            Common::DXGI_FORMAT_B8G8R8A8_UNORM, DXGI_PRESENT, DXGI_SWAP_CHAIN_FLAG, IDXGISwapChain1,
+        },
+        Gdi::{BeginPaint, EndPaint, PAINTSTRUCT},
+    },
+    UI::WindowsAndMessaging::{
+        CS_IME, GWLP_USERDATA, GetWindowLongPtrW, HWND_DESKTOP, IDC_ARROW, LoadCursorW,
+        MA_NOACTIVATE, RegisterClassExW, WINDOWPOS, WM_MOUSEACTIVATE, WM_PAINT,
+        WM_WINDOWPOSCHANGING, WNDCLASSEXW, WS_CLIPCHILDREN, WS_EX_NOREDIRECTIONBITMAP,
+        WS_EX_TOOLWINDOW, WS_EX_TOPMOST, WS_POPUP,
+    },
+};
+use windows_core::{HSTRING, PCWSTR, w};
+
+use crate::{
+    ui::{
+        UiError,
+        gfx::{
+            clamp_point_to_monitor, create_render_target, create_swapchain,
+            create_swapchain_bitmap, d3d11_device, get_dpi_for_point, get_dpi_for_window, monitors,
+            setup_direct_composition,
+        },
+        message_box::draw_message_box,
+        placement::{Side, place_popup},
+        window::Window,
+    },
+    ui_elements::{
+        candidate_list::{DWriteText, d2d_rect},
+        composition_layout::{CompositionLayout, layout_composition},
+    },
+};
+
+/// Opacity of the zhuyin syllable background so the text stays readable
+const BOPOMOFO_OPACITY: f32 = 0.3;
+
+/// Preedit window for applications without inline composition.
+#[derive(Debug)]
+pub(crate) struct CompositionWindow {
+    model: RefCell<CompositionModel>,
+    view: RefCell<RenderedView>,
+    /// Side of the caret the window is on while it stays visible
+    side: Cell<Option<Side>>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct CompositionModel {
+    pub(crate) preedit: String,
+    pub(crate) segments: Vec<(usize, usize)>,
+    pub(crate) bopomofo: Option<(usize, usize)>,
+    pub(crate) cursor: usize,
+    pub(crate) font_family: HSTRING,
+    /// Families tried before the system fallback
+    pub(crate) font_fallback: Vec<String>,
+    /// Locale of the preedit, picks the regional glyphs of Han characters
+    pub(crate) locale: HSTRING,
+    pub(crate) font_size: f32,
+    pub(crate) fg_color: D2D1_COLOR_F,
+    pub(crate) bg_color: D2D1_COLOR_F,
+    pub(crate) highlight_bg_color: D2D1_COLOR_F,
+    pub(crate) border_color: D2D1_COLOR_F,
+    /// Caret to place the window around
+    pub(crate) caret: Option<ScreenRect>,
+}
+
+extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    let get_this = || unsafe {
+        let this_ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *const CompositionWindow;
+        Rc::increment_strong_count(this_ptr);
+        Rc::from_raw(this_ptr)
+    };
+    match msg {
+        WM_PAINT => {
+            let this = get_this();
+            let view = this.view.borrow();
+            let window = view.window();
+            let model = this.model.borrow();
+            let mut ps = PAINTSTRUCT::default();
+            unsafe { BeginPaint(window.hwnd(), &mut ps) };
+            let _ = view.on_paint(&model);
+            let _ = unsafe { EndPaint(window.hwnd(), &ps) };
+            LRESULT(0)
+        }
+        WM_WINDOWPOSCHANGING => {
+            let pos = lparam.0 as *mut WINDOWPOS;
+            if let Some(pos) = unsafe { pos.as_mut() } {
+                let this = get_this();
+                let view = this.view.borrow();
+                let model = this.model.borrow();
+                let dpi = get_dpi_for_point(POINT { x: pos.x, y: pos.y });
+                if let Ok(layout) = view.layout(&model, dpi) {
+                    pos.cx = layout.hw_width as i32;
+                    pos.cy = layout.hw_height as i32;
+                    (pos.x, pos.y) = match model.caret {
+                        Some(caret) => this.place(caret, pos.cx, pos.cy),
+                        None => clamp_point_to_monitor(pos.x, pos.y, pos.cx, pos.cy),
+                    };
+                }
+            }
+            LRESULT(0)
+        }
+        WM_MOUSEACTIVATE => {
+            // Never steal focus from the application
+            LRESULT(MA_NOACTIVATE as isize)
+        }
+        _ => crate::ui::window::wnd_proc(hwnd, msg, wparam, lparam),
+    }
+}
+
+#[derive(Debug)]
+struct RenderedView {
+    _factory: ID2D1Factory1,
+    _dcomptarget: IDCompositionTarget,
+    dwrite_factory: IDWriteFactory1,
+    target: ID2D1DeviceContext,
+    swapchain: IDXGISwapChain1,
+    window: Window,
+}
+
+impl RenderedView {
+    fn new(user_data: *const CompositionWindow) -> Result<RenderedView, UiError> {
+        expect_error("Failed to create new RenderedView", || {
+            let window = Window::new();
+            window.create(
+                HWND_DESKTOP,
+                w!("ChewingCompositionWindow"),
+                WS_POPUP | WS_CLIPCHILDREN,
+                WS_EX_NOREDIRECTIONBITMAP | WS_EX_TOOLWINDOW | WS_EX_TOPMOST,
+                user_data.cast(),
+            );
+            unsafe {
+                let factory: ID2D1Factory1 =
+                    D2D1CreateFactory(D2D1_FACTORY_TYPE_SINGLE_THREADED, None)?;
+                let dwrite_factory: IDWriteFactory1 =
+                    DWriteCreateFactory(DWRITE_FACTORY_TYPE_SHARED)?;
+                let device = d3d11_device()?;
+                let target = create_render_target(&factory, &device)?;
+                let swapchain = create_swapchain(&device, 10, 10)?;
+                create_swapchain_bitmap(&swapchain, &target)?;
+                let dcomptarget = setup_direct_composition(&device, window.hwnd(), &swapchain)?;
+                Ok(RenderedView {
+                    _factory: factory,
+                    _dcomptarget: dcomptarget,
+                    dwrite_factory,
+                    target,
+                    swapchain,
+                    window,
+                })
+            }
+        })
+    }
+}
+
+impl RenderedView {
+    fn window(&self) -> &Window {
+        &self.window
+    }
+    fn layout(&self, model: &CompositionModel, dpi: f32) -> Result<CompositionLayout, UiError> {
+        let text = DWriteText::new(
+            &self.dwrite_factory,
+            &model.font_family,
+            &model.font_fallback,
+            &model.locale,
+        )?;
+        layout_composition(model, &text, dpi)
+    }
+    fn on_paint(&self, model: &CompositionModel) -> Result<(), UiError> {
+        expect_error("Failed to paint UI", || {
+            if model.preedit.is_empty() {
+                return Ok(());
+            }
+            let dpi = get_dpi_for_window(self.window.hwnd());
+            let text = DWriteText::new(
+                &self.dwrite_factory,
+                &model.font_family,
+                &model.font_fallback,
+                &model.locale,
+            )?;
+            let layout = layout_composition(model, &text, dpi)?;
+            unsafe {
+                self.target.SetTarget(None);
+                self.swapchain.ResizeBuffers(
+                    0,
+                    layout.hw_width,
+                    layout.hw_height,
+                    DXGI_FORMAT_B8G8R8A8_UNORM,
+                    DXGI_SWAP_CHAIN_FLAG(0),
+                )?;
+                self.target.SetDpi(dpi, dpi);
+            }
+            create_swapchain_bitmap(&self.swapchain, &self.target)?;
+
+            // Begin drawing
+            let dc = &self.target;
+            unsafe {
+                dc.BeginDraw();
+
+                draw_message_box(
+                    dc,
+                    0.0,
+                    0.0,
+                    layout.width,
+                    layout.height,
+                    model.bg_color,
+                    model.border_color,
+                )?;
+
+                let text_brush = dc.CreateSolidColorBrush(&model.fg_color, None)?;
+                if let Some(rect) = &layout.bopomofo {
+                    let bopomofo_brush =
+                        dc.CreateSolidColorBrush(&model.highlight_bg_color, None)?;
+                    bopomofo_brush.SetOpacity(BOPOMOFO_OPACITY);
+                    dc.FillRectangle(&d2d_rect(rect), &bopomofo_brush);
+                }
+                dc.DrawText(
+                    &HSTRING::from(&model.preedit),
+                    &text.text_format(model.font_size)?,
+                    &d2d_rect(&layout.text),
+                    &text_brush,
+                    D2D1_DRAW_TEXT_OPTIONS_ENABLE_COLOR_FONT,
+                    DWRITE_MEASURING_MODE_NATURAL,
+                );
+                for rect in &layout.underlines {
+                    dc.FillRectangle(&d2d_rect(rect), &text_brush);
+                }
+                dc.FillRectangle(&d2d_rect(&layout.caret), &text_brush);
+
+                dc.EndDraw(None, None)?;
+
+                // Present the draw buffer
+                self.swapchain.Present(1, DXGI_PRESENT(0)).ok()?;
+            }
+            Ok(())
+        })
+    }
+}
+
+impl CompositionWindow {
+    pub(crate) fn window_register_class(hinst: HINSTANCE) {
+        let wc = WNDCLASSEXW {
+            cbSize: size_of::<WNDCLASSEXW>() as u32,
+            style: CS_IME,
+            lpfnWndProc: Some(wnd_proc),
+            cbClsExtra: 0,
+            cbWndExtra: 0,
+            hInstance: hinst,
+            hCursor: unsafe { LoadCursorW(None, IDC_ARROW).unwrap_or_default() },
+            lpszMenuName: PCWSTR::null(),
+            lpszClassName: w!("ChewingCompositionWindow"),
+            ..Default::default()
+        };
+        unsafe { RegisterClassExW(&wc) };
+    }
+    pub(crate) fn new() -> Result<Rc<CompositionWindow>, UiError> {
+        expect_error("Failed to create composition window", || {
+            let mut composition = Rc::new_uninit();
+            let user_data = Rc::as_ptr(&composition);
+            Rc::get_mut(&mut composition)
+                .unwrap()
+                .write(CompositionWindow {
+                    model: RefCell::new(CompositionModel::default()),
+                    view: RefCell::new(RenderedView::new(user_data.cast())?),
+                    side: Cell::new(None),
+                });
+            // SAFETY: composition is unconditionally initialized
+            unsafe { Ok(composition.assume_init()) }
+        })
+    }
+    pub(crate) fn set_model(&self, model: CompositionModel) {
+        *self.model.borrow_mut() = model;
+    }
+    pub(crate) fn set_position(&self, x: i32, y: i32) {
+        let view = self.view.borrow();
+        let window = view.window();
+        window.set_position(x, y);
+    }
+    /// Places the window around `caret`, keeping it on the same side while
+    /// the preedit grows.
+    fn place(&self, caret: ScreenRect, width: i32, height: i32) -> (i32, i32) {
+        let (x, y, side) = place_popup(caret, width, height, &monitors(), self.side.get());
+        self.side.set(Some(side));
+        (x, y)
+    }
+    /// Screen area covered by the window, if shown.
+    pub(crate) fn visible_rect(&self) -> Option<RECT> {
+        let view = self.view.borrow();
+        view.window().visible_rect()
+    }
+    pub(crate) fn show(&self) {
+        let view = self.view.borrow();
+        let window = view.window();
+        window.show();
+        window.refresh();
+    }
+    pub(crate) fn hide(&self) {
+        let view = self.view.borrow();
+        let window = view.window();
+        window.hide();
+        self.side.set(None);
+    }
+}
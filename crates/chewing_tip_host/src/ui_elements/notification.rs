@@ -3,14 +3,15 @@
 
 use std::{cell::RefCell, fmt::Debug, rc::Rc, time::Duration};
 
+use chewing_tip_core::ipc::{messages::ShowNotification, values::NotificationKind};
 use error_plus::expect_error;
 use windows::Win32::{
     Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, POINT, WPARAM},
     Graphics::{
         Direct2D::{
             Common::{D2D_RECT_F, D2D1_COLOR_F},
-            D2D1_DRAW_TEXT_OPTIONS_NONE, D2D1_FACTORY_TYPE_SINGLE_THREADED, D2D1CreateFactory,
-            ID2D1DeviceContext, ID2D1Factory1,
+            D2D1_DRAW_TEXT_OPTIONS_ENABLE_COLOR_FONT, D2D1_FACTORY_TYPE_SINGLE_THREADED,
+            D2D1CreateFactory, ID2D1DeviceContext, ID2D1Factory1,
         },
         DirectComposition::IDCompositionTarget,
         DirectWrite::{
@@ -25,21 +26,29 @@ use windows::Win32::{
     },
     UI::WindowsAndMessaging::{
         CS_IME, GWLP_USERDATA, GetWindowLongPtrW, HWND_DESKTOP, IDC_ARROW, KillTimer, LoadCursorW,
-        RegisterClassExW, SetTimer, WINDOWPOS, WM_PAINT, WM_TIMER, WM_WINDOWPOSCHANGING,
-        WNDCLASSEXW, WS_CLIPCHILDREN, WS_EX_NOREDIRECTIONBITMAP, WS_EX_TOOLWINDOW, WS_EX_TOPMOST,
-        WS_POPUP,
+        MA_NOACTIVATE, RegisterClassExW, SetTimer, WINDOWPOS, WM_GETOBJECT, WM_LBUTTONUP,
+        WM_MOUSEACTIVATE, WM_PAINT, WM_TIMER, WM_WINDOWPOSCHANGING, WNDCLASSEXW, WS_CLIPCHILDREN,
+        WS_EX_NOREDIRECTIONBITMAP, WS_EX_TOOLWINDOW, WS_EX_TOPMOST, WS_POPUP,
     },
 };
 use windows_core::{HSTRING, PCWSTR, w};
 
-use crate::ui::{
-    UiError,
-    gfx::{
-        clamp_point_to_monitor, create_render_target, create_swapchain, create_swapchain_bitmap,
-        d3d11_device, get_dpi_for_point, get_dpi_for_window, setup_direct_composition,
+use crate::{
+    ui::{
+        UiError,
+        gfx::{
+            clamp_point_to_monitor, color_s, create_render_target, create_swapchain,
+            create_swapchain_bitmap, d3d11_device, get_dpi_for_point, get_dpi_for_window,
+            setup_direct_composition,
+        },
+        message_box::draw_message_box,
+        uia::NotificationAccessibility,
+        window::Window,
+    },
+    ui_elements::{
+        accessibility::{Announcement, announcement},
+        notification_queue::NotificationQueue,
     },
-    message_box::draw_message_box,
-    window::Window,
 };
 
 const ID_TIMEOUT: usize = 1;
@@ -48,6 +57,7 @@ const ID_TIMEOUT: usize = 1;
 pub(crate) struct Notification {
     model: RefCell<NotificationModel>,
     view: RefCell<RenderedView>,
+    queue: RefCell<NotificationQueue>,
 }
 
 #[derive(Debug, Default)]
@@ -58,6 +68,14 @@ pub(crate) struct NotificationModel {
     pub(crate) fg_color: D2D1_COLOR_F,
     pub(crate) bg_color: D2D1_COLOR_F,
     pub(crate) border_color: D2D1_COLOR_F,
+    /// Less padding for short labels such as the mode indicator
+    pub(crate) compact: bool,
+}
+
+impl NotificationModel {
+    fn margin(&self) -> f32 {
+        if self.compact { 4.0 } else { 10.0 }
+    }
 }
 
 extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
@@ -94,15 +112,27 @@ extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM
             LRESULT(0)
         }
         WM_TIMER => {
-            let this = get_this();
             if wparam.0 == ID_TIMEOUT {
-                let view = this.view.borrow();
-                let window = view.window();
-                let _ = unsafe { KillTimer(Some(window.hwnd()), ID_TIMEOUT) };
-                window.hide();
+                get_this().dismiss();
             }
             LRESULT(0)
         }
+        WM_MOUSEACTIVATE => {
+            // Never steal focus from the application
+            LRESULT(MA_NOACTIVATE as isize)
+        }
+        WM_LBUTTONUP => {
+            get_this().dismiss();
+            LRESULT(0)
+        }
+        WM_GETOBJECT => {
+            let this = get_this();
+            let view = this.view.borrow();
+            match view.accessibility.get_object(hwnd, wparam, lparam) {
+                Some(result) => result,
+                None => crate::ui::window::wnd_proc(hwnd, msg, wparam, lparam),
+            }
+        }
         _ => crate::ui::window::wnd_proc(hwnd, msg, wparam, lparam),
     }
 }
@@ -115,6 +145,16 @@ struct RenderedView {
     target: ID2D1DeviceContext,
     swapchain: IDXGISwapChain1,
     window: Window,
+    accessibility: NotificationAccessibility,
+}
+
+/// A queued notification ready to be shown.
+struct Presentation {
+    model: NotificationModel,
+    x: i32,
+    y: i32,
+    duration: Duration,
+    announcement: Announcement,
 }
 
 struct RenderedMetrics {
@@ -148,6 +188,7 @@ impl RenderedView {
                 target.SetDpi(dpi, dpi);
                 create_swapchain_bitmap(&swapchain, &target)?;
                 let dcomptarget = setup_direct_composition(&device, window.hwnd(), &swapchain)?;
+                let accessibility = NotificationAccessibility::new(window.hwnd());
                 Ok(RenderedView {
                     _factory: factory,
                     _dcomptarget: dcomptarget,
@@ -155,6 +196,7 @@ impl RenderedView {
                     target,
                     swapchain,
                     window,
+                    accessibility,
                 })
             }
         })
@@ -194,7 +236,7 @@ impl RenderedView {
             let mut metrics = DWRITE_TEXT_METRICS::default();
             unsafe { text_layout.GetMetrics(&mut metrics)? };
 
-            let margin = 10.0;
+            let margin = model.margin();
             let width = metrics.width + margin * 2.0;
             let height = metrics.height + margin * 2.0;
 
@@ -263,7 +305,7 @@ impl RenderedView {
                     model.border_color,
                 )?;
 
-                let margin = 10.0;
+                let margin = model.margin();
                 let text_rect = D2D_RECT_F {
                     left: margin,
                     top: margin,
@@ -276,7 +318,7 @@ impl RenderedView {
                     &text_format,
                     &text_rect,
                     &brush,
-                    D2D1_DRAW_TEXT_OPTIONS_NONE,
+                    D2D1_DRAW_TEXT_OPTIONS_ENABLE_COLOR_FONT,
                     DWRITE_MEASURING_MODE_NATURAL,
                 );
                 dc.EndDraw(None, None)?;
@@ -312,12 +354,68 @@ impl Notification {
             Rc::get_mut(&mut notification).unwrap().write(Notification {
                 model: RefCell::new(NotificationModel::default()),
                 view: RefCell::new(RenderedView::new(user_data.cast())?),
+                queue: RefCell::default(),
             });
             // SAFETY: notification is unconditionally initialized
             unsafe { Ok(notification.assume_init()) }
         })
     }
-    pub(crate) fn set_timer(&self, dur: Duration) {
+    /// Shows the notification now or after the ones before it are dismissed.
+    pub(crate) fn push(&self, notification: ShowNotification) {
+        let next = self
+            .queue
+            .borrow_mut()
+            .push(notification)
+            .map(Self::display);
+        if let Some(next) = next {
+            self.show(next);
+        }
+    }
+    /// Hides the current notification and shows the next one, if any.
+    fn dismiss(&self) {
+        let next = self.queue.borrow_mut().dismiss().map(Self::display);
+        match next {
+            Some(next) => self.show(next),
+            None => {
+                self.set_timer(Duration::ZERO);
+                self.view.borrow().window().hide();
+            }
+        }
+    }
+    fn display(notification: &ShowNotification) -> Presentation {
+        let text = match notification.icon() {
+            "" => notification.text.clone(),
+            icon => format!("{icon} {}", notification.text),
+        };
+        let model = NotificationModel {
+            text: HSTRING::from(text),
+            font_family: HSTRING::from(&notification.font_family),
+            font_size: notification.font_size,
+            fg_color: color_s(&notification.fg_color),
+            bg_color: color_s(&notification.bg_color),
+            border_color: color_s(&notification.border_color),
+            compact: notification.kind == NotificationKind::ModeIndicator,
+        };
+        Presentation {
+            model,
+            x: notification.position.x,
+            y: notification.position.y,
+            // Zero stops the timer so the notification stays until dismissed
+            duration: notification.duration().unwrap_or_default(),
+            announcement: announcement(notification.kind, &notification.text),
+        }
+    }
+    fn show(&self, display: Presentation) {
+        *self.model.borrow_mut() = display.model;
+        let view = self.view.borrow();
+        let window = view.window();
+        window.set_position(display.x, display.y);
+        window.show();
+        window.refresh();
+        view.accessibility.announce(display.announcement);
+        self.set_timer(display.duration);
+    }
+    fn set_timer(&self, dur: Duration) {
         let view = self.view.borrow();
         let window = view.window();
         if dur.is_zero() {
@@ -335,18 +433,4 @@ impl Notification {
             }
         }
     }
-    pub(crate) fn set_model(&self, model: NotificationModel) {
-        *self.model.borrow_mut() = model;
-    }
-    pub(crate) fn set_position(&self, x: i32, y: i32) {
-        let view = self.view.borrow();
-        let window = view.window();
-        window.set_position(x, y);
-    }
-    pub(crate) fn show(&self) {
-        let view = self.view.borrow();
-        let window = view.window();
-        window.show();
-        window.refresh();
-    }
 }
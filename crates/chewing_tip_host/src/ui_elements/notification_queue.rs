@@ -0,0 +1,186 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2026 Kan-Ru Chen
+
+use std::collections::VecDeque;
+
+use chewing_tip_core::ipc::{messages::ShowNotification, values::NotificationKind};
+
+/// Decides which notification is shown.
+///
+/// A new notification replaces a timed one right away. Notifications that
+/// stay until dismissed are queued instead, but a timed notification is
+/// still shown on top and the dismissable one comes back when it expires.
+#[derive(Debug, Default)]
+pub(crate) struct NotificationQueue {
+    current: Option<ShowNotification>,
+    pending: VecDeque<ShowNotification>,
+}
+
+impl NotificationQueue {
+    /// Adds a notification, returning it if it should be shown now.
+    pub(crate) fn push(&mut self, notification: ShowNotification) -> Option<&ShowNotification> {
+        self.pending.retain(|old| !supersedes(&notification, old));
+        if let Some(current) = self.current.take() {
+            if supersedes(&notification, &current) {
+                // Shown again with the new content
+            } else if notification.duration().is_some() {
+                if current.duration().is_none() {
+                    self.pending.push_front(current);
+                }
+            } else if current.duration().is_none() || !self.pending.is_empty() {
+                // Wait for the earlier dismissable notifications
+                self.current = Some(current);
+                self.pending.push_back(notification);
+                return None;
+            }
+        }
+        self.current = Some(notification);
+        self.current.as_ref()
+    }
+    /// Removes the shown notification, returning the next one to show.
+    pub(crate) fn dismiss(&mut self) -> Option<&ShowNotification> {
+        self.current = self.pending.pop_front();
+        self.current.as_ref()
+    }
+}
+
+/// Only the latest mode and one copy of the same message are kept.
+fn supersedes(new: &ShowNotification, old: &ShowNotification) -> bool {
+    let is_mode = |kind| {
+        matches!(
+            kind,
+            NotificationKind::ModeChange | NotificationKind::ModeIndicator
+        )
+    };
+    if is_mode(new.kind) && is_mode(old.kind) {
+        return true;
+    }
+    new.kind == old.kind && new.text == old.text
+}
+
+#[cfg(test)]
+mod tests {
+    use chewing_tip_core::ipc::{messages::ShowNotification, values::NotificationKind};
+
+    use super::NotificationQueue;
+
+    fn notification(kind: NotificationKind, text: &str) -> ShowNotification {
+        ShowNotification {
+            kind,
+            text: text.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn shown(queue: &mut NotificationQueue, kind: NotificationKind, text: &str) -> Option<String> {
+        queue
+            .push(notification(kind, text))
+            .map(|shown| shown.text.clone())
+    }
+
+    fn dismiss(queue: &mut NotificationQueue) -> Option<String> {
+        queue.dismiss().map(|shown| shown.text.clone())
+    }
+
+    #[test]
+    fn timed_notifications_replace_each_other() {
+        let mut queue = NotificationQueue::default();
+        assert_eq!(
+            Some("英數模式".into()),
+            shown(&mut queue, NotificationKind::ModeChange, "英數模式")
+        );
+        assert_eq!(
+            Some("刪除：測試".into()),
+            shown(&mut queue, NotificationKind::Info, "刪除：測試")
+        );
+        assert_eq!(None, dismiss(&mut queue));
+    }
+
+    #[test]
+    fn dismissable_notifications_are_queued() {
+        let mut queue = NotificationQueue::default();
+        assert_eq!(
+            Some("a".into()),
+            shown(&mut queue, NotificationKind::Error, "a")
+        );
+        assert_eq!(None, shown(&mut queue, NotificationKind::Error, "b"));
+        assert_eq!(
+            None,
+            shown(&mut queue, NotificationKind::UpdateAvailable, "c")
+        );
+        assert_eq!(Some("b".into()), dismiss(&mut queue));
+        assert_eq!(Some("c".into()), dismiss(&mut queue));
+        assert_eq!(None, dismiss(&mut queue));
+    }
+
+    #[test]
+    fn timed_notification_preempts_dismissable() {
+        let mut queue = NotificationQueue::default();
+        shown(&mut queue, NotificationKind::UpdateAvailable, "update");
+        assert_eq!(
+            Some("中文模式".into()),
+            shown(&mut queue, NotificationKind::ModeChange, "中文模式")
+        );
+        // The update comes back after the mode change expires
+        assert_eq!(Some("update".into()), dismiss(&mut queue));
+        assert_eq!(None, dismiss(&mut queue));
+    }
+
+    #[test]
+    fn duplicates_are_coalesced() {
+        let mut queue = NotificationQueue::default();
+        shown(&mut queue, NotificationKind::Error, "a");
+        shown(&mut queue, NotificationKind::Error, "b");
+        shown(&mut queue, NotificationKind::Error, "b");
+        // Showing the current message again restarts it
+        assert_eq!(
+            Some("a".into()),
+            shown(&mut queue, NotificationKind::Error, "a")
+        );
+        assert_eq!(Some("b".into()), dismiss(&mut queue));
+        assert_eq!(None, dismiss(&mut queue));
+    }
+
+    #[test]
+    fn latest_mode_change_wins() {
+        let mut queue = NotificationQueue::default();
+        shown(&mut queue, NotificationKind::Error, "error");
+        shown(&mut queue, NotificationKind::ModeChange, "英數模式");
+        shown(&mut queue, NotificationKind::Error, "error 2");
+        shown(&mut queue, NotificationKind::ModeChange, "中文模式");
+        assert_eq!(Some("error".into()), dismiss(&mut queue));
+        assert_eq!(Some("error 2".into()), dismiss(&mut queue));
+        assert_eq!(None, dismiss(&mut queue));
+    }
+
+    #[test]
+    fn mode_indicator_replaces_mode_change() {
+        let mut queue = NotificationQueue::default();
+        shown(&mut queue, NotificationKind::Error, "error");
+        shown(&mut queue, NotificationKind::ModeChange, "英數模式");
+        shown(&mut queue, NotificationKind::ModeIndicator, "中");
+        assert_eq!(Some("error".into()), dismiss(&mut queue));
+        assert_eq!(None, dismiss(&mut queue));
+        assert_eq!(
+            Some("英".into()),
+            shown(&mut queue, NotificationKind::ModeIndicator, "英")
+        );
+        assert_eq!(
+            Some("中文模式".into()),
+            shown(&mut queue, NotificationKind::ModeChange, "中文模式")
+        );
+        assert_eq!(None, dismiss(&mut queue));
+    }
+
+    #[test]
+    fn custom_duration() {
+        let mut queue = NotificationQueue::default();
+        // Info kept until dismissed
+        queue.push(ShowNotification {
+            duration_ms: Some(0),
+            ..notification(NotificationKind::Info, "sticky")
+        });
+        assert_eq!(None, shown(&mut queue, NotificationKind::Error, "later"));
+        assert_eq!(Some("later".into()), dismiss(&mut queue));
+    }
+}
@@ -1,10 +1,23 @@
 use error_plus::ErrorExt;
 
+use self::version::Version;
+
 mod config;
+mod feed;
+mod preferences;
 mod releases;
+pub(crate) mod scheduler;
 mod version;
 
-pub(crate) fn check_for_update() {
+/// Outcome of one update check, used by the scheduler to decide when to retry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CheckResult {
+    Checked,
+    Failed,
+    Disabled,
+}
+
+pub(crate) fn check_for_update() -> CheckResult {
     log::info!("Checking for update...");
     // Always clear update URL before a new check
     if let Err(error) = config::set_update_info_url("") {
@@ -14,23 +27,34 @@ pub(crate) fn check_for_update() {
         Ok(cfg) => cfg,
         Err(error) => {
             log::error!("{}", error.error_report());
-            return;
+            return CheckResult::Failed;
         }
     };
     if !cfg.enabled {
         log::info!("Check for update was disabled");
-        return;
+        return CheckResult::Disabled;
     }
     let dll_version = version::chewing_dll_version();
     log::info!("Current version = {dll_version}");
-    match releases::fetch_releases() {
+    let current = Version::parse(&dll_version);
+    match releases::fetch_releases(&cfg.feed) {
         Ok(releases) => 'check: {
             for rel in releases {
-                if rel.channel == cfg.channel && version::version_gt(&rel.version, &dll_version) {
+                if rel.channel == cfg.channel
+                    && Version::parse(&rel.version) > current
+                    && cfg.preferences.accepts(&rel.version)
+                {
                     log::info!("Updates available: version {}", rel.version);
+                    if cfg.preferences.is_snoozed(config::now_secs()) {
+                        log::info!("Update notification snoozed");
+                        break 'check;
+                    }
                     if let Err(error) = config::set_update_info_url(&rel.url) {
                         log::error!("{}", error.error_report());
                     }
+                    if let Err(error) = config::set_update_available_version(&rel.version) {
+                        log::error!("{}", error.error_report());
+                    }
                     break 'check;
                 }
             }
@@ -44,10 +68,39 @@ pub(crate) fn check_for_update() {
             if let Err(error) = config::set_update_info_url("") {
                 log::error!("{}", error.error_report());
             }
-            return;
+            return CheckResult::Failed;
         }
     }
     if let Err(error) = config::set_last_update_check_time() {
         log::error!("{}", error.error_report());
     }
+    CheckResult::Checked
+}
+
+/// Stops notifying about the currently offered release.
+pub(crate) fn skip_offered_update() {
+    match config::get_update_available_version() {
+        Ok(version) => {
+            log::info!("Skip update version {version}");
+            if let Err(error) = config::set_skip_version(&version) {
+                log::error!("{}", error.error_report());
+            }
+        }
+        Err(error) => log::error!("{}", error.error_report()),
+    }
+    if let Err(error) = config::set_update_info_url("") {
+        log::error!("{}", error.error_report());
+    }
+}
+
+/// Suppresses update notifications for the next `days` days.
+pub(crate) fn snooze_update(days: u32) {
+    log::info!("Snooze update notification for {days} day(s)");
+    let until = config::now_secs().saturating_add(u64::from(days) * 86400);
+    if let Err(error) = config::set_snooze_until(until) {
+        log::error!("{}", error.error_report());
+    }
+    if let Err(error) = config::set_update_info_url("") {
+        log::error!("{}", error.error_report());
+    }
 }
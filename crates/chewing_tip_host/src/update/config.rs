@@ -1,13 +1,22 @@
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use error_plus::{expect_error, impl_context_error};
+use chewing_tip_core::{config::Config, shell::user_dir};
+use error_plus::{ErrorExt, expect_error, impl_context_error};
 use windows_registry::CURRENT_USER;
 
-use super::version;
+use super::{
+    feed::{DEFAULT_FEED, FeedCache, FeedOptions, FeedSource},
+    preferences::UpdatePreferences,
+    version,
+};
+
+const FEED_CACHE_FILE: &str = "update_feed.xml";
 
 pub(crate) struct CheckUpdateConfig {
     pub(crate) enabled: bool,
     pub(crate) channel: String,
+    pub(crate) feed: FeedOptions,
+    pub(crate) preferences: UpdatePreferences,
 }
 
 pub(crate) fn get_check_update_config() -> Result<CheckUpdateConfig, UpdateError> {
@@ -21,8 +30,30 @@ pub(crate) fn get_check_update_config() -> Result<CheckUpdateConfig, UpdateError
                 dll_channel
             }
         };
-        let enabled = channel == "stable" || channel == "development";
-        Ok(CheckUpdateConfig { enabled, channel })
+        let mut enabled = channel == "stable" || channel == "development";
+        let cfg = Config::from_reg()
+            .map(|cfg| cfg.chewing_tsf)
+            .unwrap_or_default();
+        let feed = FeedOptions {
+            // A broken feed location disables the checks until it is fixed
+            source: cfg
+                .update_feed_url
+                .parse::<FeedSource>()
+                .unwrap_or_else(|error| {
+                    log::error!("{}", error.error_report());
+                    enabled = false;
+                    FeedSource::Http(DEFAULT_FEED.to_string())
+                }),
+            proxy: Some(cfg.update_proxy.trim().to_string()).filter(|p| !p.is_empty()),
+            timeout: Duration::from_secs(cfg.update_timeout.clamp(1, 600) as u64),
+        };
+        let preferences = UpdatePreferences::from_config(&cfg);
+        Ok(CheckUpdateConfig {
+            enabled,
+            channel,
+            feed,
+            preferences,
+        })
     })
 }
 
@@ -31,6 +62,7 @@ pub(crate) fn set_update_info_url(url: &str) -> Result<(), UpdateError> {
         let key = CURRENT_USER.create(r"Software\ChewingTextService")?;
         if url.is_empty() {
             key.remove_value("UpdateInfoUrl")?;
+            let _ = key.remove_value("UpdateAvailableVersion");
         } else {
             key.set_string("UpdateInfoUrl", &url)?;
         }
@@ -38,15 +70,89 @@ pub(crate) fn set_update_info_url(url: &str) -> Result<(), UpdateError> {
     })
 }
 
+pub(crate) fn set_update_available_version(version: &str) -> Result<(), UpdateError> {
+    expect_error("Failed to set available update version", || {
+        let key = CURRENT_USER.create(r"Software\ChewingTextService")?;
+        key.set_string("UpdateAvailableVersion", &version)?;
+        Ok(())
+    })
+}
+
+pub(crate) fn get_update_available_version() -> Result<String, UpdateError> {
+    expect_error("Failed to get available update version", || {
+        let key = CURRENT_USER.create(r"Software\ChewingTextService")?;
+        Ok(key.get_string("UpdateAvailableVersion")?)
+    })
+}
+
+pub(crate) fn set_skip_version(version: &str) -> Result<(), UpdateError> {
+    expect_error("Failed to set skipped update version", || {
+        let key = CURRENT_USER.create(r"Software\ChewingTextService")?;
+        key.set_string("UpdateSkipVersion", &version)?;
+        Ok(())
+    })
+}
+
+pub(crate) fn set_snooze_until(timestamp: u64) -> Result<(), UpdateError> {
+    expect_error("Failed to set update snooze time", || {
+        let key = CURRENT_USER.create(r"Software\ChewingTextService")?;
+        key.set_u64("UpdateSnoozeUntil", timestamp)?;
+        Ok(())
+    })
+}
+
 pub(crate) fn set_last_update_check_time() -> Result<(), UpdateError> {
     expect_error("Failed to set last update checking timestamp", || {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .as_ref()
-            .map(Duration::as_secs)
-            .unwrap_or_default();
         let key = CURRENT_USER.create(r"Software\ChewingTextService")?;
-        key.set_u64("LastUpdateCheckTime", now)?;
+        key.set_u64("LastUpdateCheckTime", now_secs())?;
+        Ok(())
+    })
+}
+
+pub(crate) fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .as_ref()
+        .map(Duration::as_secs)
+        .unwrap_or_default()
+}
+
+pub(crate) fn get_feed_cache() -> Result<FeedCache, UpdateError> {
+    expect_error("Failed to read cached update feed", || {
+        let key = CURRENT_USER.create(r"Software\ChewingTextService")?;
+        Ok(FeedCache {
+            url: key.get_string("UpdateFeedCacheUrl").ok(),
+            etag: key.get_string("UpdateFeedETag").ok(),
+            last_modified: key.get_string("UpdateFeedLastModified").ok(),
+            body: std::fs::read_to_string(user_dir()?.join(FEED_CACHE_FILE)).ok(),
+        })
+    })
+}
+
+pub(crate) fn set_feed_cache(cache: &FeedCache) -> Result<(), UpdateError> {
+    expect_error("Failed to cache update feed", || {
+        let key = CURRENT_USER.create(r"Software\ChewingTextService")?;
+        match &cache.url {
+            Some(url) => key.set_string("UpdateFeedCacheUrl", url)?,
+            None => {
+                let _ = key.remove_value("UpdateFeedCacheUrl");
+            }
+        }
+        match &cache.etag {
+            Some(etag) => key.set_string("UpdateFeedETag", etag)?,
+            None => {
+                let _ = key.remove_value("UpdateFeedETag");
+            }
+        }
+        match &cache.last_modified {
+            Some(last_modified) => key.set_string("UpdateFeedLastModified", last_modified)?,
+            None => {
+                let _ = key.remove_value("UpdateFeedLastModified");
+            }
+        }
+        if let Some(body) = &cache.body {
+            std::fs::write(user_dir()?.join(FEED_CACHE_FILE), body)?;
+        }
         Ok(())
     })
 }
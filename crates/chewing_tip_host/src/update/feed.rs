@@ -0,0 +1,368 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2026 Kan-Ru Chen
+
+use std::{path::PathBuf, str::FromStr, time::Duration};
+
+use error_plus::{expect_error, impl_context_error};
+use ureq::{Agent, Proxy};
+
+pub(crate) const DEFAULT_FEED: &str =
+    "https://chewing.im/releases/im.chewing.windows_chewing_tsf.releases.xml";
+
+/// Where the release metadata is loaded from.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum FeedSource {
+    /// A `https://` or `http://` URL
+    Http(String),
+    /// A local path or UNC share, given directly or as a `file://` URL
+    File(PathBuf),
+}
+
+impl FromStr for FeedSource {
+    type Err = FeedError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        expect_error("Failed to parse update feed location", || {
+            let s = s.trim();
+            if s.is_empty() {
+                return Ok(FeedSource::Http(DEFAULT_FEED.to_string()));
+            }
+            let lower = s.to_ascii_lowercase();
+            if lower.starts_with("https://") || lower.starts_with("http://") {
+                return Ok(FeedSource::Http(s.to_string()));
+            }
+            if lower.starts_with("file://") {
+                let path = percent_decode(&s["file://".len()..])?;
+                return match path.strip_prefix('/') {
+                    // file:///C:/path/releases.xml
+                    Some(local) if is_drive_path(local) => {
+                        Ok(FeedSource::File(PathBuf::from(local.replace('/', "\\"))))
+                    }
+                    Some(_) => Err("file URL must point to a drive or a share".into()),
+                    // file://server/share/releases.xml
+                    None => Ok(FeedSource::File(PathBuf::from(format!(
+                        r"\\{}",
+                        path.replace('/', "\\")
+                    )))),
+                };
+            }
+            if s.starts_with(r"\\") || is_drive_path(s) {
+                return Ok(FeedSource::File(PathBuf::from(s)));
+            }
+            Err(format!("unsupported update feed location \"{s}\"").into())
+        })
+    }
+}
+
+fn is_drive_path(s: &str) -> bool {
+    let bytes = s.as_bytes();
+    bytes.len() > 2
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && (bytes[2] == b'\\' || bytes[2] == b'/')
+}
+
+fn percent_decode(s: &str) -> Result<String, FeedError> {
+    expect_error("Failed to decode file URL", || {
+        let mut bytes = Vec::with_capacity(s.len());
+        let mut iter = s.bytes();
+        while let Some(b) = iter.next() {
+            if b == b'%' {
+                let hex = [
+                    iter.next().ok_or("truncated escape")?,
+                    iter.next().ok_or("truncated escape")?,
+                ];
+                bytes.push(u8::from_str_radix(std::str::from_utf8(&hex)?, 16)?);
+            } else {
+                bytes.push(b);
+            }
+        }
+        Ok(String::from_utf8(bytes)?)
+    })
+}
+
+#[derive(Debug)]
+pub(crate) struct FeedOptions {
+    pub(crate) source: FeedSource,
+    pub(crate) proxy: Option<String>,
+    pub(crate) timeout: Duration,
+}
+
+/// Validators and body of the last successful HTTP download.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct FeedCache {
+    /// The URL the body was downloaded from
+    pub(crate) url: Option<String>,
+    pub(crate) etag: Option<String>,
+    pub(crate) last_modified: Option<String>,
+    pub(crate) body: Option<String>,
+}
+
+impl FeedCache {
+    /// Returns the cache if it holds the body downloaded from `url`.
+    ///
+    /// Validators of another feed must not be sent, or a `304 Not Modified`
+    /// would make us use the body of the old feed.
+    pub(crate) fn for_url(&self, url: &str) -> Option<&FeedCache> {
+        if self.body.is_some() && self.url.as_deref() == Some(url) {
+            Some(self)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum FeedResponse {
+    Modified(FeedCache),
+    NotModified,
+}
+
+/// Loads the feed, sending conditional request headers from the cache
+/// if the source is a HTTP URL.
+pub(crate) fn fetch_feed(
+    options: &FeedOptions,
+    cache: &FeedCache,
+) -> Result<FeedResponse, FeedError> {
+    expect_error("Failed to fetch update feed", || match &options.source {
+        FeedSource::File(path) => Ok(FeedResponse::Modified(FeedCache {
+            url: None,
+            etag: None,
+            last_modified: None,
+            body: Some(std::fs::read_to_string(path)?),
+        })),
+        FeedSource::Http(url) => {
+            let mut config = Agent::config_builder()
+                .timeout_global(Some(options.timeout))
+                .http_status_as_error(false);
+            if let Some(proxy) = &options.proxy {
+                config = config.proxy(Some(Proxy::new(proxy)?));
+            }
+            let agent = Agent::new_with_config(config.build());
+            let mut request = agent.get(url);
+            // Only send validators when we still have the body they refer to
+            let cached = cache.for_url(url);
+            if let Some(cached) = cached {
+                if let Some(etag) = &cached.etag {
+                    request = request.header("If-None-Match", etag);
+                }
+                if let Some(last_modified) = &cached.last_modified {
+                    request = request.header("If-Modified-Since", last_modified);
+                }
+            }
+            let mut response = request.call()?;
+            let status = response.status();
+            if status == 304 {
+                if cached.is_none() {
+                    return Err("server returned HTTP status 304 without a cached feed".into());
+                }
+                return Ok(FeedResponse::NotModified);
+            }
+            if !status.is_success() {
+                return Err(format!("server returned HTTP status {status}").into());
+            }
+            let header = |name: &str| {
+                response
+                    .headers()
+                    .get(name)
+                    .and_then(|v| v.to_str().ok())
+                    .map(str::to_string)
+            };
+            let etag = header("ETag");
+            let last_modified = header("Last-Modified");
+            let body = response.body_mut().read_to_string()?;
+            Ok(FeedResponse::Modified(FeedCache {
+                url: Some(url.clone()),
+                etag,
+                last_modified,
+                body: Some(body),
+            }))
+        }
+    })
+}
+
+impl_context_error!(FeedError);
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        path::PathBuf,
+        thread::{self, JoinHandle},
+        time::Duration,
+    };
+
+    use super::{DEFAULT_FEED, FeedCache, FeedOptions, FeedResponse, FeedSource, fetch_feed};
+
+    const LAST_MODIFIED: &str = "Wed, 21 Oct 2026 07:28:00 GMT";
+    const OK: &str = "HTTP/1.1 200 OK\r\n\
+        ETag: \"abc\"\r\n\
+        Last-Modified: Wed, 21 Oct 2026 07:28:00 GMT\r\n\
+        Content-Length: 11\r\n\
+        Connection: close\r\n\
+        \r\n\
+        <releases/>";
+    const NOT_MODIFIED: &str = "HTTP/1.1 304 Not Modified\r\n\
+        Connection: close\r\n\
+        \r\n";
+
+    /// Answers one connection with each of `responses` and returns the
+    /// headers of the requests received.
+    fn serve(responses: Vec<&'static str>) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/releases.xml", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            responses
+                .into_iter()
+                .map(|response| {
+                    let (mut stream, _) = listener.accept().unwrap();
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    let mut request = String::new();
+                    loop {
+                        let mut line = String::new();
+                        reader.read_line(&mut line).unwrap();
+                        if line.trim_end().is_empty() {
+                            break;
+                        }
+                        request.push_str(&line.to_ascii_lowercase());
+                    }
+                    stream.write_all(response.as_bytes()).unwrap();
+                    request
+                })
+                .collect()
+        });
+        (url, server)
+    }
+
+    #[test]
+    fn parse_empty_is_default_feed() {
+        assert_eq!(
+            FeedSource::Http(DEFAULT_FEED.to_string()),
+            "".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_http_urls() {
+        assert_eq!(
+            FeedSource::Http("https://mirror.example/releases.xml".to_string()),
+            "https://mirror.example/releases.xml".parse().unwrap()
+        );
+        assert_eq!(
+            FeedSource::Http("http://127.0.0.1:8080/releases.xml".to_string()),
+            "http://127.0.0.1:8080/releases.xml".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_file_urls() {
+        assert_eq!(
+            FeedSource::File(PathBuf::from(r"C:\Program Files\feed\releases.xml")),
+            "file:///C:/Program%20Files/feed/releases.xml"
+                .parse()
+                .unwrap()
+        );
+        assert_eq!(
+            FeedSource::File(PathBuf::from(r"\\fileserver\share\releases.xml")),
+            "file://fileserver/share/releases.xml".parse().unwrap()
+        );
+        assert!("file:///releases.xml".parse::<FeedSource>().is_err());
+    }
+
+    #[test]
+    fn parse_paths() {
+        assert_eq!(
+            FeedSource::File(PathBuf::from(r"\\fileserver\share\releases.xml")),
+            r"\\fileserver\share\releases.xml".parse().unwrap()
+        );
+        assert_eq!(
+            FeedSource::File(PathBuf::from(r"D:\mirror\releases.xml")),
+            r"D:\mirror\releases.xml".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_unsupported() {
+        assert!(
+            "ftp://mirror.example/releases.xml"
+                .parse::<FeedSource>()
+                .is_err()
+        );
+        assert!("releases.xml".parse::<FeedSource>().is_err());
+    }
+
+    #[test]
+    fn cache_is_tied_to_url() {
+        let cache = FeedCache {
+            url: Some("https://chewing.im/releases.xml".to_string()),
+            etag: Some("\"abc\"".to_string()),
+            last_modified: None,
+            body: Some("<releases/>".to_string()),
+        };
+        assert_eq!(
+            Some(&cache),
+            cache.for_url("https://chewing.im/releases.xml")
+        );
+        assert_eq!(None, cache.for_url("https://mirror.example/releases.xml"));
+        let without_url = FeedCache {
+            url: None,
+            ..cache.clone()
+        };
+        assert_eq!(None, without_url.for_url("https://chewing.im/releases.xml"));
+        let without_body = FeedCache {
+            body: None,
+            ..cache
+        };
+        assert_eq!(
+            None,
+            without_body.for_url("https://chewing.im/releases.xml")
+        );
+    }
+
+    #[test]
+    fn conditional_get_reuses_cache() {
+        let (url, server) = serve(vec![OK, NOT_MODIFIED, OK]);
+        let options = FeedOptions {
+            source: FeedSource::Http(url.clone()),
+            proxy: None,
+            timeout: Duration::from_secs(10),
+        };
+        let cache = FeedCache {
+            url: Some(url.clone()),
+            etag: Some("\"abc\"".to_string()),
+            last_modified: Some(LAST_MODIFIED.to_string()),
+            body: Some("<releases/>".to_string()),
+        };
+        assert_eq!(
+            FeedResponse::Modified(cache.clone()),
+            fetch_feed(&options, &FeedCache::default()).unwrap()
+        );
+        assert_eq!(
+            FeedResponse::NotModified,
+            fetch_feed(&options, &cache).unwrap()
+        );
+        // The cache of another feed is not revalidated
+        let other = FeedCache {
+            url: Some("https://mirror.example/releases.xml".to_string()),
+            ..cache.clone()
+        };
+        assert_eq!(
+            FeedResponse::Modified(cache),
+            fetch_feed(&options, &other).unwrap()
+        );
+
+        let requests = server.join().unwrap();
+        let if_none_match = "if-none-match: \"abc\"\r\n";
+        let if_modified_since = format!(
+            "if-modified-since: {}\r\n",
+            LAST_MODIFIED.to_ascii_lowercase()
+        );
+        assert!(!requests[0].contains("if-none-match"));
+        assert!(!requests[0].contains("if-modified-since"));
+        assert!(requests[1].contains(if_none_match));
+        assert!(requests[1].contains(&if_modified_since));
+        assert!(!requests[2].contains("if-none-match"));
+        assert!(!requests[2].contains("if-modified-since"));
+    }
+}
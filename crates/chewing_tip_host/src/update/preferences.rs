@@ -0,0 +1,90 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2026 Kan-Ru Chen
+
+use chewing_tip_core::config::ChewingTsfConfig;
+
+use super::version::Version;
+
+/// User choices that suppress update notifications.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct UpdatePreferences {
+    /// Never notify about this exact version
+    pub(crate) skip_version: Option<String>,
+    /// Seconds since UNIX epoch until which notifications are suppressed
+    pub(crate) snooze_until: u64,
+    /// Only consider releases with this major version
+    pub(crate) pin_major: Option<u64>,
+}
+
+impl UpdatePreferences {
+    pub(crate) fn from_config(cfg: &ChewingTsfConfig) -> UpdatePreferences {
+        UpdatePreferences {
+            skip_version: Some(cfg.update_skip_version.trim().to_string())
+                .filter(|v| !v.is_empty()),
+            snooze_until: cfg.update_snooze_until,
+            pin_major: u64::try_from(cfg.update_pin_major).ok().filter(|&v| v > 0),
+        }
+    }
+    pub(crate) fn is_snoozed(&self, now: u64) -> bool {
+        now < self.snooze_until
+    }
+    /// Returns true if the user did not opt out of `ver`.
+    pub(crate) fn accepts(&self, ver: &str) -> bool {
+        if self
+            .skip_version
+            .as_deref()
+            .is_some_and(|skip| Version::parse(skip) == Version::parse(ver))
+        {
+            return false;
+        }
+        if let Some(pin) = self.pin_major
+            && Version::parse(ver).major() != Some(pin)
+        {
+            return false;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UpdatePreferences;
+
+    #[test]
+    fn default_accepts_everything() {
+        let prefs = UpdatePreferences::default();
+        assert!(prefs.accepts("26.5.2.0"));
+        assert!(!prefs.is_snoozed(0));
+    }
+
+    #[test]
+    fn skip_exact_version() {
+        let prefs = UpdatePreferences {
+            skip_version: Some("26.5.2.0".to_string()),
+            ..Default::default()
+        };
+        assert!(!prefs.accepts("26.5.2.0"));
+        assert!(!prefs.accepts("26.5.2"));
+        assert!(prefs.accepts("26.5.3.0"));
+    }
+
+    #[test]
+    fn pin_major_version() {
+        let prefs = UpdatePreferences {
+            pin_major: Some(25),
+            ..Default::default()
+        };
+        assert!(prefs.accepts("25.12.1.0"));
+        assert!(!prefs.accepts("26.1.0.0"));
+    }
+
+    #[test]
+    fn snooze_until() {
+        let prefs = UpdatePreferences {
+            snooze_until: 1000,
+            ..Default::default()
+        };
+        assert!(prefs.is_snoozed(999));
+        assert!(!prefs.is_snoozed(1000));
+    }
+}
@@ -1,6 +1,11 @@
-use error_plus::{expect_error, impl_context_error};
+use error_plus::{ErrorExt, expect_error, impl_context_error};
 use roxmltree::Document;
 
+use super::{
+    config,
+    feed::{FeedCache, FeedOptions, FeedResponse, fetch_feed},
+};
+
 #[derive(Debug)]
 pub(crate) struct Release {
     pub(crate) version: String,
@@ -8,12 +13,32 @@ pub(crate) struct Release {
     pub(crate) url: String,
 }
 
-const RELEASES: &str = "https://chewing.im/releases/im.chewing.windows_chewing_tsf.releases.xml";
-
-pub(crate) fn fetch_releases() -> Result<Vec<Release>, FetchReleasesError> {
+pub(crate) fn fetch_releases(options: &FeedOptions) -> Result<Vec<Release>, FetchReleasesError> {
     expect_error("Failed to download release metadata", || {
-        let releases_xml = ureq::get(RELEASES).call()?.body_mut().read_to_string()?;
-        let doc = Document::parse(&releases_xml)?;
+        // Without a usable cache we simply download the whole feed again
+        let cache = config::get_feed_cache().unwrap_or_else(|error| {
+            log::warn!("{}", error.error_report());
+            FeedCache::default()
+        });
+        let releases_xml = match fetch_feed(options, &cache)? {
+            FeedResponse::NotModified => {
+                log::info!("Update feed was not modified, using cached copy");
+                cache.body.ok_or("cached update feed is missing")?
+            }
+            FeedResponse::Modified(fresh) => {
+                if let Err(error) = config::set_feed_cache(&fresh) {
+                    log::warn!("Failed to cache update feed: {error}");
+                }
+                fresh.body.ok_or("update feed is empty")?
+            }
+        };
+        Ok(parse_releases(&releases_xml)?)
+    })
+}
+
+pub(crate) fn parse_releases(releases_xml: &str) -> Result<Vec<Release>, FetchReleasesError> {
+    expect_error("Failed to parse release metadata", || {
+        let doc = Document::parse(releases_xml)?;
         let mut ret = vec![];
         for rel in doc.root_element().children() {
             if rel.has_tag_name("release")
@@ -39,3 +64,43 @@ pub(crate) fn fetch_releases() -> Result<Vec<Release>, FetchReleasesError> {
 }
 
 impl_context_error!(FetchReleasesError);
+
+#[cfg(test)]
+mod tests {
+    use super::parse_releases;
+
+    const FIXTURE: &str = r#"
+<releases>
+  <release version="25.8.1.468" date="2025-08-17" type="development" urgency="low">
+    <url>
+      https://github.com/chewing/windows-chewing-tsf/releases/tag/nightly-25.8.1.468
+    </url>
+  </release>
+  <release version="25.8.1.0" date="2025-07-31" type="stable" urgency="medium">
+    <url>https://github.com/chewing/windows-chewing-tsf/releases/tag/v25.8.1.0</url>
+  </release>
+  <release version="25.8.0.0" date="2025-07-01">
+    <url>https://example.com/missing-type</url>
+  </release>
+</releases>
+"#;
+
+    #[test]
+    fn parse_fixture() {
+        let releases = parse_releases(FIXTURE).unwrap();
+        assert_eq!(2, releases.len());
+        assert_eq!("25.8.1.468", releases[0].version);
+        assert_eq!("development", releases[0].channel);
+        assert_eq!(
+            "https://github.com/chewing/windows-chewing-tsf/releases/tag/nightly-25.8.1.468",
+            releases[0].url
+        );
+        assert_eq!("25.8.1.0", releases[1].version);
+        assert_eq!("stable", releases[1].channel);
+    }
+
+    #[test]
+    fn parse_invalid_xml() {
+        assert!(parse_releases("<releases>").is_err());
+    }
+}
@@ -0,0 +1,350 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2026 Kan-Ru Chen
+
+use std::{
+    hash::{BuildHasher, RandomState},
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use chewing_tip_core::config::Config;
+use error_plus::ErrorExt;
+use windows::Networking::Connectivity::{NetworkCostType, NetworkInformation};
+
+use super::{CheckResult, check_for_update};
+
+/// How often the scheduler wakes up to re-read the config when nothing is due.
+const POLL_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+pub(crate) trait Clock {
+    fn now(&self) -> SystemTime;
+}
+
+pub(crate) struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+pub(crate) trait NetworkStatus {
+    fn is_metered(&self) -> bool;
+}
+
+pub(crate) struct SystemNetworkStatus;
+
+impl NetworkStatus for SystemNetworkStatus {
+    fn is_metered(&self) -> bool {
+        let cost = NetworkInformation::GetInternetConnectionProfile()
+            .and_then(|profile| profile.GetConnectionCost());
+        match cost {
+            Ok(cost) => {
+                matches!(
+                    cost.NetworkCostType(),
+                    Ok(NetworkCostType::Fixed | NetworkCostType::Variable)
+                ) || cost.Roaming().unwrap_or_default()
+                    || cost.OverDataLimit().unwrap_or_default()
+            }
+            // No connection profile, let the check fail normally
+            Err(_) => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Schedule {
+    pub(crate) interval: Duration,
+    pub(crate) jitter: Duration,
+    pub(crate) retry_base: Duration,
+    pub(crate) retry_max: Duration,
+    pub(crate) skip_metered: bool,
+}
+
+impl Schedule {
+    pub(crate) fn from_config(cfg: &Config) -> Schedule {
+        let cfg = &cfg.chewing_tsf;
+        Schedule {
+            interval: Duration::from_secs(cfg.update_check_interval.max(1) as u64 * 3600),
+            jitter: Duration::from_secs(cfg.update_check_jitter.max(0) as u64 * 60),
+            retry_base: Duration::from_secs(5 * 60),
+            retry_max: Duration::from_secs(cfg.update_check_interval.max(1) as u64 * 3600),
+            skip_metered: cfg.skip_update_on_metered_network,
+        }
+    }
+    /// Computes when the next check is due.
+    ///
+    /// `jitter_seed` spreads the checks of many installations over the jitter
+    /// window so they don't all hit the release server at the same time.
+    pub(crate) fn next_check(&self, state: &SchedulerState, jitter_seed: u64) -> SystemTime {
+        // Only look at the config again later while checks are disabled
+        if state.disabled
+            && let Some(last_attempt) = state.last_attempt
+        {
+            return last_attempt + POLL_INTERVAL;
+        }
+        if state.failures > 0
+            && let Some(last_attempt) = state.last_attempt
+        {
+            return last_attempt + self.backoff(state.failures);
+        }
+        let jitter_secs = self.jitter.as_secs();
+        let jitter = if jitter_secs == 0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs(jitter_seed % (jitter_secs + 1))
+        };
+        UNIX_EPOCH + Duration::from_secs(state.last_check_time) + self.interval + jitter
+    }
+    /// Exponential backoff after `failures` consecutive failed checks.
+    pub(crate) fn backoff(&self, failures: u32) -> Duration {
+        let exp = failures.saturating_sub(1).min(16);
+        self.retry_base
+            .saturating_mul(1 << exp)
+            .min(self.retry_max.max(self.retry_base))
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct SchedulerState {
+    /// Seconds since UNIX epoch of the last successful check
+    pub(crate) last_check_time: u64,
+    pub(crate) last_attempt: Option<SystemTime>,
+    pub(crate) failures: u32,
+    /// Whether the last attempt found update checks disabled
+    pub(crate) disabled: bool,
+}
+
+impl SchedulerState {
+    pub(crate) fn record(&mut self, now: SystemTime, result: CheckResult) {
+        self.last_attempt = Some(now);
+        self.disabled = result == CheckResult::Disabled;
+        match result {
+            CheckResult::Checked => {
+                self.failures = 0;
+                self.last_check_time = now
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or_default();
+            }
+            CheckResult::Failed => self.failures = self.failures.saturating_add(1),
+            CheckResult::Disabled => self.failures = 0,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Decision {
+    Check,
+    Wait(Duration),
+}
+
+pub(crate) fn decide(
+    schedule: &Schedule,
+    state: &SchedulerState,
+    now: SystemTime,
+    jitter_seed: u64,
+    network: &impl NetworkStatus,
+) -> Decision {
+    let due = schedule.next_check(state, jitter_seed);
+    match due.duration_since(now) {
+        Ok(wait) if !wait.is_zero() => Decision::Wait(wait.min(POLL_INTERVAL)),
+        _ if schedule.skip_metered && network.is_metered() => {
+            log::info!("Skip update check on metered network");
+            Decision::Wait(POLL_INTERVAL)
+        }
+        _ => Decision::Check,
+    }
+}
+
+/// Runs periodic update checks forever. Should be spawned on its own thread.
+pub(crate) fn run_update_scheduler(clock: impl Clock, network: impl NetworkStatus) {
+    let jitter_seed = RandomState::new().hash_one(std::process::id());
+    let mut state = SchedulerState::default();
+    loop {
+        let cfg = Config::from_reg().unwrap_or_else(|error| {
+            log::error!("{}", error.error_report());
+            Config::default()
+        });
+        // The last check time might also be updated by a manual check
+        state.last_check_time = state
+            .last_check_time
+            .max(cfg.chewing_tsf.last_update_check_time);
+        let schedule = Schedule::from_config(&cfg);
+        match decide(&schedule, &state, clock.now(), jitter_seed, &network) {
+            Decision::Check => {
+                let result = check_for_update();
+                state.record(clock.now(), result);
+                if state.failures > 0 {
+                    log::info!(
+                        "Update check failed {} time(s), retry in {:?}",
+                        state.failures,
+                        schedule.backoff(state.failures)
+                    );
+                }
+            }
+            Decision::Wait(duration) => thread::sleep(duration),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use super::{Decision, NetworkStatus, Schedule, SchedulerState, decide};
+    use crate::update::CheckResult;
+
+    struct Metered(bool);
+
+    impl NetworkStatus for Metered {
+        fn is_metered(&self) -> bool {
+            self.0
+        }
+    }
+
+    const HOUR: u64 = 3600;
+
+    fn schedule() -> Schedule {
+        Schedule {
+            interval: Duration::from_secs(24 * HOUR),
+            jitter: Duration::from_secs(HOUR),
+            retry_base: Duration::from_secs(300),
+            retry_max: Duration::from_secs(24 * HOUR),
+            skip_metered: true,
+        }
+    }
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn next_check_after_interval() {
+        let state = SchedulerState {
+            last_check_time: 1000,
+            ..Default::default()
+        };
+        assert_eq!(at(1000 + 24 * HOUR), schedule().next_check(&state, 0));
+        assert_eq!(at(1000 + 24 * HOUR + 42), schedule().next_check(&state, 42));
+        // jitter is bounded by the jitter window
+        assert_eq!(
+            at(1000 + 24 * HOUR + HOUR),
+            schedule().next_check(&state, HOUR)
+        );
+        assert_eq!(
+            at(1000 + 24 * HOUR),
+            schedule().next_check(&state, HOUR + 1)
+        );
+    }
+
+    #[test]
+    fn never_checked_is_due() {
+        let state = SchedulerState::default();
+        assert_eq!(
+            Decision::Check,
+            decide(&schedule(), &state, at(30 * 24 * HOUR), 0, &Metered(false))
+        );
+    }
+
+    #[test]
+    fn wait_is_capped_by_poll_interval() {
+        let state = SchedulerState {
+            last_check_time: 1000,
+            ..Default::default()
+        };
+        assert_eq!(
+            Decision::Wait(Duration::from_secs(600)),
+            decide(&schedule(), &state, at(1000), 0, &Metered(false))
+        );
+        assert_eq!(
+            Decision::Wait(Duration::from_secs(100)),
+            decide(
+                &schedule(),
+                &state,
+                at(1000 + 24 * HOUR - 100),
+                0,
+                &Metered(false)
+            )
+        );
+    }
+
+    #[test]
+    fn exponential_backoff() {
+        let schedule = schedule();
+        assert_eq!(Duration::from_secs(300), schedule.backoff(1));
+        assert_eq!(Duration::from_secs(600), schedule.backoff(2));
+        assert_eq!(Duration::from_secs(1200), schedule.backoff(3));
+        assert_eq!(Duration::from_secs(24 * HOUR), schedule.backoff(20));
+        assert_eq!(Duration::from_secs(24 * HOUR), schedule.backoff(u32::MAX));
+    }
+
+    #[test]
+    fn failures_use_backoff_and_success_resets() {
+        let schedule = schedule();
+        let mut state = SchedulerState {
+            last_check_time: 1000,
+            ..Default::default()
+        };
+        state.record(at(100 * HOUR), CheckResult::Failed);
+        state.record(at(100 * HOUR + 300), CheckResult::Failed);
+        assert_eq!(2, state.failures);
+        assert_eq!(at(100 * HOUR + 900), schedule.next_check(&state, 0));
+
+        state.record(at(101 * HOUR), CheckResult::Checked);
+        assert_eq!(0, state.failures);
+        assert_eq!(101 * HOUR, state.last_check_time);
+        assert_eq!(at(125 * HOUR), schedule.next_check(&state, 0));
+    }
+
+    #[test]
+    fn disabled_waits_for_poll_interval() {
+        let mut state = SchedulerState::default();
+        state.record(at(30 * 24 * HOUR), CheckResult::Disabled);
+        assert_eq!(
+            Decision::Wait(Duration::from_secs(600)),
+            decide(&schedule(), &state, at(30 * 24 * HOUR), 0, &Metered(false))
+        );
+        assert_eq!(
+            Decision::Wait(Duration::from_secs(300)),
+            decide(
+                &schedule(),
+                &state,
+                at(30 * 24 * HOUR + 300),
+                0,
+                &Metered(false)
+            )
+        );
+        assert_eq!(
+            Decision::Check,
+            decide(
+                &schedule(),
+                &state,
+                at(30 * 24 * HOUR + 600),
+                0,
+                &Metered(false)
+            )
+        );
+        // Enabled again, back to the normal schedule
+        state.record(at(30 * 24 * HOUR + 600), CheckResult::Checked);
+        assert!(!state.disabled);
+        assert_eq!(at(31 * 24 * HOUR + 600), schedule().next_check(&state, 0));
+    }
+
+    #[test]
+    fn skip_on_metered_network() {
+        let state = SchedulerState::default();
+        assert_eq!(
+            Decision::Wait(Duration::from_secs(600)),
+            decide(&schedule(), &state, at(30 * 24 * HOUR), 0, &Metered(true))
+        );
+        let schedule = Schedule {
+            skip_metered: false,
+            ..schedule()
+        };
+        assert_eq!(
+            Decision::Check,
+            decide(&schedule, &state, at(30 * 24 * HOUR), 0, &Metered(true))
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use std::cmp::Ordering;
 use std::ptr::null_mut;
 
 use chewing_tip_core::shell::program_dir;
@@ -44,65 +45,116 @@ pub(crate) fn chewing_dll_version() -> String {
 }
 
 pub(crate) fn chewing_dll_channel() -> String {
-    let (_, _, _, build) = parse_version(&chewing_dll_version());
-    if build == 0 {
-        "stable".to_string()
-    } else {
-        "development".to_string()
+    release_channel(&Version::parse(&chewing_dll_version())).to_string()
+}
+
+/// Release builds have a zero build number. An unparsable version is treated
+/// as a release build, as it has always been.
+fn release_channel(version: &Version) -> &'static str {
+    match version.build() {
+        Some(0) | None => "stable",
+        Some(_) => "development",
     }
 }
 
-fn parse_version(ver: &str) -> (u64, u64, u64, u64) {
-    let mut parts = ver.split('.');
-    (
-        parts
-            .next()
-            .map(|s| s.parse().unwrap_or_default())
-            .unwrap_or_default(),
-        parts
-            .next()
-            .map(|s| s.parse().unwrap_or_default())
-            .unwrap_or_default(),
-        parts
-            .next()
-            .map(|s| s.parse().unwrap_or_default())
-            .unwrap_or_default(),
-        parts
-            .next()
-            .map(|s| s.parse().unwrap_or_default())
-            .unwrap_or_default(),
-    )
+/// A release version such as `25.10.0.477` or `26.5.2-rc1`.
+///
+/// Versions have up to four numeric parts, missing parts are zero. An optional
+/// pre-release tag sorts before the same version without a tag, following the
+/// semver precedence rules. Anything unparsable is `Invalid` and sorts before
+/// every valid version so it is never considered an update.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Version {
+    Invalid,
+    Valid {
+        numbers: [u64; 4],
+        pre: Vec<PreRelease>,
+    },
 }
 
-pub(crate) fn version_gt(ver_a: &str, ver_b: &str) -> bool {
-    let (o_major, o_minor, o_patch, o_build) = parse_version(ver_b);
-    let (n_major, n_minor, n_patch, n_build) = parse_version(ver_a);
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum PreRelease {
+    Numeric(u64),
+    Alpha(String),
+}
 
-    if n_major > o_major {
-        return true;
+impl Version {
+    pub(crate) fn parse(ver: &str) -> Version {
+        Version::try_parse(ver).unwrap_or(Version::Invalid)
     }
-    if n_major < o_major {
-        return false;
-    }
-    if n_minor > o_minor {
-        return true;
-    }
-    if n_minor < o_minor {
-        return false;
+    fn try_parse(ver: &str) -> Option<Version> {
+        let ver = ver.trim();
+        let ver = ver.strip_prefix(['v', 'V']).unwrap_or(ver);
+        // Build metadata does not affect precedence
+        let ver = ver.split_once('+').map_or(ver, |(ver, _)| ver);
+        let (core, pre) = match ver.split_once('-') {
+            Some((core, pre)) => (core, Some(pre)),
+            None => (ver, None),
+        };
+        let mut numbers = [0; 4];
+        for (i, part) in core.split('.').enumerate() {
+            if i == numbers.len() || part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            numbers[i] = part.parse().ok()?;
+        }
+        let mut ids = vec![];
+        if let Some(pre) = pre {
+            for id in pre.split('.') {
+                if id.is_empty() || !id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-') {
+                    return None;
+                }
+                if id.bytes().all(|b| b.is_ascii_digit()) {
+                    ids.push(PreRelease::Numeric(id.parse().ok()?));
+                } else {
+                    ids.push(PreRelease::Alpha(id.to_string()));
+                }
+            }
+        }
+        Some(Version::Valid { numbers, pre: ids })
     }
-    if n_patch > o_patch {
-        return true;
+    pub(crate) fn major(&self) -> Option<u64> {
+        match self {
+            Version::Invalid => None,
+            Version::Valid { numbers, .. } => Some(numbers[0]),
+        }
     }
-    if n_patch < o_patch {
-        return false;
+    pub(crate) fn build(&self) -> Option<u64> {
+        match self {
+            Version::Invalid => None,
+            Version::Valid { numbers, .. } => Some(numbers[3]),
+        }
     }
-    if n_build > o_build {
-        return true;
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Version::Invalid, Version::Invalid) => Ordering::Equal,
+            (Version::Invalid, Version::Valid { .. }) => Ordering::Less,
+            (Version::Valid { .. }, Version::Invalid) => Ordering::Greater,
+            (
+                Version::Valid { numbers, pre },
+                Version::Valid {
+                    numbers: other_numbers,
+                    pre: other_pre,
+                },
+            ) => numbers.cmp(other_numbers).then_with(|| {
+                match (pre.is_empty(), other_pre.is_empty()) {
+                    (true, true) => Ordering::Equal,
+                    (true, false) => Ordering::Greater,
+                    (false, true) => Ordering::Less,
+                    (false, false) => pre.cmp(other_pre),
+                }
+            }),
+        }
     }
-    if n_build < o_build {
-        return false;
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
-    false
 }
 
 pub const fn hi_word(v: u32) -> u16 {
@@ -115,44 +167,181 @@ pub const fn lo_word(v: u32) -> u16 {
 
 #[cfg(test)]
 mod tests {
-    use super::{parse_version, version_gt};
+    use proptest::prelude::*;
+
+    use super::{PreRelease, Version, release_channel};
+
+    fn gt(a: &str, b: &str) -> bool {
+        Version::parse(a) > Version::parse(b)
+    }
 
     #[test]
     fn parse_version_test() {
         let ver = "25.10.0.477";
-        let ver_tuple = parse_version(ver);
-        assert_eq!((25, 10, 0, 477), ver_tuple);
+        assert_eq!(
+            Version::Valid {
+                numbers: [25, 10, 0, 477],
+                pre: vec![]
+            },
+            Version::parse(ver)
+        );
+        assert_eq!(Version::parse("25.10"), Version::parse("25.10.0.0"));
+        assert_eq!(Version::parse("v25.10.0"), Version::parse("25.10.0.0"));
+    }
+
+    #[test]
+    fn parse_pre_release_test() {
+        assert_eq!(
+            Version::Valid {
+                numbers: [26, 5, 2, 0],
+                pre: vec![PreRelease::Alpha("rc1".to_string())]
+            },
+            Version::parse("26.5.2-rc1")
+        );
+        assert_eq!(
+            Version::Valid {
+                numbers: [26, 5, 2, 0],
+                pre: vec![
+                    PreRelease::Alpha("beta".to_string()),
+                    PreRelease::Numeric(2)
+                ]
+            },
+            Version::parse("26.5.2-beta.2+build.5")
+        );
+    }
+
+    #[test]
+    fn parse_invalid_test() {
+        for ver in [
+            "",
+            "stable",
+            "25..1",
+            "25.10.0.477.1",
+            "25.x",
+            "25.10-",
+            "25.10-rc..1",
+            "99999999999999999999999",
+        ] {
+            assert_eq!(Version::Invalid, Version::parse(ver), "{ver}");
+        }
     }
 
     #[test]
     fn compare_build_test() {
         let v1 = "25.10.0.476";
         let v2 = "25.10.0.477";
-        assert!(!version_gt(v1, v2));
-        assert!(version_gt(v2, v1));
+        assert!(!gt(v1, v2));
+        assert!(gt(v2, v1));
     }
 
     #[test]
     fn compare_patch_test() {
         let v1 = "25.10.0.476";
         let v2 = "25.10.1.477";
-        assert!(!version_gt(v1, v2));
-        assert!(version_gt(v2, v1));
+        assert!(!gt(v1, v2));
+        assert!(gt(v2, v1));
     }
 
     #[test]
     fn compare_minor_test() {
         let v1 = "25.10.0.476";
         let v2 = "25.11.0.477";
-        assert!(!version_gt(v1, v2));
-        assert!(version_gt(v2, v1));
+        assert!(!gt(v1, v2));
+        assert!(gt(v2, v1));
     }
 
     #[test]
     fn compare_major_test() {
         let v1 = "25.10.0.476";
         let v2 = "26.01.0.477";
-        assert!(!version_gt(v1, v2));
-        assert!(version_gt(v2, v1));
+        assert!(!gt(v1, v2));
+        assert!(gt(v2, v1));
+    }
+
+    #[test]
+    fn compare_pre_release_test() {
+        assert!(gt("26.5.2", "26.5.2-rc1"));
+        assert!(gt("26.5.2-rc1", "25.10.0.477"));
+        assert!(gt("26.5.2-rc.2", "26.5.2-rc.1"));
+        assert!(gt("26.5.2-rc.10", "26.5.2-rc.2"));
+        assert!(gt("26.5.2-rc", "26.5.2-beta"));
+        assert!(gt("26.5.2-rc.1", "26.5.2-rc"));
+        assert!(gt("26.5.2-alpha", "26.5.2-1"));
+    }
+
+    #[test]
+    fn invalid_is_never_newer() {
+        assert!(gt("0.0.0.0", "garbage"));
+        assert!(!gt("garbage", "0.0.0.0"));
+        assert!(!gt("garbage", "other garbage"));
+    }
+
+    #[test]
+    fn release_channel_test() {
+        assert_eq!("stable", release_channel(&Version::parse("25.10.0.0")));
+        assert_eq!("stable", release_channel(&Version::parse("26.5.2")));
+        assert_eq!(
+            "development",
+            release_channel(&Version::parse("25.10.0.477"))
+        );
+        assert_eq!("stable", release_channel(&Version::parse("garbage")));
+    }
+
+    fn version_string() -> impl Strategy<Value = String> {
+        let numbers = prop::collection::vec(0u64..1000, 1..=4).prop_map(|parts| {
+            parts
+                .iter()
+                .map(u64::to_string)
+                .collect::<Vec<_>>()
+                .join(".")
+        });
+        let pre = prop::option::of(prop::collection::vec("[0-9]{1,3}|[a-z][a-z0-9]{0,4}", 1..3))
+            .prop_map(|ids| ids.map(|ids| ids.join(".")));
+        prop_oneof![
+            9 => (numbers, pre).prop_map(|(numbers, pre)| match pre {
+                Some(pre) => format!("{numbers}-{pre}"),
+                None => numbers,
+            }),
+            1 => ".*",
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn ordering_is_total(a in version_string(), b in version_string()) {
+            let (a, b) = (Version::parse(&a), Version::parse(&b));
+            prop_assert_eq!(a.cmp(&b), b.cmp(&a).reverse());
+            prop_assert_eq!(a == b, a.cmp(&b).is_eq());
+        }
+
+        #[test]
+        fn ordering_is_transitive(
+            a in version_string(),
+            b in version_string(),
+            c in version_string(),
+        ) {
+            let (a, b, c) = (Version::parse(&a), Version::parse(&b), Version::parse(&c));
+            if a <= b && b <= c {
+                prop_assert!(a <= c);
+            }
+            if a < b && b < c {
+                prop_assert!(a < c);
+            }
+        }
+
+        #[test]
+        fn pre_release_sorts_before_release(
+            numbers in prop::collection::vec(0u64..1000, 4),
+            pre in "[a-z][a-z0-9]{0,4}",
+        ) {
+            let release = numbers.iter().map(u64::to_string).collect::<Vec<_>>().join(".");
+            let pre_release = format!("{release}-{pre}");
+            prop_assert!(Version::parse(&release) > Version::parse(&pre_release));
+        }
+
+        #[test]
+        fn invalid_sorts_first(a in version_string()) {
+            prop_assert!(Version::Invalid <= Version::parse(&a));
+        }
     }
 }
@@ -0,0 +1,312 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2026 Kan-Ru Chen
+
+//! Reverse lookup of the zhuyin readings of a text (查注音).
+//!
+//! A text found as a phrase in the dictionaries gets the readings of the
+//! phrase. Otherwise each character gets its own readings.
+//!
+//! Dictionaries can only be searched by their syllables, so the readings of
+//! the characters are indexed once when the dictionaries are loaded. Phrases
+//! are then looked up by the combinations of the readings of their
+//! characters.
+
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+};
+
+use chewing::{
+    dictionary::{Dictionary, LookupStrategy, SystemDictionaryLoader},
+    zhuyin::Syllable,
+};
+use error_plus::{expect_error, impl_context_error};
+use serde::{Deserialize, Serialize};
+
+/// Dictionaries with the readings of characters and phrases. The characters
+/// are indexed from the first one.
+pub const READING_DICT_NAMES: [&str; 2] = ["word.dat", "tsi.dat"];
+
+/// Readings of a phrase tried at most, a phrase of polyphonic characters
+/// has many combinations.
+const MAX_COMBINATIONS: usize = 256;
+
+/// Readings of a character or a phrase.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Readings {
+    pub text: String,
+    /// Readings like `ㄧㄣˊ ㄏㄤˊ`, most frequent first
+    pub readings: Vec<String>,
+}
+
+impl Display for Readings {
+    /// Writes the text and its readings, e.g. `行：ㄒㄧㄥˊ／ㄏㄤˊ`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.readings.is_empty() {
+            write!(f, "{}：？", self.text)
+        } else {
+            write!(f, "{}：{}", self.text, self.readings.join("／"))
+        }
+    }
+}
+
+/// System dictionaries and the readings of their characters.
+pub struct ReadingDictionaries {
+    dicts: Vec<Box<dyn Dictionary>>,
+    /// Readings of each character, most frequent first
+    chars: HashMap<char, Vec<Syllable>>,
+}
+
+impl ReadingDictionaries {
+    /// Loads [`READING_DICT_NAMES`] from `search_path`, a `;` separated list
+    /// of directories.
+    pub fn load(search_path: &str) -> Result<ReadingDictionaries, ReadingError> {
+        expect_error("Unable to load dictionaries for reading lookup", || {
+            Ok(ReadingDictionaries::new(
+                SystemDictionaryLoader::new()
+                    .sys_path(search_path.to_string())
+                    .load(&READING_DICT_NAMES)?,
+            ))
+        })
+    }
+    /// Indexes the characters of the first of `dicts`, normally `word.dat`.
+    pub fn new(dicts: Vec<Box<dyn Dictionary>>) -> ReadingDictionaries {
+        let mut found: HashMap<char, Vec<(Syllable, u32)>> = HashMap::new();
+        for (syllables, phrase) in dicts.first().into_iter().flat_map(|dict| dict.entries()) {
+            let mut chars = phrase.as_str().chars();
+            if let ([syllable], Some(ch), None) = (syllables.as_slice(), chars.next(), chars.next())
+            {
+                found
+                    .entry(ch)
+                    .or_default()
+                    .push((*syllable, phrase.freq()));
+            }
+        }
+        let chars = found
+            .into_iter()
+            .map(|(ch, found)| (ch, by_frequency(found)))
+            .collect();
+        ReadingDictionaries { dicts, chars }
+    }
+    /// Looks up `text` in the system dictionaries and `user_dict`.
+    pub fn lookup(&self, user_dict: Option<&dyn Dictionary>, text: &str) -> Vec<Readings> {
+        let char_readings: Vec<&[Syllable]> = text
+            .chars()
+            .map(|ch| self.chars.get(&ch).map(Vec::as_slice).unwrap_or_default())
+            .collect();
+        // A single character is found in the index already
+        if char_readings.len() == 1 {
+            return vec![Readings {
+                text: text.to_string(),
+                readings: char_readings[0].iter().map(|s| s.to_string()).collect(),
+            }];
+        }
+        let dicts: Vec<&dyn Dictionary> = self
+            .dicts
+            .iter()
+            .map(|dict| dict.as_ref())
+            .chain(user_dict)
+            .collect();
+        let mut phrase = Vec::new();
+        for syllables in combinations(&char_readings, MAX_COMBINATIONS) {
+            for dict in &dicts {
+                for found in dict.lookup(&syllables, LookupStrategy::Standard) {
+                    if found.as_str() == text {
+                        phrase.push((join(&syllables), found.freq()));
+                    }
+                }
+            }
+        }
+        if !phrase.is_empty() {
+            return vec![Readings {
+                text: text.to_string(),
+                readings: by_frequency(phrase),
+            }];
+        }
+        text.chars()
+            .zip(char_readings)
+            .map(|(ch, readings)| Readings {
+                text: ch.to_string(),
+                readings: readings.iter().map(|s| s.to_string()).collect(),
+            })
+            .collect()
+    }
+}
+
+impl fmt::Debug for ReadingDictionaries {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReadingDictionaries")
+            .field("dicts", &self.dicts.len())
+            .field("chars", &self.chars.len())
+            .finish()
+    }
+}
+
+/// Readings ordered by their highest frequency in any dictionary.
+fn by_frequency<T: Clone + PartialEq + Display>(mut found: Vec<(T, u32)>) -> Vec<T> {
+    // Ties are ordered by the readings to keep the result stable
+    found.sort_by_cached_key(|(reading, freq)| (u32::MAX - freq, reading.to_string()));
+    let mut readings: Vec<T> = Vec::new();
+    for (reading, _) in found {
+        if !readings.contains(&reading) {
+            readings.push(reading);
+        }
+    }
+    readings
+}
+
+/// Up to `max` sequences taking one of the readings of each character.
+fn combinations(char_readings: &[&[Syllable]], max: usize) -> Vec<Vec<Syllable>> {
+    let mut sequences = vec![vec![]];
+    for readings in char_readings {
+        sequences = sequences
+            .iter()
+            .flat_map(|sequence| {
+                readings.iter().map(move |reading| {
+                    let mut sequence = sequence.clone();
+                    sequence.push(*reading);
+                    sequence
+                })
+            })
+            .take(max)
+            .collect();
+    }
+    sequences
+}
+
+fn join(syllables: &[Syllable]) -> String {
+    syllables
+        .iter()
+        .map(|syllable| syllable.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Notification text for the readings of `text`.
+pub fn format_readings(text: &str, readings: &[Readings]) -> String {
+    if readings.iter().all(|readings| readings.readings.is_empty()) {
+        return format!("查無注音：{text}");
+    }
+    readings
+        .iter()
+        .map(|readings| readings.to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+impl_context_error!(pub ReadingError);
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chewing::{
+        dictionary::{Dictionary, Phrase},
+        zhuyin::Syllable,
+    };
+
+    use super::{ReadingDictionaries, Readings, format_readings};
+
+    fn dict(entries: &[(&[&str], &str, u32)]) -> HashMap<Vec<Syllable>, Vec<Phrase>> {
+        let mut dict: HashMap<Vec<Syllable>, Vec<Phrase>> = HashMap::new();
+        for (syllables, phrase, freq) in entries {
+            let syllables = syllables.iter().map(|s| s.parse().unwrap()).collect();
+            dict.entry(syllables)
+                .or_default()
+                .push(Phrase::new(*phrase, *freq));
+        }
+        dict
+    }
+
+    fn dictionaries() -> ReadingDictionaries {
+        let words = dict(&[
+            (&["ㄒㄧㄥˊ"], "行", 500),
+            (&["ㄏㄤˊ"], "行", 800),
+            (&["ㄒㄧㄥˋ"], "行", 10),
+            (&["ㄧㄣˊ"], "銀", 300),
+        ]);
+        let phrases = dict(&[
+            (&["ㄧㄣˊ", "ㄏㄤˊ"], "銀行", 900),
+            (&["ㄒㄧㄥˊ", "ㄒㄧㄥˊ"], "行行", 5),
+        ]);
+        ReadingDictionaries::new(vec![Box::new(words), Box::new(phrases)])
+    }
+
+    fn readings(text: &str, readings: &[&str]) -> Readings {
+        Readings {
+            text: text.to_string(),
+            readings: readings.iter().map(|r| r.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn lookup_character() {
+        let dicts = dictionaries();
+        assert_eq!(
+            vec![readings("行", &["ㄏㄤˊ", "ㄒㄧㄥˊ", "ㄒㄧㄥˋ"])],
+            dicts.lookup(None, "行")
+        );
+        assert_eq!(vec![readings("酷", &[])], dicts.lookup(None, "酷"));
+    }
+
+    #[test]
+    fn lookup_phrase() {
+        let dicts = dictionaries();
+        assert_eq!(
+            vec![readings("銀行", &["ㄧㄣˊ ㄏㄤˊ"])],
+            dicts.lookup(None, "銀行")
+        );
+        assert_eq!(
+            vec![readings("行行", &["ㄒㄧㄥˊ ㄒㄧㄥˊ"])],
+            dicts.lookup(None, "行行")
+        );
+        // Not a phrase, so each character
+        assert_eq!(
+            vec![
+                readings("行", &["ㄏㄤˊ", "ㄒㄧㄥˊ", "ㄒㄧㄥˋ"]),
+                readings("銀", &["ㄧㄣˊ"]),
+                readings("酷", &[]),
+            ],
+            dicts.lookup(None, "行銀酷")
+        );
+        // Phrases learned by the user
+        let user_dict = dict(&[(&["ㄏㄤˊ", "ㄧㄣˊ"], "行銀", 1)]);
+        assert_eq!(
+            vec![readings("行銀", &["ㄏㄤˊ ㄧㄣˊ"])],
+            dicts.lookup(Some(&user_dict as &dyn Dictionary), "行銀")
+        );
+    }
+
+    #[test]
+    fn format_notification() {
+        let dicts = dictionaries();
+        let found = dicts.lookup(None, "行銀");
+        assert_eq!(
+            "行：ㄏㄤˊ／ㄒㄧㄥˊ／ㄒㄧㄥˋ\n銀：ㄧㄣˊ",
+            format_readings("行銀", &found)
+        );
+        let found = dicts.lookup(None, "酷");
+        assert_eq!("查無注音：酷", format_readings("酷", &found));
+    }
+
+    /// Uses the dictionaries downloaded by `cargo xtask download-components`
+    /// unless `CHEWING_PATH` is set.
+    #[test]
+    #[ignore = "needs the bundled dictionaries"]
+    fn lookup_bundled_dictionaries() {
+        let path = std::env::var("CHEWING_PATH").unwrap_or_else(|_| {
+            concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/../../build/installer/Dictionary"
+            )
+            .to_string()
+        });
+        let dicts = ReadingDictionaries::load(&path).unwrap();
+        let found = dicts.lookup(None, "銀行");
+        assert_eq!("銀行", found[0].text);
+        assert!(found[0].readings.contains(&"ㄧㄣˊ ㄏㄤˊ".to_string()));
+        let found = dicts.lookup(None, "行");
+        assert!(found[0].readings.contains(&"ㄒㄧㄥˊ".to_string()));
+        assert!(found[0].readings.contains(&"ㄏㄤˊ".to_string()));
+    }
+}
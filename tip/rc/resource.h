@@ -59,6 +59,8 @@
 #define ID_MOEDICT 40025
 #define ID_POPUP_40026 40026
 #define ID_OUTPUT_SIMP_CHINESE 40027
+#define ID_SKIP_UPDATE 40028
+#define ID_SNOOZE_UPDATE 40029
 #define IDC_STATIC -1
 
 // Next default values for new objects
@@ -67,7 +69,7 @@
 #ifndef APSTUDIO_READONLY_SYMBOLS
 #define _APS_NO_MFC 1
 #define _APS_NEXT_RESOURCE_VALUE 111
-#define _APS_NEXT_COMMAND_VALUE 40028
+#define _APS_NEXT_COMMAND_VALUE 40030
 #define _APS_NEXT_CONTROL_VALUE 1025
 #define _APS_NEXT_SYMED_VALUE 104
 #endif
@@ -3,7 +3,6 @@
 
 mod com;
 mod imm32;
-mod keybind;
 mod logging;
 mod msctf;
 mod quirk;
@@ -22,3 +22,20 @@ impl Quirk {
         None
     }
 }
+
+/// File name of the executable hosting the text service, e.g. "notepad.exe"
+pub(crate) fn exe_name() -> Option<String> {
+    let exe_path = exe_path()?;
+    let name = exe_path.rsplit('\\').next()?;
+    Some(name.to_string())
+}
+
+fn exe_path() -> Option<String> {
+    let mut buffer = [0u16; MAX_PATH as usize];
+    let len = unsafe { GetModuleFileNameW(None, &mut buffer[..]) as usize };
+    if len == 0 {
+        return None;
+    }
+    // The returned length does not include the terminating null
+    Some(String::from_utf16_lossy(&buffer[..len]))
+}
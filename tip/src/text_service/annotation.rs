@@ -0,0 +1,115 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2026 Kan-Ru Chen
+
+/// Short names of symbols that are hard to tell apart in the candidate list.
+const SYMBOL_GLOSSES: &[(char, &str)] = &[
+    ('，', "逗號"),
+    ('、', "頓號"),
+    ('。', "句號"),
+    ('．', "全形句點"),
+    ('・', "中點"),
+    ('‧', "間隔號"),
+    ('…', "刪節號"),
+    ('；', "分號"),
+    ('：', "冒號"),
+    ('？', "問號"),
+    ('！', "驚嘆號"),
+    ('「', "上引號"),
+    ('」', "下引號"),
+    ('『', "上雙引號"),
+    ('』', "下雙引號"),
+    ('（', "左括號"),
+    ('）', "右括號"),
+    ('〔', "左龜甲括號"),
+    ('〕', "右龜甲括號"),
+    ('【', "左粗括號"),
+    ('】', "右粗括號"),
+    ('《', "左書名號"),
+    ('》', "右書名號"),
+    ('〈', "左篇名號"),
+    ('〉', "右篇名號"),
+    ('—', "破折號"),
+    ('～', "波浪號"),
+    ('＿', "底線"),
+    ('　', "全形空白"),
+    ('★', "黑星"),
+    ('☆', "白星"),
+    ('○', "白圈"),
+    ('●', "黑圈"),
+    ('◎', "雙圈"),
+    ('□', "白方塊"),
+    ('■', "黑方塊"),
+    ('△', "白三角"),
+    ('▲', "黑三角"),
+    ('→', "右箭頭"),
+    ('←', "左箭頭"),
+    ('↑', "上箭頭"),
+    ('↓', "下箭頭"),
+    ('※', "參考符號"),
+    ('℃', "攝氏"),
+    ('°', "度"),
+    ('±', "正負"),
+    ('×', "乘號"),
+    ('÷', "除號"),
+    ('≠', "不等於"),
+    ('≦', "小於等於"),
+    ('≧', "大於等於"),
+    ('∞', "無限大"),
+    ('√', "根號"),
+    ('＄', "錢號"),
+    ('￥', "日圓"),
+    ('€', "歐元"),
+    ('£', "英鎊"),
+];
+
+/// Returns a short description of a single symbol candidate.
+///
+/// Han characters and multi-character phrases have no gloss. Symbols not in
+/// the table are described by their code point.
+pub(super) fn symbol_gloss(candidate: &str) -> Option<String> {
+    let mut chars = candidate.chars();
+    let (Some(ch), None) = (chars.next(), chars.next()) else {
+        return None;
+    };
+    if let Some((_, gloss)) = SYMBOL_GLOSSES.iter().find(|(sym, _)| *sym == ch) {
+        return Some(gloss.to_string());
+    }
+    if is_han(ch) || ch.is_alphanumeric() {
+        return None;
+    }
+    Some(format!("U+{:04X}", ch as u32))
+}
+
+fn is_han(ch: char) -> bool {
+    matches!(ch,
+        '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{20000}'..='\u{3134F}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::symbol_gloss;
+
+    #[test]
+    fn known_symbols() {
+        assert_eq!(Some("頓號".to_string()), symbol_gloss("、"));
+        assert_eq!(Some("全形空白".to_string()), symbol_gloss("　"));
+    }
+
+    #[test]
+    fn unknown_symbol_uses_code_point() {
+        assert_eq!(Some("U+2603".to_string()), symbol_gloss("☃"));
+        assert_eq!(Some("U+1F600".to_string()), symbol_gloss("😀"));
+    }
+
+    #[test]
+    fn no_gloss_for_words() {
+        assert_eq!(None, symbol_gloss("測"));
+        assert_eq!(None, symbol_gloss("𠀀"));
+        assert_eq!(None, symbol_gloss("ａ"));
+        assert_eq!(None, symbol_gloss("測試"));
+        assert_eq!(None, symbol_gloss(""));
+    }
+}
@@ -0,0 +1,175 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2026 Kan-Ru Chen
+
+use std::{cell::RefCell, collections::HashMap, ffi::c_void, sync::atomic::Ordering, thread};
+
+use anyhow::Result;
+use chewing_tip_core::ipc::{
+    client::ChewingIpcClient,
+    messages::{UnwatchCandidateEvents, WatchCandidateEvents},
+    values::CandidateEvent,
+    varlink::MethodCall,
+};
+use error_plus::{ErrorExt, expect_error};
+use log::{debug, error};
+use windows::Win32::{
+    Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, WPARAM},
+    UI::WindowsAndMessaging::{
+        CreateWindowExW, DefWindowProcW, DestroyWindow, PostMessageW, RegisterClassExW, WM_APP,
+        WM_SETTINGCHANGE, WM_THEMECHANGED, WNDCLASSEXW, WS_EX_TOOLWINDOW, WS_POPUP,
+    },
+};
+use windows_core::w;
+
+use crate::com::G_HINSTANCE;
+
+use super::{IFnRunCommand, theme::ThemeDetector};
+
+const WM_CANDIDATE_CLICKED: u32 = WM_APP + 1;
+const WM_CANDIDATE_PAGE_REQUESTED: u32 = WM_APP + 2;
+
+thread_local! {
+    // Window procedures run on the thread that created the window
+    static RUN_COMMANDS: RefCell<HashMap<usize, IFnRunCommand>> = RefCell::default();
+}
+
+/// Receives mouse events of the host candidate window.
+///
+/// Events are read by a background thread and posted to a hidden window so
+/// they are handled on the thread that owns the text service. The window is
+/// not message-only so it also gets the broadcasts about theme changes.
+pub(super) struct CandidateEventListener {
+    hwnd: HWND,
+    session: u64,
+}
+
+impl CandidateEventListener {
+    pub(super) fn new(run_command: IFnRunCommand) -> Result<CandidateEventListener> {
+        let hinst = HINSTANCE(G_HINSTANCE.load(Ordering::Relaxed) as *mut c_void);
+        let wc = WNDCLASSEXW {
+            cbSize: size_of::<WNDCLASSEXW>() as u32,
+            lpfnWndProc: Some(wnd_proc),
+            hInstance: hinst,
+            lpszClassName: w!("ChewingCandidateEvents"),
+            ..Default::default()
+        };
+        // Registering again fails harmlessly when the class already exists
+        unsafe { RegisterClassExW(&wc) };
+        let hwnd = unsafe {
+            CreateWindowExW(
+                WS_EX_TOOLWINDOW,
+                w!("ChewingCandidateEvents"),
+                None,
+                WS_POPUP,
+                0,
+                0,
+                0,
+                0,
+                None,
+                None,
+                Some(hinst),
+                None,
+            )?
+        };
+        RUN_COMMANDS.with_borrow_mut(|map| map.insert(hwnd.0 as usize, run_command));
+        Ok(CandidateEventListener {
+            hwnd,
+            session: getrandom::u64()?,
+        })
+    }
+    pub(super) fn session(&self) -> u64 {
+        self.session
+    }
+    /// Starts streaming events from chewing_tip_host.
+    ///
+    /// Must be called again after reconnecting to a restarted host.
+    pub(super) fn watch(&self) {
+        let hwnd = self.hwnd.0 as usize;
+        let session = self.session;
+        thread::spawn(move || {
+            if let Err(error) = stream_events(HWND(hwnd as *mut c_void), session) {
+                debug!("{}", error.error_report());
+            }
+        });
+    }
+    pub(super) fn unwatch(&self, ipc_client: &ChewingIpcClient) {
+        let res: Result<(), error_plus::Error> =
+            expect_error("Failed to stop candidate events", || {
+                ipc_client.send(MethodCall {
+                    method: UnwatchCandidateEvents::METHOD.to_string(),
+                    parameters: serde_json::to_value(UnwatchCandidateEvents {
+                        session: self.session,
+                    })?,
+                    oneway: Some(true),
+                    more: None,
+                    upgrade: None,
+                })?;
+                Ok(())
+            });
+        if let Err(error) = res {
+            error!("{}", error.error_report());
+        }
+    }
+}
+
+impl Drop for CandidateEventListener {
+    fn drop(&mut self) {
+        // Release the text service reference to break the cycle
+        RUN_COMMANDS.with_borrow_mut(|map| map.remove(&(self.hwnd.0 as usize)));
+        if let Err(error) = unsafe { DestroyWindow(self.hwnd) } {
+            error!("Failed to destroy candidate event window: {error}");
+        }
+    }
+}
+
+fn stream_events(hwnd: HWND, session: u64) -> Result<(), error_plus::Error> {
+    expect_error("Candidate event stream closed", || {
+        let client = ChewingIpcClient::new();
+        client.connect()?;
+        let mut reply = client.send(MethodCall {
+            method: WatchCandidateEvents::METHOD.to_string(),
+            parameters: serde_json::to_value(WatchCandidateEvents { session })?,
+            oneway: None,
+            more: Some(true),
+            upgrade: None,
+        })?;
+        loop {
+            // Heartbeats from the host carry no event
+            if !reply.parameters.is_null() {
+                let (msg, value) = match serde_json::from_value(reply.parameters)? {
+                    CandidateEvent::CandidateClicked { index } => {
+                        (WM_CANDIDATE_CLICKED, index as isize)
+                    }
+                    CandidateEvent::CandidatePageRequested { delta } => {
+                        (WM_CANDIDATE_PAGE_REQUESTED, delta as isize)
+                    }
+                };
+                // Fails once the window is destroyed
+                unsafe { PostMessageW(Some(hwnd), msg, WPARAM(0), LPARAM(value))? };
+            }
+            reply = client.receive()?;
+        }
+    })
+}
+
+extern "system" fn wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    let event = match msg {
+        WM_SETTINGCHANGE | WM_THEMECHANGED => {
+            ThemeDetector::appearance_changed();
+            return unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) };
+        }
+        WM_CANDIDATE_CLICKED => CandidateEvent::CandidateClicked {
+            index: lparam.0 as u32,
+        },
+        WM_CANDIDATE_PAGE_REQUESTED => CandidateEvent::CandidatePageRequested {
+            delta: lparam.0 as i32,
+        },
+        _ => return unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) },
+    };
+    // Clone out of the map so the handler can drop the listener
+    let run_command = RUN_COMMANDS.with_borrow(|map| map.get(&(hwnd.0 as usize)).cloned());
+    if let Some(run_command) = run_command {
+        unsafe { run_command.on_candidate_event(&event) };
+    }
+    LRESULT(0)
+}
@@ -6,9 +6,10 @@ use std::collections::HashMap;
 use std::ffi::c_void;
 use std::mem;
 use std::rc::{Rc, Weak};
+use std::str::FromStr;
 use std::sync::OnceLock;
 use std::sync::atomic::Ordering;
-use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result, bail};
 use chewing::conversion::{ChewingEngine, FuzzyChewingEngine, SimpleEngine};
@@ -19,21 +20,33 @@ use chewing::editor::{
     UserPhraseAddDirection,
 };
 use chewing::input::keycode::Keycode;
-use chewing::input::keysym::{Keysym, SYM_CAPSLOCK, SYM_LEFTSHIFT, SYM_RIGHTSHIFT, SYM_SPACE};
+use chewing::input::keysym::{
+    Keysym, SYM_BACKSPACE, SYM_CAPSLOCK, SYM_PAGEDOWN, SYM_PAGEUP, SYM_SPACE,
+};
 use chewing::input::{KeyState, KeyboardEvent, keycode, keysym};
 use chewing::zhuyin::Syllable;
+use chewing_tip_core::action::{ActionHandler, dispatch, keyboard_layout_name};
 use chewing_tip_core::config::{ChewingTsfConfig, Config};
+use chewing_tip_core::english::{EnglishWords, parse_convert_key};
 use chewing_tip_core::ipc::client::ChewingIpcClient;
 use chewing_tip_core::ipc::messages::{
-    CheckUpdate, OnTestKeyDown, ShowCandidateList, ShowNotification,
+    OnTestKeyDown, ShowCandidateList, ShowComposition, ShowNotification,
+};
+use chewing_tip_core::ipc::values::{
+    CandidateAnnotation, CandidateEvent, IpcKeyEvent, NotificationKind, Position, ScreenRect,
 };
-use chewing_tip_core::ipc::values::{IpcKeyEvent, IpcShiftKeyState, Position};
 use chewing_tip_core::ipc::varlink::MethodCall;
+use chewing_tip_core::keybind::{
+    EditState, InputState, KeyCombo, KeyMatch, KeybindingMatcher, parse_keybindings,
+};
+use chewing_tip_core::lang_toggle::{LangToggle, ToggleMatch};
+use chewing_tip_core::reading::{ReadingDictionaries, format_readings, load_dictionaries};
+use chewing_tip_core::script::{ConvertedCandidates, ScriptOverrides, ScriptVariant};
 use chewing_tip_core::shell::{launch_tip_host, open_url, program_dir, user_dir};
+use chewing_tip_core::theme::{Theme, ThemeChoice};
 use error_plus::impl_context_error;
 use error_plus::{ErrorExt, expect_error};
 use log::{debug, error, info};
-use serde_json::Value;
 use windows::Win32::Foundation::{GetLastError, HINSTANCE, POINT, RECT};
 use windows::Win32::System::Variant::VARIANT;
 use windows::Win32::UI::Input::KeyboardAndMouse::GetFocus;
@@ -56,23 +69,26 @@ use windows_core::{ComObject, ComObjectInner, GUID, HSTRING, Interface, PCWSTR};
 use zhconv::{Variant, zhconv};
 
 use crate::com::G_HINSTANCE;
-use crate::keybind::Keybinding;
+use crate::quirk::exe_name;
 use crate::text_service::TextService;
+use crate::text_service::candidate_events::CandidateEventListener;
 use crate::text_service::edit_session::request_edit_session;
 use crate::text_service::lang_bar::LangBarFactory;
 
 use super::CommandType;
 use super::GUID_INPUT_DISPLAY_ATTRIBUTE_1;
 use super::GUID_INPUT_DISPLAY_ATTRIBUTE_2;
+use super::annotation::symbol_gloss;
 use super::display_attribute::register_display_attribute;
 use super::edit_session::InsertText;
 use super::edit_session::{EndComposition, SelectionRect, SetCompositionString};
 use super::key_event::SystemKeyboardEvent;
 use super::lang_bar::LangBarButton;
 use super::menu::Menu;
+use super::mode_indicator::{ModeIndicatorThrottle, mode_indicator_text};
 use super::resources::*;
 use super::theme::{ThemeDetector, WindowsTheme};
-use super::ui_elements::{CandidateList, FilterKeyResult, Notification};
+use super::ui_elements::{CandidateList, CompositionWindow, FilterKeyResult, Notification};
 
 const GUID_MODE_BUTTON: GUID = GUID::from_u128(0xB59D51B9_B832_40D2_9A8D_56959372DDC7);
 const GUID_SHAPE_TYPE_BUTTON: GUID = GUID::from_u128(0x5325DBF5_5FBE_467B_ADF0_2395BE9DD2BB);
@@ -82,6 +98,9 @@ pub(crate) const CLSID_TEXT_SERVICE: GUID = GUID::from_u128(0x13F2EF08_575C_4D8C
 
 impl_context_error!(TsfError);
 
+/// Days the update notification is put off from the menu
+const UPDATE_SNOOZE_DAYS: u32 = 7;
+
 const SEL_KEYS: [&str; 6] = [
     "1234567890",
     "asdfghjkl;",
@@ -91,24 +110,6 @@ const SEL_KEYS: [&str; 6] = [
     "1234qweras",
 ];
 
-#[derive(Debug)]
-enum ShiftKeyState {
-    Down(Instant),
-    Consumed,
-    Up,
-}
-
-impl ShiftKeyState {
-    fn release(&mut self) -> Option<Duration> {
-        let duration = match self {
-            ShiftKeyState::Down(instant) => Some(instant.elapsed()),
-            ShiftKeyState::Consumed | ShiftKeyState::Up => None,
-        };
-        *self = ShiftKeyState::Up;
-        duration
-    }
-}
-
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum TsfLangMode {
     Chinese,
@@ -163,6 +164,7 @@ pub(super) struct ChewingTextService {
     lang_bar_buttons: Vec<ITfLangBarItemButton>,
     composition_sink: ITfCompositionSink,
     ipc_client: ChewingIpcClient,
+    candidate_events: CandidateEventListener,
 
     switch_lang_button: ComObject<LangBarButton>,
     switch_shape_button: ComObject<LangBarButton>,
@@ -172,14 +174,42 @@ pub(super) struct ChewingTextService {
     pending_lang_mode_change: Cell<bool>,
 
     has_focus: bool,
-    output_simp_chinese: bool,
-    shift_key_state: ShiftKeyState,
+    output_variant: ScriptVariant,
+    script_overrides: ScriptOverrides,
+    /// Show the preedit in a host window instead of inline
+    floating_composition: bool,
+    lang_toggle: LangToggle,
     cfg: Config,
     kbtype: KeyboardLayoutCompat,
-    keybindings: Vec<Keybinding>,
+    keybindings: KeybindingMatcher,
+    /// Keys typed since the composition started, for `commit_raw_keys`
+    ///
+    /// `None` once the buffer was edited other than by typing at its end,
+    /// as the keys no longer spell out the buffer then.
+    raw_keys: Option<String>,
+    /// Positions of the keys in `raw_keys` the keyboard layout rejected as
+    /// zhuyin
+    rejected_keys: Vec<usize>,
+    english_words: EnglishWords,
+    /// Key committing `raw_keys` as English while `english_hint` is set
+    english_convert_key: Option<KeyCombo>,
+    /// The convert key commits `raw_keys` as English
+    english_hint: bool,
+    /// Text committed last by the editor, for `{last}` in text templates
+    last_commit: String,
+    theme: ThemeChoice,
+    user_theme: Option<Theme>,
     chewing_editor: Editor,
+    /// Loaded on the first reading lookup
+    reading_dicts: Option<ReadingDictionaries>,
     notification: Option<ComObject<Notification>>,
+    /// Update info already announced, so it is shown only once
+    notified_update_url: String,
+    mode_indicator: ModeIndicatorThrottle,
     candidate_list: Option<ComObject<CandidateList>>,
+    /// Candidates in `candidate_list`, converted to the output variant
+    shown_candidates: ConvertedCandidates,
+    composition_window: Option<CompositionWindow>,
     composition: Rc<RefCell<Option<ITfComposition>>>,
     pending_edit: Weak<RefCell<Option<CompositionString>>>,
 }
@@ -278,23 +308,39 @@ impl ChewingTextService {
             tid,
             composition_sink: ts.cast()?,
             ipc_client: ChewingIpcClient::new(),
+            candidate_events: CandidateEventListener::new(ts.cast()?)?,
             input_da_atom: [input_da_atom_1, input_da_atom_2],
             _menu: menu,
             popup_menu,
             lang_mode: Cell::new(TsfLangMode::English),
             has_focus: true,
-            output_simp_chinese: Default::default(),
-            shift_key_state: ShiftKeyState::Up,
+            output_variant: ScriptVariant::default(),
+            script_overrides: ScriptOverrides::default(),
+            floating_composition: false,
+            lang_toggle: LangToggle::default(),
             cfg,
             kbtype: KeyboardLayoutCompat::Default,
-            keybindings: vec![],
+            keybindings: KeybindingMatcher::default(),
+            raw_keys: Some(String::new()),
+            rejected_keys: Vec::new(),
+            english_words: EnglishWords::default(),
+            english_convert_key: None,
+            english_hint: false,
+            last_commit: String::new(),
+            theme: ThemeChoice::Custom,
+            user_theme: None,
             chewing_editor: editor,
+            reading_dicts: None,
             lang_bar_buttons,
             switch_lang_button,
             switch_shape_button,
             ime_mode_button,
             notification: Default::default(),
+            notified_update_url: String::new(),
+            mode_indicator: ModeIndicatorThrottle::default(),
             candidate_list: Default::default(),
+            shown_candidates: ConvertedCandidates::default(),
+            composition_window: None,
             composition: Default::default(),
             pending_edit: Weak::new(),
             pending_lang_mode_change: Cell::new(false),
@@ -310,29 +356,10 @@ impl ChewingTextService {
 
         if let Err(error) = cts.ipc_client.connect() {
             error!("{}", error.error_report());
+        } else {
+            cts.candidate_events.watch();
         }
 
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .as_ref()
-            .map(Duration::as_secs)
-            .unwrap_or_default();
-        if cts.cfg.chewing_tsf.auto_check_update_channel != "none"
-            && now.abs_diff(cts.cfg.chewing_tsf.last_update_check_time) > 3600
-        {
-            if let Err(error) = cts.ipc_client.send(MethodCall {
-                method: CheckUpdate::METHOD.to_string(),
-                oneway: Some(true),
-                parameters: Value::Null,
-                more: None,
-                upgrade: None,
-            }) {
-                error!(
-                    "unable to send IPC message CheckUpdate: {}",
-                    error.error_report()
-                );
-            }
-        }
         Ok(cts)
     }
 
@@ -340,6 +367,12 @@ impl ChewingTextService {
         if let Err(error) = self.remove_buttons() {
             error!("failed to remove buttons: {error:#}");
         }
+        self.candidate_events.unwatch(&self.ipc_client);
+        if let Some(composition_window) = self.composition_window.take()
+            && let Err(error) = composition_window.hide()
+        {
+            error!("failed to hide composition window: {error:#}");
+        }
         // TSF doc: The corresponding ITfTextInputProcessor::Deactivate
         // method that shuts down the text service must release all references
         // to the ptim parameter.
@@ -362,6 +395,7 @@ impl ChewingTextService {
     pub(super) fn on_focus(&mut self) -> Result<()> {
         debug!("on_focus");
         self.has_focus = true;
+        self.show_mode_indicator();
         Ok(())
     }
 
@@ -369,6 +403,25 @@ impl ChewingTextService {
         let _ = self.cfg.reload_if_needed();
         self.apply_runtime_config()?;
         self.sync_lang_mode(true)?;
+        self.notify_update()?;
+        Ok(())
+    }
+
+    fn notify_update(&mut self) -> Result<()> {
+        let url = &self.cfg.chewing_tsf.update_info_url;
+        if url.is_empty() || *url == self.notified_update_url {
+            return Ok(());
+        }
+        // Try again on the next focus change if no document has focus yet
+        let Ok(context) = (unsafe { self.thread_mgr.GetFocus().and_then(|dm| dm.GetTop()) }) else {
+            return Ok(());
+        };
+        self.notified_update_url = url.clone();
+        self.show_message(
+            &context,
+            &HSTRING::from("新版本已推出，請從語言列選單更新"),
+            NotificationKind::UpdateAvailable,
+        )?;
         Ok(())
     }
 
@@ -419,7 +472,11 @@ impl ChewingTextService {
                     error!("{}", error.error_report());
                 } else if let Err(error) = self.ipc_client.connect() {
                     error!("{}", error.error_report());
+                } else {
+                    self.candidate_events.watch();
                 }
+            } else {
+                self.candidate_events.watch();
             }
         }
 
@@ -427,14 +484,10 @@ impl ChewingTextService {
         let is_composing = self.is_composing();
         let evt = ev.to_keyboard_event(self.cfg.chewing_tsf.simulate_english_layout);
         let simulate_english_layout = self.cfg.chewing_tsf.simulate_english_layout != 0;
-        // Determine shift key state here, this might be our last chance seeing this key.
-        if evt.ksym != SYM_LEFTSHIFT
-            && evt.ksym != SYM_RIGHTSHIFT
-            && evt.is_state_on(KeyState::Shift)
-        {
-            self.shift_key_state = ShiftKeyState::Consumed;
-        }
-        debug!(evt:?, shift_key_state:? = self.shift_key_state; "on_test_keydown");
+        let press = ev.to_key_press(self.cfg.chewing_tsf.simulate_english_layout);
+        // Track the toggle key here, this might be our last chance seeing this key.
+        let lang_toggle = self.lang_toggle.test_key_down(press, Instant::now());
+        debug!(evt:?, lang_toggle:?; "on_test_keydown");
 
         // Send IPC
         let _handled = self.ipc_client.send(MethodCall {
@@ -442,11 +495,6 @@ impl ChewingTextService {
             parameters: serde_json::to_value(OnTestKeyDown {
                 is_context_mutable,
                 is_composing,
-                shift_key_state: match self.shift_key_state {
-                    ShiftKeyState::Down(_) => IpcShiftKeyState::Down,
-                    ShiftKeyState::Consumed => IpcShiftKeyState::Consumed,
-                    ShiftKeyState::Up => IpcShiftKeyState::Up,
-                },
                 event: IpcKeyEvent {
                     vk: ev.vk,
                     scan_code: ev.scan_code,
@@ -458,16 +506,6 @@ impl ChewingTextService {
             more: None,
             upgrade: None,
         });
-        let mut shift_down = false;
-        if (evt.ksym == SYM_LEFTSHIFT || evt.ksym == SYM_RIGHTSHIFT)
-            && self.cfg.chewing_tsf.switch_lang_with_shift
-            && matches!(self.shift_key_state, ShiftKeyState::Up)
-        {
-            debug!("shift_key_state = Down");
-            self.shift_key_state = ShiftKeyState::Down(Instant::now());
-            shift_down = true;
-            // return Ok(false);
-        }
         // Ok(handled?.parameters.as_bool().unwrap_or_default())
         //
         // Step 1. apply any config changes
@@ -482,21 +520,27 @@ impl ChewingTextService {
         if self.lang_mode.get().is_disabled() {
             return Ok(false);
         }
+        // Also tracks modifier taps, so test before any early return
+        let keybinding = self
+            .keybindings
+            .test_key_down(self.input_state(), press, Instant::now());
         //
-        // Step 2.1 handle switch lang with Shift
+        // Step 2.1 handle the language toggle key
         //
-        if shift_down {
-            return Ok(false);
+        match lang_toggle {
+            ToggleMatch::None => {}
+            ToggleMatch::Tap => return Ok(false),
+            ToggleMatch::Press => return Ok(true),
         }
         //
         // Step 2.2 handle any keybindings
         //
-        if self.keybindings.iter().any(|kb| kb.matches(&evt)) {
+        if keybinding != KeyMatch::None {
             return Ok(true);
         }
         //
         // Step 2.3 ignore CapsLock if disabled
-        if evt.ksym == SYM_CAPSLOCK && !self.cfg.chewing_tsf.enable_caps_lock {
+        if evt.ksym == SYM_CAPSLOCK && !self.caps_lock_locks_lang() {
             return Ok(false);
         }
         //
@@ -528,7 +572,7 @@ impl ChewingTextService {
                 return Ok(false);
             }
         }
-        if self.cfg.chewing_tsf.enable_caps_lock
+        if self.caps_lock_locks_lang()
             && !self.cfg.chewing_tsf.lock_chinese_on_caps_lock
             && evt.ksym.is_unicode()
         {
@@ -580,42 +624,60 @@ impl ChewingTextService {
         let mut evt = ev.to_keyboard_event(self.cfg.chewing_tsf.simulate_english_layout);
         debug!(evt:?; "on_keydown");
 
+        let press = ev.to_key_press(self.cfg.chewing_tsf.simulate_english_layout);
+        if self.lang_toggle.key_down(press, Instant::now()) {
+            self.switch_lang_with_key(context)?;
+            return Ok(true);
+        }
+
         // Handle keybindings
-        // FIXME: refactor this
-        let mut text_action = None;
-        if let Some(keybinding) = self.keybindings.iter().find(|kb| kb.matches(&evt)) {
-            debug!("matched keybinding on action={}", keybinding.action);
-            let mut handled = true;
-            match keybinding.action.as_str() {
-                "toggle_simplified_chinese" => {
-                    self.toggle_simp_chinese()?;
-                }
-                "toggle_hsu_keyboard" => {
-                    self.toggle_hsu_keyboard(context)?;
-                }
-                "text" => {
-                    if !self.chewing_editor.is_empty() {
-                        self.chewing_editor.commit()?;
-                    }
-                    text_action = Some(keybinding.param.clone());
-                    handled = false;
-                }
-                act => {
-                    if act.starts_with("selecting_") {
-                        handled = false;
-                    } else {
-                        error!("Unsupported keybinding action: {act}");
-                    }
-                }
-            }
-            if handled {
+        match self
+            .keybindings
+            .key_down(self.input_state(), press, Instant::now())
+        {
+            KeyMatch::None => {}
+            KeyMatch::Pending => {
+                debug!("waiting for the next key of a keybinding");
                 return Ok(true);
             }
+            KeyMatch::Complete(action) => {
+                debug!("matched keybinding on action={action}");
+                let mut actions = KeyActions {
+                    service: self,
+                    context,
+                };
+                if dispatch(&mut actions, &action)? {
+                    return Ok(true);
+                }
+            }
         }
 
-        if text_action.is_some() {
-            // do nothing, handled later
-        } else if evt.ksym.is_unicode() {
+        if self.english_hint
+            && self
+                .english_convert_key
+                .is_some_and(|key| key.matches(&press))
+            && self.is_composing()
+            && !self.chewing_editor.is_selecting()
+        {
+            debug!("committing raw keys as English");
+            self.english_hint = false;
+            let mut actions = KeyActions {
+                service: self,
+                context,
+            };
+            actions.commit_raw_keys()?;
+            return Ok(true);
+        }
+
+        if evt.ksym.is_unicode() {
+            if !self.is_composing() {
+                self.raw_keys = Some(String::new());
+                self.rejected_keys.clear();
+            }
+            let selecting = self.chewing_editor.is_selecting();
+            if !selecting && !self.cursor_at_end() {
+                self.forget_raw_keys();
+            }
             let mut momentary_english_mode = false;
             let mut upper_case = false;
             if evt.is_state_on(KeyState::Shift) {
@@ -644,11 +706,14 @@ impl ChewingTextService {
             } else {
                 evt.ksym
             };
+            let shift_space = evt.ksym == SYM_SPACE && evt.is_state_on(KeyState::Shift);
             // HACK: convert sel_keys key to number key
-            if self.chewing_editor.is_selecting() {
+            if selecting {
                 evt = self.map_sel_key(evt);
+            } else if !shift_space && let Some(raw_keys) = &mut self.raw_keys {
+                raw_keys.push(evt.ksym.to_unicode());
             }
-            if evt.ksym == SYM_SPACE && evt.is_state_on(KeyState::Shift) {
+            if shift_space {
                 // TODO: maybe this can be merged back to the default branch?
                 self.chewing_editor.process_keyevent(evt);
             } else if self.lang_mode.get() == LanguageMode::English || momentary_english_mode {
@@ -660,6 +725,16 @@ impl ChewingTextService {
                     .set_editor_options(|opt| opt.language_mode = old_lang_mode);
             } else {
                 self.chewing_editor.process_keyevent(evt);
+                if self.chewing_editor.last_key_behavior() == EditorKeyBehavior::Bell
+                    && let Some(raw_keys) = &self.raw_keys
+                {
+                    self.rejected_keys
+                        .push(raw_keys.chars().count().saturating_sub(1));
+                }
+            }
+            // Selecting a candidate may move the cursor away from the end
+            if selecting {
+                self.forget_raw_keys();
             }
         } else {
             let mut key_handled = false;
@@ -668,8 +743,11 @@ impl ChewingTextService {
             {
                 match candidate_list.filter_key_event(evt.ksym) {
                     FilterKeyResult::HandledCommit => {
-                        let sel_key = candidate_list.current_sel();
-                        self.chewing_editor.select(sel_key)?;
+                        let sel = candidate_list.current_sel();
+                        if let Some(index) = self.shown_candidates.source_index(sel) {
+                            self.chewing_editor.select(index)?;
+                        }
+                        self.forget_raw_keys();
                         key_handled = true;
                     }
                     FilterKeyResult::Handled => {
@@ -682,79 +760,44 @@ impl ChewingTextService {
                         // do nothing
                     }
                 }
-                if let Some(keybinding) = self.keybindings.iter().find(|kb| kb.matches(&evt)) {
-                    debug!("matched keybinding on action={}", keybinding.action);
-                    match keybinding.action.as_str() {
-                        "selecting_unlearn_phrase" => {
-                            if self.chewing_editor.is_selecting() {
-                                if self.cfg.chewing_tsf.cursor_cand_list
-                                    && let Some(candidate_list) = &self.candidate_list
-                                {
-                                    let phrase = candidate_list.current_phrase();
-                                    let phrase_len = phrase.chars().count();
-                                    // TODO: expose begin and end from selector
-                                    let cursor = if self.cfg.chewing_tsf.phrase_choice_rearward {
-                                        self.chewing_editor.cursor().saturating_sub(phrase_len - 1)
-                                    } else {
-                                        self.chewing_editor.cursor()
-                                    };
-                                    let syllables: Vec<Syllable> = self
-                                        .chewing_editor
-                                        .symbols()
-                                        .iter()
-                                        .skip(cursor)
-                                        .take(phrase_len)
-                                        .map_while(|s| s.to_syllable())
-                                        .collect();
-                                    if syllables.len() == phrase_len {
-                                        if let Err(error) =
-                                            self.chewing_editor.unlearn_phrase(&syllables, &phrase)
-                                        {
-                                            error!("failed to unlearn phrase: {error}");
-                                        }
-                                        self.update_candidates(context)?;
-                                        // TODO: move this to editor
-                                        self.show_message(
-                                            context,
-                                            &format!("刪除：{phrase}").into(),
-                                        )?;
-                                        key_handled = true;
-                                    }
-                                }
-                            }
-                        }
-                        act => {
-                            error!("Unsupported keybinding action: {act}");
-                        }
-                    }
-                }
             }
 
             if !key_handled {
+                let erases_last_key = evt.ksym == SYM_BACKSPACE && self.backspace_erases_last_key();
                 self.chewing_editor.process_keyevent(evt);
+                if erases_last_key && let Some(raw_keys) = &mut self.raw_keys {
+                    raw_keys.pop();
+                } else if self.chewing_editor.last_key_behavior() != EditorKeyBehavior::Ignore {
+                    // Cursor moves, Delete and the like edit the buffer elsewhere
+                    self.forget_raw_keys();
+                }
             }
         }
 
-        let last_behavior = self.chewing_editor.last_key_behavior();
-
-        if last_behavior == EditorKeyBehavior::Ignore {
+        if self.chewing_editor.last_key_behavior() == EditorKeyBehavior::Ignore {
             debug!("early return - chewing ignored key");
             return Ok(false);
         }
 
+        self.update_after_key(context)?;
+        Ok(true)
+    }
+
+    /// Commits or updates the composition after the editor handled a key.
+    fn update_after_key(&mut self, context: &ITfContext) -> Result<()> {
+        let last_behavior = self.chewing_editor.last_key_behavior();
+        if last_behavior == EditorKeyBehavior::Commit {
+            // The keys of the committed text are still in `raw_keys`
+            self.forget_raw_keys();
+        }
+
         // Not composing so we can commit the text immediately
-        if !self.is_composing()
-            && (last_behavior == EditorKeyBehavior::Commit || text_action.is_some())
-        {
-            let text = self.chewing_editor.display_commit().to_owned();
-            self.chewing_editor.ack();
+        if !self.is_composing() && last_behavior == EditorKeyBehavior::Commit {
+            let text = self.take_commit();
             debug!(text; "commit string");
             self.insert_text(context, &text)?;
-            if let Some(param) = text_action {
-                self.insert_text(context, &param)?;
-            }
             debug!("commit string ok");
-            return Ok(true);
+            return Ok(());
         }
 
         if let Err(error) = self.update_candidates(context) {
@@ -764,12 +807,7 @@ impl ChewingTextService {
         debug!("updated candidates");
 
         let commit = if last_behavior == EditorKeyBehavior::Commit {
-            let mut commit = self.chewing_editor.display_commit().to_owned();
-            self.chewing_editor.ack();
-            if let Some(param) = text_action {
-                commit.push_str(&param);
-            }
-            commit
+            self.take_commit()
         } else {
             String::new()
         };
@@ -778,12 +816,72 @@ impl ChewingTextService {
 
         if !self.chewing_editor.notification().is_empty() {
             let msg = HSTRING::from(self.chewing_editor.notification());
-            if let Err(error) = self.show_message(context, &msg) {
+            if let Err(error) = self.show_message(context, &msg, NotificationKind::Info) {
                 error!("{}", error.error_report());
             }
         }
 
-        Ok(true)
+        if let Err(error) = self.update_english_hint(context) {
+            error!("{}", error.error_report());
+        }
+
+        Ok(())
+    }
+
+    /// Offers the convert key to commit the typed keys as English when they
+    /// look like an English word.
+    fn update_english_hint(&mut self, context: &ITfContext) -> Result<()> {
+        let was_shown = self.english_hint;
+        self.english_hint = self.cfg.chewing_tsf.detect_english
+            && self.lang_mode.get() == TsfLangMode::Chinese
+            && self.is_composing()
+            && !self.chewing_editor.is_selecting()
+            && self.raw_keys.as_deref().is_some_and(|raw_keys| {
+                self.english_words
+                    .detect(raw_keys, self.rejected_keys.len())
+                    .is_some()
+            });
+        // Only notify when the hint appears, not on every key
+        if self.english_hint && !was_shown {
+            let msg = match self.english_convert_key {
+                Some(key) => format!("按 {key} 輸出英文"),
+                None => "輸入的可能是英文".to_string(),
+            };
+            self.show_message(context, &HSTRING::from(msg), NotificationKind::Info)?;
+        }
+        Ok(())
+    }
+
+    pub(super) fn on_candidate_event(&mut self, event: CandidateEvent) -> Result<()> {
+        debug!(event:?; "on_candidate_event");
+        if !self.chewing_editor.is_selecting() {
+            debug!("candidate event ignored - not selecting");
+            return Ok(());
+        }
+        let context = unsafe {
+            self.thread_mgr
+                .GetFocus()
+                .context("failed to get current ITfDocumentMgr")?
+                .GetTop()
+                .context("failed to get current ITfContext")?
+        };
+        match event {
+            CandidateEvent::CandidateClicked { index } => {
+                if let Some(index) = self.shown_candidates.source_index(index as usize) {
+                    self.chewing_editor.select(index)?;
+                }
+                self.forget_raw_keys();
+            }
+            CandidateEvent::CandidatePageRequested { delta } => {
+                let ksym = if delta < 0 { SYM_PAGEUP } else { SYM_PAGEDOWN };
+                for _ in 0..delta.unsigned_abs() {
+                    self.chewing_editor
+                        .process_keyevent(KeyboardEvent::builder().ksym(ksym).build());
+                }
+            }
+        }
+
+        self.update_after_key(&context)
     }
 
     pub(super) fn on_test_keyup(
@@ -803,50 +901,36 @@ impl ChewingTextService {
         ev: SystemKeyboardEvent,
     ) -> Result<bool> {
         let evt = ev.to_keyboard_event(self.cfg.chewing_tsf.simulate_english_layout);
-        let last_is_shift = evt.ksym == SYM_LEFTSHIFT || evt.ksym == SYM_RIGHTSHIFT;
         let last_is_capslock = evt.ksym == SYM_CAPSLOCK;
 
-        debug!(last_is_shift, last_is_capslock; "");
+        debug!(last_is_capslock; "");
 
-        if last_is_shift
-            && self.shift_key_state.release().is_some_and(|duration| {
-                duration < Duration::from_millis(self.cfg.chewing_tsf.shift_key_sensitivity as u64)
-            })
-            && self.cfg.chewing_tsf.switch_lang_with_shift
+        if let Some(action) = self
+            .keybindings
+            .key_up(self.input_state(), evt.ksym, Instant::now())
         {
-            // TODO: simplify this
-            if self.cfg.chewing_tsf.enable_caps_lock {
-                // Locked by CapsLock
-                let msg = match self.lang_mode.get() {
-                    TsfLangMode::English => HSTRING::from("CapsLock 鎖定英數模式"),
-                    TsfLangMode::Chinese => HSTRING::from("CapsLock 鎖定中文模式"),
-                    _ => HSTRING::from("輸入法關閉中"), // unreachable
-                };
-                if self.cfg.chewing_tsf.show_notification {
-                    self.show_message(context, &msg)?;
-                }
-            } else {
-                self.toggle_lang_mode()?;
-                let msg = match self.lang_mode.get() {
-                    TsfLangMode::English => HSTRING::from("英數模式"),
-                    TsfLangMode::Chinese => HSTRING::from("中文模式"),
-                    _ => HSTRING::from("輸入法關閉中"), // unreachable
-                };
-                if self.cfg.chewing_tsf.show_notification {
-                    self.show_message(context, &msg)?;
-                }
-            }
+            debug!("tapped keybinding on action={action}");
+            let mut actions = KeyActions {
+                service: self,
+                context,
+            };
+            dispatch(&mut actions, &action)?;
         }
 
-        if self.cfg.chewing_tsf.enable_caps_lock && last_is_capslock {
+        if self.lang_toggle.key_up(evt.ksym, Instant::now()) {
+            self.switch_lang_with_key(context)?;
+        }
+
+        if self.caps_lock_locks_lang() && last_is_capslock {
             self.sync_lang_mode(true)?;
+            self.show_mode_indicator();
             let msg = match self.lang_mode.get() {
                 TsfLangMode::English => HSTRING::from("英數模式"),
                 TsfLangMode::Chinese => HSTRING::from("中文模式"),
                 _ => HSTRING::from("輸入法關閉中"), // unreachable
             };
-            if self.cfg.chewing_tsf.show_notification {
-                self.show_message(context, &msg)?;
+            if self.cfg.chewing_tsf.show_notification && !self.cfg.chewing_tsf.show_mode_indicator {
+                self.show_message(context, &msg, NotificationKind::ModeChange)?;
             }
         }
 
@@ -857,6 +941,40 @@ impl ChewingTextService {
         Ok(false)
     }
 
+    /// Switches the language after the toggle key, unless CapsLock locks it.
+    fn switch_lang_with_key(&mut self, context: &ITfContext) -> Result<()> {
+        // TODO: simplify this
+        if self.caps_lock_locks_lang() {
+            // Locked by CapsLock
+            let msg = match self.lang_mode.get() {
+                TsfLangMode::English => HSTRING::from("CapsLock 鎖定英數模式"),
+                TsfLangMode::Chinese => HSTRING::from("CapsLock 鎖定中文模式"),
+                _ => HSTRING::from("輸入法關閉中"), // unreachable
+            };
+            if self.cfg.chewing_tsf.show_notification {
+                self.show_message(context, &msg, NotificationKind::ModeChange)?;
+            }
+        } else {
+            self.toggle_lang_mode()?;
+            let msg = match self.lang_mode.get() {
+                TsfLangMode::English => HSTRING::from("英數模式"),
+                TsfLangMode::Chinese => HSTRING::from("中文模式"),
+                _ => HSTRING::from("輸入法關閉中"), // unreachable
+            };
+            // The mode indicator already shows the new mode
+            if self.cfg.chewing_tsf.show_notification && !self.cfg.chewing_tsf.show_mode_indicator {
+                self.show_message(context, &msg, NotificationKind::ModeChange)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the CapsLock state decides the language. CapsLock as the
+    /// toggle key only switches it.
+    fn caps_lock_locks_lang(&self) -> bool {
+        self.cfg.chewing_tsf.enable_caps_lock && !self.lang_toggle.uses_caps_lock()
+    }
+
     fn toggle_keyboard_openclose(&self) {
         self.lang_mode.update(|mode| match mode {
             TsfLangMode::Chinese => TsfLangMode::DisabledChinese,
@@ -946,8 +1064,7 @@ impl ChewingTextService {
             self.sync_lang_mode(false)?;
             if self.is_composing() && self.lang_mode.get().is_disabled() {
                 self.chewing_editor.commit()?;
-                let commit = self.chewing_editor.display_commit().to_owned();
-                self.chewing_editor.ack();
+                let commit = self.take_commit();
                 debug!(commit; "commit string");
                 unsafe {
                     let doc_mgr = self
@@ -1019,6 +1136,16 @@ impl ChewingTextService {
                     }
                 }
                 ID_CHECK_NEW_VER => open_url(&self.cfg.chewing_tsf.update_info_url),
+                ID_SKIP_UPDATE => {
+                    if let Err(error) = self.dismiss_update(None) {
+                        error!("unable to skip update: {error}");
+                    }
+                }
+                ID_SNOOZE_UPDATE => {
+                    if let Err(error) = self.dismiss_update(Some(UPDATE_SNOOZE_DAYS)) {
+                        error!("unable to snooze update: {error}");
+                    }
+                }
                 ID_ABOUT => open_url("chewing-preferences://about"),
                 ID_WEBSITE => open_url("https://chewing.im/"),
                 ID_GROUP => open_url("https://groups.google.com/group/chewing-devel"),
@@ -1111,6 +1238,9 @@ impl ChewingTextService {
     }
 
     fn end_composition(&mut self, context: &ITfContext) -> Result<()> {
+        if let Some(composition_window) = self.composition_window.take() {
+            composition_window.hide()?;
+        }
         // Transfer the ownership of this composition to the EndComposition edit session.
         let composition = mem::replace(&mut self.composition, Rc::new(RefCell::new(None)));
         self.pending_edit = Weak::new();
@@ -1133,16 +1263,11 @@ impl ChewingTextService {
         cursor: usize,
     ) -> Result<()> {
         debug!(commit, preedit; "set composition string");
-        let commit = if self.output_simp_chinese {
-            zhconv(commit, Variant::ZhHans).into()
-        } else {
-            commit.into()
-        };
-        let preedit = if self.output_simp_chinese {
-            zhconv(preedit, Variant::ZhHans).into()
-        } else {
-            preedit.into()
-        };
+        let commit = self.convert_script(commit);
+        let preedit = self.convert_script(preedit);
+        if self.floating_composition {
+            return self.set_floating_composition(context, &commit, &preedit, segments, cursor);
+        }
         if let Some(cell) = self.pending_edit.upgrade() {
             debug!(cursor, preedit:%; "Reuse existing edit session");
             cell.replace(Some(CompositionString {
@@ -1177,6 +1302,72 @@ impl ChewingTextService {
         Ok(())
     }
 
+    /// Only inserts the commit string into the document and shows the
+    /// preedit in chewing_tip_host.
+    fn set_floating_composition(
+        &mut self,
+        context: &ITfContext,
+        commit: &str,
+        preedit: &str,
+        segments: Vec<(usize, usize)>,
+        cursor: usize,
+    ) -> Result<()> {
+        if !commit.is_empty() {
+            self.insert_text(context, commit)?;
+        }
+        if preedit.is_empty() {
+            if let Some(composition_window) = self.composition_window.take() {
+                composition_window.hide()?;
+            }
+            return Ok(());
+        }
+        // Bopomofo is always inserted at the cursor
+        let bopomofo_len = self
+            .chewing_editor
+            .syllable_buffer_display()
+            .chars()
+            .count();
+        let rect = self.get_selection_rect(context).unwrap_or_default();
+        let colors = self.current_theme().candidate;
+        let cfg = &self.cfg.chewing_tsf;
+        let model = ShowComposition {
+            position: Position {
+                x: rect.left,
+                y: rect.bottom,
+            },
+            preedit: preedit.to_string(),
+            segments,
+            bopomofo: (bopomofo_len > 0).then_some((cursor, cursor + bopomofo_len)),
+            cursor,
+            font_family: cfg.font_family.clone(),
+            font_size: cfg.font_size as f32,
+            fg_color: colors.fg_color,
+            bg_color: colors.bg_color,
+            highlight_bg_color: colors.highlight_bg_color,
+            border_color: colors.border_color,
+            caret: Some(screen_rect(&rect)),
+            font_fallback: cfg.font_fallback_families(),
+            locale: self.text_locale().to_string(),
+        };
+        self.composition_window
+            .get_or_insert_with(|| CompositionWindow::new(self.ipc_client.clone()))
+            .show(&model)
+    }
+
+    /// Han characters are drawn with the glyphs of the output variant.
+    fn text_locale(&self) -> &'static str {
+        self.output_variant.locale()
+    }
+
+    /// Converts editor text to the output variant.
+    fn convert_script(&self, text: &str) -> String {
+        let Some(variant) = zhconv_variant(self.output_variant) else {
+            return text.to_string();
+        };
+        self.script_overrides
+            .convert(self.output_variant, text, |text| zhconv(text, variant))
+    }
+
     fn get_selection_rect(&self, context: &ITfContext) -> Result<RECT> {
         let session = SelectionRect::new(context.clone()).into_object();
         request_edit_session(
@@ -1192,20 +1383,41 @@ impl ChewingTextService {
         &mut self,
         context: &ITfContext,
         text: &HSTRING,
+        kind: NotificationKind,
     ) -> Result<(), error_plus::Error> {
         expect_error("Failed to show message", || {
             let rect = self.get_selection_rect(context).unwrap_or_default();
-            let call = ShowNotification {
-                position: Position {
+            let theme = self.current_theme();
+            let duration_ms = match kind {
+                NotificationKind::ModeChange
+                | NotificationKind::ModeIndicator
+                | NotificationKind::Info => {
+                    Some(self.cfg.chewing_tsf.notification_duration.max(0) as u32)
+                }
+                _ => None,
+            };
+            let position = match kind {
+                // Right next to the caret
+                NotificationKind::ModeIndicator => Position {
+                    x: rect.right + 4,
+                    y: rect.top,
+                },
+                _ => Position {
                     x: rect.left + 50,
                     y: rect.bottom + 50,
                 },
+            };
+            let call = ShowNotification {
+                kind,
+                duration_ms,
+                icon: String::new(),
+                position,
                 text: text.to_string_lossy(),
                 font_family: self.cfg.chewing_tsf.font_family.clone(),
                 font_size: self.cfg.chewing_tsf.font_size as f32,
-                fg_color: self.cfg.chewing_tsf.notify_fg_color.clone(),
-                bg_color: self.cfg.chewing_tsf.notify_bg_color.clone(),
-                border_color: self.cfg.chewing_tsf.notify_border_color.clone(),
+                fg_color: theme.notification.fg_color,
+                bg_color: theme.notification.bg_color,
+                border_color: theme.notification.border_color,
             };
             let cth_client = self.ipc_client.clone();
             let notification = Notification::new(self.thread_mgr.clone(), cth_client, call)?;
@@ -1214,6 +1426,35 @@ impl ChewingTextService {
         })
     }
 
+    /// Shows the input mode near the caret of the focused document.
+    fn show_mode_indicator(&mut self) {
+        if !self.cfg.chewing_tsf.show_mode_indicator
+            || !self.has_focus
+            || self.lang_mode.get().is_disabled()
+        {
+            return;
+        }
+        let Ok(context) = (unsafe { self.thread_mgr.GetFocus().and_then(|dm| dm.GetTop()) }) else {
+            return;
+        };
+        if !self.is_context_mutable(&context).unwrap_or(false) {
+            return;
+        }
+        let text = mode_indicator_text(
+            self.lang_mode.get() == TsfLangMode::Chinese,
+            self.chewing_editor.editor_options().character_form == CharacterForm::Fullwidth,
+            self.output_variant,
+        );
+        if !self.mode_indicator.should_show(Instant::now(), &text) {
+            return;
+        }
+        if let Err(error) =
+            self.show_message(&context, &text.into(), NotificationKind::ModeIndicator)
+        {
+            error!("{}", error.error_report());
+        }
+    }
+
     fn hide_message(&mut self) {
         if let Some(notification) = self.notification.take() {
             notification.end_ui_element();
@@ -1236,62 +1477,198 @@ impl ChewingTextService {
             }
 
             let editor = &self.chewing_editor;
+            let n = editor.editor_options().candidates_per_page;
+            let total_page = editor.total_page()? as u32;
+            let current_page = editor.current_page_no()? as u32 + 1;
+            let mut items = editor.paginated_candidates()?;
+            if total_page == 0 {
+                // TODO: handle this properly in chewing-rs
+                self.chewing_editor.cancel_selecting()?;
+                self.hide_candidates();
+                return Ok(());
+            }
+            items.truncate(n);
+            let shown = ConvertedCandidates::new(&items, |item| self.convert_script(item));
+            let annotations = if self.cfg.chewing_tsf.show_cand_annotations {
+                let sources: Vec<String> =
+                    shown.sources().iter().map(|&i| items[i].clone()).collect();
+                self.candidate_annotations(&sources)
+            } else {
+                vec![]
+            };
             if let Some(candidate_list) = &self.candidate_list {
                 let cfg = &self.cfg.chewing_tsf;
                 let sel_keys = SEL_KEYS[cfg.sel_key_type as usize];
-                let n = editor.editor_options().candidates_per_page;
-                let total_page = editor.total_page()? as u32;
-                let current_page = editor.current_page_no()? as u32 + 1;
-                let mut items = editor.paginated_candidates()?;
-                if total_page == 0 {
-                    // TODO: handle this properly in chewing-rs
-                    self.chewing_editor.cancel_selecting()?;
-                    self.hide_candidates();
-                    return Ok(());
-                }
-                items.truncate(n);
                 let rect = self.get_selection_rect(context).unwrap_or_default();
+                let colors = self.current_theme().candidate;
+                let title = if cfg.show_cand_title {
+                    // Candidates are listed from the longest phrase
+                    let phrase_len = items.iter().map(|s| s.chars().count()).max();
+                    self.selected_syllables(phrase_len.unwrap_or(1))
+                        .iter()
+                        .map(|syllable| syllable.to_string())
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                } else {
+                    String::new()
+                };
                 candidate_list.set_model(ShowCandidateList {
                     position: Position {
                         x: rect.left,
                         y: rect.bottom,
                     },
-                    items,
+                    items: shown.items.clone(),
+                    annotations,
                     selkeys: sel_keys.chars().take(n).map(|k| k as u16).collect(),
                     cand_per_row: cfg.cand_per_row as u32,
                     total_page,
                     current_page,
                     font_family: cfg.font_family.clone(),
                     font_size: cfg.font_size as f32,
-                    fg_color: cfg.font_fg_color.clone(),
-                    bg_color: cfg.font_bg_color.clone(),
-                    highlight_fg_color: cfg.font_highlight_fg_color.clone(),
-                    highlight_bg_color: cfg.font_highlight_bg_color.clone(),
-                    border_color: cfg.cand_list_border_color.clone(),
-                    selkey_color: cfg.font_number_fg_color.clone(),
+                    fg_color: colors.fg_color,
+                    bg_color: colors.bg_color,
+                    highlight_fg_color: colors.highlight_fg_color,
+                    highlight_bg_color: colors.highlight_bg_color,
+                    border_color: colors.border_color,
+                    selkey_color: colors.selkey_color,
+                    page_indicator_color: colors.page_indicator_color,
+                    layout: cfg.cand_list_layout.into(),
+                    show_page_arrows: cfg.show_page_arrows,
+                    title,
+                    compact: cfg.compact_cand_list,
                     use_cursor: cfg.cursor_cand_list,
                     current_sel: 0,
+                    session: self.candidate_events.session(),
+                    caret: Some(screen_rect(&rect)),
+                    font_fallback: cfg.font_fallback_families(),
+                    locale: self.text_locale().to_string(),
                 });
                 candidate_list.show()?;
             }
+            self.shown_candidates = shown;
 
             Ok(())
         })
     }
 
+    /// Readings, user phrase markers and symbol glosses of candidates.
+    fn candidate_annotations(&mut self, items: &[String]) -> Vec<CandidateAnnotation> {
+        let mut annotations: Vec<CandidateAnnotation> = items
+            .iter()
+            .map(|item| CandidateAnnotation {
+                gloss: symbol_gloss(item).unwrap_or_default(),
+                ..Default::default()
+            })
+            .collect();
+        let phrase_len = items.iter().map(|s| s.chars().count()).max();
+        for len in 1..=phrase_len.unwrap_or(0) {
+            let syllables = self.selected_syllables(len);
+            if syllables.len() != len {
+                continue;
+            }
+            let reading = syllables
+                .iter()
+                .map(|syllable| syllable.to_string())
+                .collect::<Vec<_>>()
+                .join(" ");
+            let user_phrases = self
+                .chewing_editor
+                .user_dict()
+                .lookup(&syllables, LookupStrategy::Standard);
+            for (item, annotation) in items.iter().zip(annotations.iter_mut()) {
+                if item.chars().count() != len {
+                    continue;
+                }
+                annotation.reading = reading.clone();
+                if let Some(phrase) = user_phrases.iter().find(|p| p.as_str() == item) {
+                    annotation.user_phrase = true;
+                    annotation.frequency = Some(phrase.freq());
+                }
+            }
+        }
+        // Trailing empty annotations need not be sent
+        while annotations.last().is_some_and(|a| a.is_empty()) {
+            annotations.pop();
+        }
+        annotations
+    }
+
+    /// The candidate under the cursor of the candidate list, as produced by
+    /// the editor before converting it to the output variant.
+    fn selected_candidate(&self) -> Option<String> {
+        if !self.chewing_editor.is_selecting() || !self.cfg.chewing_tsf.cursor_cand_list {
+            return None;
+        }
+        let sel = self.candidate_list.as_ref()?.current_sel();
+        let index = self.shown_candidates.source_index(sel)?;
+        self.chewing_editor
+            .paginated_candidates()
+            .ok()?
+            .into_iter()
+            .nth(index)
+    }
+
+    /// Syllables under a phrase of `phrase_len` being selected.
+    fn selected_syllables(&self, phrase_len: usize) -> Vec<Syllable> {
+        // TODO: expose begin and end from selector
+        let cursor = if self.cfg.chewing_tsf.phrase_choice_rearward {
+            self.chewing_editor
+                .cursor()
+                .saturating_sub(phrase_len.saturating_sub(1))
+        } else {
+            self.chewing_editor.cursor()
+        };
+        self.chewing_editor
+            .symbols()
+            .iter()
+            .skip(cursor)
+            .take(phrase_len)
+            .map_while(|s| s.to_syllable())
+            .collect()
+    }
+
+    /// Colors of the candidate list and notification windows.
+    ///
+    /// Resolved every time so the auto theme follows system theme changes.
+    /// The detected system theme itself is cached until Windows announces a
+    /// change.
+    fn current_theme(&self) -> Theme {
+        match &self.theme {
+            ThemeChoice::Custom => Theme::from_config(&self.cfg.chewing_tsf),
+            ThemeChoice::Auto => ThemeDetector::builtin_theme().theme(),
+            ThemeChoice::Builtin(builtin) => builtin.theme(),
+            ThemeChoice::File(_) => self
+                .user_theme
+                .clone()
+                .unwrap_or_else(|| Theme::from_config(&self.cfg.chewing_tsf)),
+        }
+    }
+
     fn hide_candidates(&mut self) {
         if let Some(candidate_list) = self.candidate_list.take() {
             candidate_list.end_ui_element();
         }
+        self.shown_candidates = ConvertedCandidates::default();
     }
 
     fn toggle_simp_chinese(&mut self) -> Result<()> {
-        self.output_simp_chinese = !self.output_simp_chinese;
-        debug!(
-            "toggle output simplified chinese: {}",
-            self.output_simp_chinese
-        );
-        let check_flag = if self.output_simp_chinese {
+        let variant = self
+            .output_variant
+            .toggle_simplified(self.configured_variant());
+        self.set_output_variant(variant)
+    }
+
+    fn set_output_variant(&mut self, variant: ScriptVariant) -> Result<()> {
+        self.output_variant = variant;
+        debug!("set output variant: {variant}");
+        self.check_simp_chinese_menu();
+        self.update_lang_buttons()?;
+        self.show_mode_indicator();
+        Ok(())
+    }
+
+    fn check_simp_chinese_menu(&self) {
+        let check_flag = if self.output_variant.is_simplified() {
             MF_CHECKED
         } else {
             MF_UNCHECKED
@@ -1299,8 +1676,13 @@ impl ChewingTextService {
         unsafe {
             CheckMenuItem(self.popup_menu, ID_OUTPUT_SIMP_CHINESE, check_flag.0);
         }
-        self.update_lang_buttons()?;
-        Ok(())
+    }
+
+    fn configured_variant(&self) -> ScriptVariant {
+        ScriptVariant::from_config(&self.cfg.chewing_tsf).unwrap_or_else(|error| {
+            error!("{}", error.error_report());
+            ScriptVariant::default()
+        })
     }
 
     fn toggle_shape_mode(&mut self) -> Result<()> {
@@ -1318,32 +1700,18 @@ impl ChewingTextService {
             CheckMenuItem(self.popup_menu, ID_SWITCH_SHAPE, check_flag.0);
         }
         self.update_lang_buttons()?;
+        self.show_mode_indicator();
 
         Ok(())
     }
 
-    fn toggle_hsu_keyboard(&mut self, context: &ITfContext) -> Result<()> {
-        if self.kbtype == KeyboardLayoutCompat::Hsu {
-            self.kbtype = KeyboardLayoutCompat::Default;
-            self.chewing_editor
-                .set_syllable_editor(syl_editor_from_kbtype(KeyboardLayoutCompat::Default));
-            self.show_message(context, &HSTRING::from("標準鍵盤"))?;
-        } else {
-            self.kbtype = KeyboardLayoutCompat::Hsu;
-            self.chewing_editor
-                .set_syllable_editor(syl_editor_from_kbtype(KeyboardLayoutCompat::Hsu));
-            self.show_message(context, &HSTRING::from("許氏鍵盤"))?;
-        }
-        Ok(())
-    }
-
     fn sync_lang_mode(&self, internal: bool) -> Result<()> {
         debug!("set pending_lang_mode_change to {internal}");
         self.pending_lang_mode_change.set(internal);
         if !self.lang_mode.get().is_disabled() {
             let cfg = &self.cfg.chewing_tsf;
             let evt = SystemKeyboardEvent::default().to_keyboard_event(cfg.simulate_english_layout);
-            if cfg.enable_caps_lock {
+            if self.caps_lock_locks_lang() {
                 let (locked_mode, unlocked_mode) = if cfg.lock_chinese_on_caps_lock {
                     (TsfLangMode::Chinese, TsfLangMode::English)
                 } else {
@@ -1397,13 +1765,26 @@ impl ChewingTextService {
                     .GetTop()
                     .context("failed to get current ITfContext")?;
                 self.chewing_editor.clear_syllable_editor();
+                self.forget_raw_keys();
                 self.update_preedit(&context, String::new())?;
             }
+            self.show_mode_indicator();
         }
 
         Ok(())
     }
 
+    /// Stops offering the available update, for good or for `snooze_days`.
+    fn dismiss_update(&mut self, snooze_days: Option<u32>) -> Result<()> {
+        match snooze_days {
+            Some(days) => self.ipc_client.snooze_update(days)?,
+            None => self.ipc_client.skip_update()?,
+        }
+        // The host clears the update URL, no need to wait for the reload
+        self.cfg.chewing_tsf.update_info_url.clear();
+        self.update_lang_buttons()
+    }
+
     fn get_lang_icon_id(&self) -> u32 {
         let mut icon_id = match (ThemeDetector::detect_theme(), self.lang_mode.get()) {
             (WindowsTheme::Light, TsfLangMode::Chinese) => IDI_CHI,
@@ -1412,7 +1793,7 @@ impl ChewingTextService {
             (WindowsTheme::Dark, TsfLangMode::English) => IDI_ENG_DARK,
             _ => IDI_CHI,
         };
-        if self.output_simp_chinese {
+        if self.output_variant.is_simplified() {
             icon_id = match icon_id {
                 IDI_CHI => IDI_SIMP,
                 IDI_CHI_DARK => IDI_SIMP_DARK,
@@ -1433,7 +1814,65 @@ impl ChewingTextService {
 
     fn is_composing(&self) -> bool {
         // when candidate window is shown we are composing even without a composition
-        self.composition.borrow().is_some() || self.candidate_list.is_some()
+        self.composition.borrow().is_some()
+            || self.composition_window.is_some()
+            || self.candidate_list.is_some()
+    }
+
+    fn cursor_at_end(&self) -> bool {
+        let len = self
+            .chewing_editor
+            .intervals()
+            .last()
+            .map_or(0, |it| it.end);
+        self.chewing_editor.cursor() == len
+    }
+
+    /// Stops tracking the typed keys after an edit they cannot follow.
+    fn forget_raw_keys(&mut self) {
+        self.raw_keys = None;
+        self.rejected_keys.clear();
+    }
+
+    /// Whether Backspace erases the last typed key, which is only the case
+    /// while an unfinished syllable is at the end of the buffer.
+    fn backspace_erases_last_key(&self) -> bool {
+        let Some(raw_keys) = &self.raw_keys else {
+            return false;
+        };
+        let count = raw_keys.chars().count();
+        // A rejected key never reached the editor
+        count > 0
+            && self.rejected_keys.last() != Some(&(count - 1))
+            && !self.chewing_editor.is_selecting()
+            && !self.chewing_editor.syllable_buffer_display().is_empty()
+            && self.cursor_at_end()
+    }
+
+    /// Takes the text committed by the editor, remembering it for `{last}`
+    /// in text templates.
+    fn take_commit(&mut self) -> String {
+        let commit = self.chewing_editor.display_commit().to_owned();
+        self.chewing_editor.ack();
+        if !commit.is_empty() {
+            self.last_commit.clone_from(&commit);
+        }
+        commit
+    }
+
+    /// The state keybindings are scoped to.
+    fn input_state(&self) -> InputState {
+        let edit = if self.chewing_editor.is_selecting() {
+            EditState::Selecting
+        } else if self.is_composing() {
+            EditState::Composing
+        } else {
+            EditState::Idle
+        };
+        InputState {
+            edit,
+            english: self.lang_mode.get() == TsfLangMode::English,
+        }
     }
 
     fn init_chewing_context(&mut self) -> Result<()> {
@@ -1515,16 +1954,8 @@ impl ChewingTextService {
 
     /// Initializes the config to the user default
     fn apply_init_config(&mut self) -> Result<()> {
-        let cfg = &self.cfg.chewing_tsf;
-        self.output_simp_chinese = cfg.output_simp_chinese;
-        let check_flag = if self.output_simp_chinese {
-            MF_CHECKED
-        } else {
-            MF_UNCHECKED
-        };
-        unsafe {
-            CheckMenuItem(self.popup_menu, ID_OUTPUT_SIMP_CHINESE, check_flag.0);
-        }
+        self.output_variant = self.configured_variant();
+        self.check_simp_chinese_menu();
         self.chewing_editor.set_editor_options(|opt| {
             if self.cfg.chewing_tsf.default_full_space {
                 opt.character_form = CharacterForm::Fullwidth;
@@ -1547,13 +1978,50 @@ impl ChewingTextService {
         self.kbtype = KeyboardLayoutCompat::try_from(cfg.keyboard_layout as u8)
             .unwrap_or(KeyboardLayoutCompat::Default);
         self.chewing_editor = Self::build_editor_from_cfg(cfg)?;
+        // Reload with the editor to pick up new dictionary files
+        self.reading_dicts = None;
         let _ = self.update_lang_buttons();
-        let keybindings = cfg
-            .keybind
-            .iter()
-            .filter_map(|kb| Keybinding::try_from(kb).ok())
-            .collect();
-        self.keybindings = keybindings;
+        let (keybindings, issues) = parse_keybindings(cfg);
+        for issue in issues {
+            error!("{issue}");
+        }
+        self.keybindings = KeybindingMatcher::new(
+            keybindings,
+            Duration::from_millis(cfg.shift_key_sensitivity as u64),
+        );
+        self.lang_toggle = LangToggle::from_config(cfg).unwrap_or_else(|error| {
+            error!("{}", error.error_report());
+            LangToggle::default()
+        });
+        self.script_overrides = ScriptOverrides::load().unwrap_or_else(|error| {
+            error!("{}", error.error_report());
+            ScriptOverrides::default()
+        });
+        self.english_words = if cfg.detect_english {
+            EnglishWords::load().unwrap_or_else(|error| {
+                error!("{}", error.error_report());
+                EnglishWords::bundled()
+            })
+        } else {
+            EnglishWords::default()
+        };
+        self.english_convert_key =
+            parse_convert_key(&cfg.english_convert_key).unwrap_or_else(|error| {
+                error!("{}", error.error_report());
+                None
+            });
+        self.floating_composition = cfg.use_floating_composition(&exe_name().unwrap_or_default());
+        self.theme = ThemeChoice::from_str(&cfg.theme).unwrap_or_else(|error| {
+            error!("{}", error.error_report());
+            ThemeChoice::Custom
+        });
+        // Theme files are only read when the config changes
+        self.user_theme = match &self.theme {
+            ThemeChoice::File(name) => Theme::load(name)
+                .inspect_err(|error| error!("{}", error.error_report()))
+                .ok(),
+            _ => None,
+        };
         Ok(())
     }
 
@@ -1586,15 +2054,14 @@ impl ChewingTextService {
                 },
             );
         }
-        unsafe {
-            let _ = EnableMenuItem(
-                self.popup_menu,
-                ID_CHECK_NEW_VER,
-                match self.cfg.chewing_tsf.update_info_url.as_str() {
-                    "" => MF_GRAYED,
-                    _ => MF_ENABLED,
-                },
-            );
+        let update_flag = match self.cfg.chewing_tsf.update_info_url.as_str() {
+            "" => MF_GRAYED,
+            _ => MF_ENABLED,
+        };
+        for id in [ID_CHECK_NEW_VER, ID_SKIP_UPDATE, ID_SNOOZE_UPDATE] {
+            unsafe {
+                let _ = EnableMenuItem(self.popup_menu, id, update_flag);
+            }
         }
         Ok(())
     }
@@ -1610,10 +2077,16 @@ impl ChewingTextService {
     }
 
     fn map_sel_key(&self, mut evt: KeyboardEvent) -> KeyboardEvent {
-        if let Some(idx) = SEL_KEYS[self.cfg.chewing_tsf.sel_key_type as usize]
+        if let Some(sel) = SEL_KEYS[self.cfg.chewing_tsf.sel_key_type as usize]
             .chars()
             .position(|it| it == evt.ksym.to_unicode())
         {
+            // Shown candidates skip the ones converted to the same text. Keys
+            // past them stay past the candidates of the editor.
+            let idx = self
+                .shown_candidates
+                .source_index(sel)
+                .unwrap_or(sel + self.shown_candidates.hidden());
             match idx {
                 0..9 => {
                     evt.code = Keycode(keycode::KEY_1.0 + idx as u8);
@@ -1682,6 +2155,181 @@ impl<'a> ReentrantOps<'a> {
     }
 }
 
+/// Runs keybinding actions against the text service and the focused context.
+struct KeyActions<'a> {
+    service: &'a mut ChewingTextService,
+    context: &'a ITfContext,
+}
+
+impl ActionHandler for KeyActions<'_> {
+    type Error = anyhow::Error;
+
+    fn toggle_language(&mut self) -> Result<()> {
+        self.service.toggle_lang_mode()?;
+        let cfg = &self.service.cfg.chewing_tsf;
+        // The mode indicator already shows the new mode
+        if cfg.show_notification && !cfg.show_mode_indicator {
+            let msg = match self.service.lang_mode.get() {
+                TsfLangMode::English => HSTRING::from("英數模式"),
+                _ => HSTRING::from("中文模式"),
+            };
+            self.service
+                .show_message(self.context, &msg, NotificationKind::ModeChange)?;
+        }
+        Ok(())
+    }
+    fn toggle_shape(&mut self) -> Result<()> {
+        self.service.toggle_shape_mode()
+    }
+    fn toggle_simplified_chinese(&mut self) -> Result<()> {
+        self.service.toggle_simp_chinese()
+    }
+    fn set_output_variant(&mut self, variant: ScriptVariant) -> Result<()> {
+        self.service.set_output_variant(variant)?;
+        let cfg = &self.service.cfg.chewing_tsf;
+        // The mode indicator already shows the new variant
+        if cfg.show_notification && !cfg.show_mode_indicator {
+            self.service.show_message(
+                self.context,
+                &HSTRING::from(variant.label()),
+                NotificationKind::ModeChange,
+            )?;
+        }
+        Ok(())
+    }
+    fn output_variant(&self) -> ScriptVariant {
+        self.service.output_variant
+    }
+    fn set_keyboard_layout(&mut self, layout: KeyboardLayoutCompat) -> Result<()> {
+        self.service.kbtype = layout;
+        self.service
+            .chewing_editor
+            .set_syllable_editor(syl_editor_from_kbtype(layout));
+        self.service.show_message(
+            self.context,
+            &HSTRING::from(keyboard_layout_name(layout)),
+            NotificationKind::ModeChange,
+        )?;
+        Ok(())
+    }
+    fn keyboard_layout(&self) -> KeyboardLayoutCompat {
+        self.service.kbtype
+    }
+    fn toggle_auto_learn(&mut self) -> Result<()> {
+        let editor = &mut self.service.chewing_editor;
+        editor.set_editor_options(|opt| {
+            opt.disable_auto_learn_phrase = !opt.disable_auto_learn_phrase;
+        });
+        let msg = if editor.editor_options().disable_auto_learn_phrase {
+            "停用自動學習"
+        } else {
+            "啟用自動學習"
+        };
+        self.service.show_message(
+            self.context,
+            &HSTRING::from(msg),
+            NotificationKind::ModeChange,
+        )?;
+        Ok(())
+    }
+    fn commit_raw_keys(&mut self) -> Result<()> {
+        let Some(keys) = self.service.raw_keys.replace(String::new()) else {
+            // The buffer was edited since, commit it as shown instead
+            return self.commit_text("");
+        };
+        self.service.chewing_editor.clear();
+        self.service.hide_candidates();
+        if self.service.is_composing() {
+            self.service.update_preedit(self.context, keys)?;
+        } else if !keys.is_empty() {
+            self.service.insert_text(self.context, &keys)?;
+        }
+        Ok(())
+    }
+    fn clear_buffer(&mut self) -> Result<()> {
+        self.service.raw_keys = Some(String::new());
+        self.service.chewing_editor.clear();
+        self.service.hide_candidates();
+        self.service.update_preedit(self.context, String::new())
+    }
+    fn commit_text(&mut self, text: &str) -> Result<()> {
+        let mut commit = String::new();
+        if !self.service.chewing_editor.is_empty() {
+            self.service.chewing_editor.commit()?;
+            commit = self.service.take_commit();
+        }
+        commit.push_str(text);
+        self.service.raw_keys = Some(String::new());
+        if self.service.is_composing() {
+            self.service.hide_candidates();
+            self.service.update_preedit(self.context, commit)?;
+        } else {
+            self.service.insert_text(self.context, &commit)?;
+        }
+        Ok(())
+    }
+    fn last_commit(&self) -> &str {
+        &self.service.last_commit
+    }
+    fn unlearn_selected_phrase(&mut self) -> Result<bool> {
+        let service = &mut *self.service;
+        let Some(phrase) = service.selected_candidate() else {
+            return Ok(false);
+        };
+        let phrase_len = phrase.chars().count();
+        let syllables = service.selected_syllables(phrase_len);
+        if syllables.len() != phrase_len {
+            return Ok(false);
+        }
+        let (msg, kind) = match service.chewing_editor.unlearn_phrase(&syllables, &phrase) {
+            Ok(_) => (format!("刪除：{phrase}"), NotificationKind::Info),
+            Err(error) => {
+                error!("failed to unlearn phrase: {error}");
+                (format!("無法刪除：{phrase}"), NotificationKind::Error)
+            }
+        };
+        service.update_candidates(self.context)?;
+        // TODO: move this to editor
+        service.show_message(self.context, &msg.into(), kind)?;
+        Ok(true)
+    }
+    fn show_readings(&mut self) -> Result<bool> {
+        let service = &mut *self.service;
+        let text = service
+            .selected_candidate()
+            .unwrap_or_else(|| service.last_commit.clone());
+        if text.is_empty() {
+            return Ok(false);
+        }
+        let dicts = match &mut service.reading_dicts {
+            Some(dicts) => dicts,
+            slot @ None => slot.insert(load_dictionaries()?),
+        };
+        let readings = dicts.lookup(Some(&*service.chewing_editor.user_dict()), &text);
+        let msg = format_readings(&text, &readings);
+        service.show_message(self.context, &msg.into(), NotificationKind::Info)?;
+        Ok(true)
+    }
+}
+
+fn screen_rect(rect: &RECT) -> ScreenRect {
+    ScreenRect {
+        left: rect.left,
+        top: rect.top,
+        right: rect.right,
+        bottom: rect.bottom,
+    }
+}
+
+fn zhconv_variant(variant: ScriptVariant) -> Option<Variant> {
+    match variant {
+        ScriptVariant::Traditional => None,
+        ScriptVariant::Simplified => Some(Variant::ZhCN),
+        ScriptVariant::HongKong => Some(Variant::ZhHK),
+        ScriptVariant::SimplifiedTaiwan => Some(Variant::ZhHans),
+    }
+}
+
 fn syl_editor_from_kbtype(kbtype: KeyboardLayoutCompat) -> Box<dyn SyllableEditor> {
     use zhuyin_layout::*;
     match kbtype {
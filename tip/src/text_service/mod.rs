@@ -6,6 +6,7 @@ use std::{
     ptr::null_mut,
 };
 
+use chewing_tip_core::ipc::values::CandidateEvent;
 use error_plus::{ErrorExt, expect_error, impl_context_error};
 use log::{debug, error};
 use windows::Win32::{
@@ -27,12 +28,15 @@ use self::chewing::ChewingTextService;
 use self::display_attribute::{EnumTfDisplayAttributeInfo, get_display_attribute_info};
 use self::key_event::SystemKeyboardEvent;
 
+mod annotation;
+mod candidate_events;
 mod chewing;
 mod display_attribute;
 mod edit_session;
 mod key_event;
 mod lang_bar;
 mod menu;
+mod mode_indicator;
 mod resources;
 mod theme;
 mod ui_elements;
@@ -55,6 +59,7 @@ pub(super) enum CommandType {
 #[interface("f320f835-b95d-4d3f-89d5-fd4ab7b9d7bb")]
 pub(super) unsafe trait IFnRunCommand: IUnknown {
     fn on_command(&self, id: u32, cmd_type: CommandType);
+    fn on_candidate_event(&self, event: *const CandidateEvent);
 }
 
 #[implement(
@@ -153,6 +158,27 @@ impl IFnRunCommand_Impl for TextService_Impl {
             error!("Unable to sync lang mode: {error:#}");
         }
     }
+
+    unsafe fn on_candidate_event(&self, event: *const CandidateEvent) {
+        let Some(event) = (unsafe { event.as_ref() }) else {
+            return;
+        };
+        {
+            // Posted messages can be dispatched inside a modal loop such as
+            // the popup menu, so never assume the text service is free.
+            let Ok(mut borrowed_ts) = self.inner.try_borrow_mut() else {
+                debug!("text service is busy - drop candidate event");
+                return;
+            };
+            let Some(ts) = borrowed_ts.as_mut() else {
+                return;
+            };
+            if let Err(error) = ts.on_candidate_event(*event) {
+                error!("Unable to handle candidate event: {error:#}");
+            }
+        }
+        self.tail_handler();
+    }
 }
 
 impl ITfTextInputProcessor_Impl for TextService_Impl {
@@ -0,0 +1,90 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2026 Kan-Ru Chen
+
+use std::time::{Duration, Instant};
+
+use chewing_tip_core::script::ScriptVariant;
+
+/// The same indicator is not shown again within this interval.
+const THROTTLE_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Label of the input mode, e.g. "中", "英全" or "中簡".
+pub(crate) fn mode_indicator_text(
+    chinese: bool,
+    fullwidth: bool,
+    variant: ScriptVariant,
+) -> String {
+    let mut text = String::from(if chinese { "中" } else { "英" });
+    if fullwidth {
+        text.push('全');
+    }
+    // The output variant only applies to Chinese
+    if chinese {
+        text.push_str(variant.indicator());
+    }
+    text
+}
+
+/// Keeps the mode indicator from flashing when the focus moves around
+/// without the mode changing.
+#[derive(Debug, Default)]
+pub(crate) struct ModeIndicatorThrottle {
+    last_shown: Option<(Instant, String)>,
+}
+
+impl ModeIndicatorThrottle {
+    /// Returns whether `text` should be shown at `now` and records it if so.
+    pub(crate) fn should_show(&mut self, now: Instant, text: &str) -> bool {
+        if let Some((shown_at, last)) = &self.last_shown
+            && last == text
+            && now.saturating_duration_since(*shown_at) < THROTTLE_INTERVAL
+        {
+            return false;
+        }
+        self.last_shown = Some((now, text.to_string()));
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use chewing_tip_core::script::ScriptVariant;
+
+    use super::{ModeIndicatorThrottle, mode_indicator_text};
+
+    #[test]
+    fn indicator_text() {
+        let traditional = ScriptVariant::Traditional;
+        let simplified = ScriptVariant::Simplified;
+        assert_eq!("中", mode_indicator_text(true, false, traditional));
+        assert_eq!("英", mode_indicator_text(false, false, traditional));
+        assert_eq!("中全簡", mode_indicator_text(true, true, simplified));
+        assert_eq!("英全", mode_indicator_text(false, true, simplified));
+        assert_eq!(
+            "中港",
+            mode_indicator_text(true, false, ScriptVariant::HongKong)
+        );
+    }
+
+    #[test]
+    fn same_mode_is_throttled() {
+        let start = Instant::now();
+        let mut throttle = ModeIndicatorThrottle::default();
+        assert!(throttle.should_show(start, "中"));
+        assert!(!throttle.should_show(start + Duration::from_secs(1), "中"));
+        // Throttled calls do not extend the interval
+        assert!(throttle.should_show(start + Duration::from_secs(3), "中"));
+    }
+
+    #[test]
+    fn mode_change_is_shown_immediately() {
+        let start = Instant::now();
+        let mut throttle = ModeIndicatorThrottle::default();
+        assert!(throttle.should_show(start, "中"));
+        assert!(throttle.should_show(start, "英"));
+        assert!(throttle.should_show(start, "中"));
+        assert!(!throttle.should_show(start, "中"));
+    }
+}
@@ -29,6 +29,8 @@ pub(super) const ID_CONFIG: u32 = 40017;
 pub(super) const ID_OUTPUT_SIMP_CHINESE: u32 = 40027;
 pub(super) const ID_ABOUT: u32 = 40005;
 pub(super) const ID_CHECK_NEW_VER: u32 = 40006;
+pub(super) const ID_SKIP_UPDATE: u32 = 40028;
+pub(super) const ID_SNOOZE_UPDATE: u32 = 40029;
 pub(super) const ID_WEBSITE: u32 = 40007;
 pub(super) const ID_GROUP: u32 = 40008;
 pub(super) const ID_BUGREPORT: u32 = 40009;
@@ -1,8 +1,21 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 // Copyright (c) 2026 Kan-Ru Chen
 
+use std::{ffi::c_void, sync::Mutex};
+
+use chewing_tip_core::theme::BuiltinTheme;
+use windows::Win32::UI::{
+    Accessibility::{HCF_HIGHCONTRASTON, HIGHCONTRASTW},
+    WindowsAndMessaging::{
+        SPI_GETHIGHCONTRAST, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS, SystemParametersInfoW,
+    },
+};
 use windows_registry::CURRENT_USER;
 
+/// Built-in theme of the auto theme choice, detected again only after the
+/// system appearance changes
+static AUTO_THEME: Mutex<Option<BuiltinTheme>> = Mutex::new(None);
+
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) enum WindowsTheme {
     Light,
@@ -44,6 +57,49 @@ impl ThemeDetector {
             .ok()
     }
 
+    /// Whether a Windows high contrast theme is active
+    pub(crate) fn is_high_contrast() -> bool {
+        let mut high_contrast = HIGHCONTRASTW {
+            cbSize: size_of::<HIGHCONTRASTW>() as u32,
+            ..Default::default()
+        };
+        let found = unsafe {
+            SystemParametersInfoW(
+                SPI_GETHIGHCONTRAST,
+                high_contrast.cbSize,
+                Some(&mut high_contrast as *mut HIGHCONTRASTW as *mut c_void),
+                SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+            )
+        };
+        found.is_ok() && high_contrast.dwFlags.0 & HCF_HIGHCONTRASTON.0 != 0
+    }
+
+    /// Built-in theme matching the current Windows appearance
+    pub(crate) fn builtin_theme() -> BuiltinTheme {
+        match AUTO_THEME.lock() {
+            Ok(mut auto_theme) => *auto_theme.get_or_insert_with(Self::detect_builtin_theme),
+            Err(_) => Self::detect_builtin_theme(),
+        }
+    }
+
+    /// Forgets the detected built-in theme, on `WM_SETTINGCHANGE` or
+    /// `WM_THEMECHANGED`.
+    pub(crate) fn appearance_changed() {
+        if let Ok(mut auto_theme) = AUTO_THEME.lock() {
+            *auto_theme = None;
+        }
+    }
+
+    fn detect_builtin_theme() -> BuiltinTheme {
+        if Self::is_high_contrast() {
+            return BuiltinTheme::HighContrast;
+        }
+        match Self::detect_theme() {
+            WindowsTheme::Dark => BuiltinTheme::Dark,
+            WindowsTheme::Light | WindowsTheme::Unknown => BuiltinTheme::Light,
+        }
+    }
+
     /// Get detailed theme information
     pub(crate) fn get_theme_info() -> ThemeInfo {
         ThemeInfo {
@@ -118,7 +118,7 @@ impl CandidateList {
                     _ => res = FilterKeyResult::NotHandled,
                 }
             } else {
-                let cand_per_row = model.cand_per_row as usize;
+                let cand_per_row = model.columns();
                 match ksym {
                     SYM_UP => {
                         if model.current_sel >= cand_per_row {
@@ -162,10 +162,6 @@ impl CandidateList {
     pub(crate) fn current_sel(&self) -> usize {
         self.model.borrow().current_sel
     }
-    pub(crate) fn current_phrase(&self) -> String {
-        let sel = self.current_sel();
-        self.model.borrow().items[sel].clone()
-    }
     pub(crate) fn show(&self) -> Result<(), error_plus::Error> {
         expect_error("Failed to show candidate window", || {
             self.cth_client.send(MethodCall {
@@ -0,0 +1,40 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (c) 2026 Kan-Ru Chen
+
+use anyhow::Result;
+use chewing_tip_core::ipc::{
+    client::ChewingIpcClient,
+    messages::{HideComposition, ShowComposition},
+    varlink::MethodCall,
+};
+
+/// Preedit rendered by chewing_tip_host instead of inline in the document.
+pub(crate) struct CompositionWindow {
+    cth_client: ChewingIpcClient,
+}
+
+impl CompositionWindow {
+    pub(crate) fn new(cth_client: ChewingIpcClient) -> CompositionWindow {
+        CompositionWindow { cth_client }
+    }
+    pub(crate) fn show(&self, model: &ShowComposition) -> Result<()> {
+        self.cth_client.send(MethodCall {
+            method: ShowComposition::METHOD.to_string(),
+            parameters: serde_json::to_value(model)?,
+            oneway: Some(true),
+            more: None,
+            upgrade: None,
+        })?;
+        Ok(())
+    }
+    pub(crate) fn hide(&self) -> Result<()> {
+        self.cth_client.send(MethodCall {
+            method: HideComposition::METHOD.to_string(),
+            parameters: serde_json::to_value(HideComposition)?,
+            oneway: Some(true),
+            more: None,
+            upgrade: None,
+        })?;
+        Ok(())
+    }
+}
@@ -2,7 +2,9 @@
 // Copyright (c) 2026 Kan-Ru Chen
 
 mod candidate_list;
+mod composition_window;
 mod notification;
 
 pub(super) use candidate_list::{CandidateList, FilterKeyResult};
+pub(super) use composition_window::CompositionWindow;
 pub(super) use notification::Notification;